@@ -1,8 +1,11 @@
 use clap::{Parser, ValueEnum};
-use parser::{Camt053Data, CsvData, Mt940Data, ParseError, Statement};
+use parser::{
+    DiffOptions, ParseError, Statement, StatementDiff, detect_format, diff_statements_with,
+    open_maybe_gzip, parse_statement_with_warnings,
+};
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, Read, Seek};
 use std::path::PathBuf;
 use std::process;
 
@@ -14,21 +17,35 @@ use std::process;
     long_about = None,
 )]
 struct Args {
-    /// Входной файл 1
+    /// Входной файл 1 (сжатый gzip распаковывается автоматически)
     #[arg(long)]
     file1: PathBuf,
 
-    /// Формат входного файла 1
+    /// Формат входного файла 1. Если не указан, определяется по содержимому
     #[arg(long, value_enum)]
-    format1: Format,
+    format1: Option<Format>,
 
-    /// Входной файл 2
+    /// Входной файл 2 (сжатый gzip распаковывается автоматически)
     #[arg(long)]
     file2: PathBuf,
 
-    /// Формат входного файла 2
+    /// Формат входного файла 2. Если не указан, определяется по содержимому
     #[arg(long, value_enum)]
-    format2: Format,
+    format2: Option<Format>,
+
+    /// Вместо построчного вывода расхождений напечатать сводку по полям
+    /// (только для `--format text`)
+    #[arg(long)]
+    summary: bool,
+
+    /// Формат вывода расхождений
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Допуск на расхождение сумм в минимальных единицах валюты ("копейках"):
+    /// суммы, отличающиеся не больше чем на N, считаются равными
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    amount_tolerance: u64,
 }
 
 /// Поддерживаемые форматы для CLI
@@ -39,28 +56,52 @@ enum Format {
     Mt940,
 }
 
+/// Формат вывода результата сравнения
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Построчное описание расхождений
+    Text,
+    /// `StatementDiff` одной строкой JSON
+    Json,
+    /// Списки транзакций в стиле unified diff
+    Unified,
+}
+
+impl From<Format> for parser::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Csv => parser::Format::Csv,
+            Format::Camt053 => parser::Format::Camt053,
+            Format::Mt940 => parser::Format::Mt940,
+        }
+    }
+}
+
 fn main() {
     if let Err(err) = run() {
         eprintln!("Error: {err}");
+        if let Some(excerpt) = err.excerpt() {
+            eprintln!("{excerpt}");
+        }
         process::exit(1);
     }
 }
 
-fn parse_to_statement<R: Read>(input_format: &Format, reader: R) -> Result<Statement, ParseError> {
-    // парсинг в общую структуру
-    match input_format {
-        Format::Csv => {
-            let data = CsvData::parse(reader)?;
-            Statement::try_from(data)
-        }
-        Format::Camt053 => {
-            let data = Camt053Data::parse(reader)?;
-            Statement::try_from(data)
-        }
-        Format::Mt940 => {
-            let data = Mt940Data::parse(reader)?;
-            Statement::try_from(data)
-        }
+/// Возвращает явно указанный формат или пытается определить его по содержимому
+fn resolve_format<R: Read + Seek>(
+    format: Option<Format>,
+    reader: &mut R,
+    flag: &str,
+) -> Result<parser::Format, ParseError> {
+    match format {
+        Some(format) => Ok(format.into()),
+        None => detect_format(reader)
+            .map(parser::Format::from)
+            .ok_or_else(|| {
+                ParseError::BadInput(format!(
+                    "failed to detect input format, please pass --{flag}"
+                ))
+            }),
     }
 }
 
@@ -73,55 +114,91 @@ where
     println!("  file2: {b}");
 }
 
-fn compare_transactions(a: &Statement, b: &Statement) -> bool {
-    let mut eq = true;
-
-    let len_a = a.transactions.len();
-    let len_b = b.transactions.len();
-    let max_len = len_a.max(len_b);
-
-    for i in 0..max_len {
-        let tx_a = a.transactions.get(i);
-        let tx_b = b.transactions.get(i);
-
-        match (tx_a, tx_b) {
-            (Some(ta), Some(tb)) => {
-                if ta != tb {
-                    print_diff("transaction", ta, tb);
-                    eq = false;
-                }
-            }
-            (Some(ta), None) => {
-                println!("Лишняя транзакция в file1 на позиции {i}: {ta}",);
-                eq = false;
-            }
-            (None, Some(tb)) => {
-                println!("Лишняя транзакция в file2 на позиции {i}: {tb}");
-                eq = false;
-            }
-            (None, None) => unreachable!("И там, и там None при i < max_len"),
-        }
+fn print_header_diff(diff: &StatementDiff) {
+    if let Some((acc_a, acc_b)) = &diff.account_id {
+        print_diff("account id", acc_a.as_str(), acc_b.as_str());
+    }
+    if let Some((ccy_a, ccy_b)) = &diff.currency {
+        print_diff("currency", ccy_a, ccy_b);
     }
-    eq
 }
 
-fn compare_statements(a: &Statement, b: &Statement) {
-    let mut eq = true;
-    if a.account_id != b.account_id {
-        eq = false;
-        print_diff("account id", a.account_id.as_str(), b.account_id.as_str());
-    }
+fn compare_statements(a: &Statement, b: &Statement, options: &DiffOptions) {
+    let diff = diff_statements_with(a, b, options);
 
-    eq = compare_transactions(a, b) && eq;
+    print_header_diff(&diff);
+    for mismatch in &diff.mismatched {
+        let i = mismatch.index;
+        print_diff("transaction", &a.transactions[i], &b.transactions[i]);
+    }
+    for &i in &diff.only_in_first {
+        println!(
+            "Лишняя транзакция в file1 на позиции {i}: {}",
+            a.transactions[i]
+        );
+    }
+    for &i in &diff.only_in_second {
+        println!(
+            "Лишняя транзакция в file2 на позиции {i}: {}",
+            b.transactions[i]
+        );
+    }
 
-    if eq {
+    if diff.is_empty() {
         println!("statements are equal")
     }
 }
 
+/// Обе выписки в стиле unified diff: ` ` - совпавшая транзакция,
+/// `-` - только в file1 (или её версия из file1), `+` - в file2
+fn print_unified(a: &Statement, b: &Statement, options: &DiffOptions) {
+    let diff = diff_statements_with(a, b, options);
+
+    println!("--- file1");
+    println!("+++ file2");
+    if let Some((acc_a, acc_b)) = &diff.account_id {
+        println!("-account id: {acc_a}");
+        println!("+account id: {acc_b}");
+    }
+    if let Some((ccy_a, ccy_b)) = &diff.currency {
+        println!("-currency: {ccy_a}");
+        println!("+currency: {ccy_b}");
+    }
+
+    let mut mismatched = diff.mismatched.iter().map(|m| m.index).peekable();
+    for (i, (ta, tb)) in a.transactions.iter().zip(&b.transactions).enumerate() {
+        if mismatched.next_if_eq(&i).is_some() {
+            println!("-{ta}");
+            println!("+{tb}");
+        } else {
+            println!(" {ta}");
+        }
+    }
+    for &i in &diff.only_in_first {
+        println!("-{}", a.transactions[i]);
+    }
+    for &i in &diff.only_in_second {
+        println!("+{}", b.transactions[i]);
+    }
+}
+
+/// Сводка: расхождения шапки и счётчики по полям транзакций
+fn print_summary(a: &Statement, b: &Statement, options: &DiffOptions) {
+    let diff = diff_statements_with(a, b, options);
+
+    print_header_diff(&diff);
+    println!("{}", diff.summary());
+}
+
 fn run() -> Result<(), ParseError> {
     let args = Args::parse();
 
+    if args.summary && args.format != OutputFormat::Text {
+        return Err(ParseError::BadInput(
+            "--summary can only be used with --format text".into(),
+        ));
+    }
+
     if !args.file1.exists() {
         eprintln!("input file 1 does not exist: {}", args.file1.display());
         process::exit(1)
@@ -148,13 +225,42 @@ fn run() -> Result<(), ParseError> {
         process::exit(1);
     });
 
-    let reader1 = io::BufReader::new(file1);
-    let reader2 = io::BufReader::new(file2);
+    let mut reader1 = open_maybe_gzip(io::BufReader::new(file1))?;
+    let mut reader2 = open_maybe_gzip(io::BufReader::new(file2))?;
+
+    let format1 = resolve_format(args.format1, &mut reader1, "format1")?;
+    let format2 = resolve_format(args.format2, &mut reader2, "format2")?;
 
-    let statement1 = parse_to_statement(&args.format1, reader1)?;
-    let statement2 = parse_to_statement(&args.format2, reader2)?;
+    let (statement1, warnings1) = parse_statement_with_warnings(reader1, format1)?;
+    let (statement2, warnings2) = parse_statement_with_warnings(reader2, format2)?;
+    for warning in warnings1 {
+        eprintln!("warning (file1): {warning}");
+    }
+    for warning in warnings2 {
+        eprintln!("warning (file2): {warning}");
+    }
+
+    let options = DiffOptions {
+        amount_tolerance: args.amount_tolerance,
+    };
 
-    compare_statements(&statement1, &statement2);
+    if args.format == OutputFormat::Json {
+        println!(
+            "{}",
+            diff_statements_with(&statement1, &statement2, &options).to_json()
+        );
+    } else if args.format == OutputFormat::Unified {
+        print_unified(&statement1, &statement2, &options);
+    } else if args.summary {
+        print_summary(&statement1, &statement2, &options);
+    } else if statement1.content_hash() == statement2.content_hash()
+        && diff_statements_with(&statement1, &statement2, &options).is_empty()
+    {
+        // хэш покрывает не все поля, поэтому "равны" - только если и diff пуст
+        println!("statements are equal");
+    } else {
+        compare_statements(&statement1, &statement2, &options);
+    }
 
     Ok(())
 }
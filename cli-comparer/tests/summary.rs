@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn compare(file1: &str, file2: &str, extra_args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-comparer"))
+        .arg("--file1")
+        .arg(fixture_path(file1))
+        .arg("--file2")
+        .arg(fixture_path(file2))
+        .args(extra_args)
+        .output()
+        .expect("failed to run cli-comparer");
+
+    assert!(
+        output.status.success(),
+        "cli-comparer failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).expect("cli-comparer output is not UTF-8")
+}
+
+#[test]
+fn summary_reports_field_tallies() {
+    let stdout = compare("base.mt940", "changed.mt940", &["--summary"]);
+
+    assert_eq!(
+        stdout.trim(),
+        "matched 2/4, date mismatches: 1, amount mismatches: 1"
+    );
+}
+
+#[test]
+fn summary_of_identical_files_matches_everything() {
+    let stdout = compare("base.mt940", "base.mt940", &["--summary"]);
+
+    assert_eq!(stdout.trim(), "matched 4/4");
+}
+
+#[test]
+fn identical_files_are_reported_equal() {
+    let stdout = compare("base.mt940", "base.mt940", &[]);
+
+    assert_eq!(stdout.trim(), "statements are equal");
+}
+
+#[test]
+fn value_date_difference_is_not_reported_equal() {
+    let stdout = compare("base.mt940", "value_date.mt940", &[]);
+
+    assert!(
+        stdout.starts_with("Несовпадение transaction"),
+        "unexpected output: {stdout}"
+    );
+    assert!(stdout.contains("2023-01-16"), "unexpected output: {stdout}");
+}
+
+#[test]
+fn amount_tolerance_hides_one_minor_unit_difference() {
+    let stdout = compare("base.mt940", "rounded.mt940", &[]);
+    assert!(
+        stdout.starts_with("Несовпадение transaction"),
+        "unexpected output: {stdout}"
+    );
+
+    let stdout = compare("base.mt940", "rounded.mt940", &["--amount-tolerance", "1"]);
+    assert_eq!(stdout.trim(), "statements are equal");
+
+    let stdout = compare(
+        "base.mt940",
+        "rounded.mt940",
+        &["--summary", "--amount-tolerance", "1"],
+    );
+    assert_eq!(stdout.trim(), "matched 4/4");
+}
+
+#[test]
+fn json_format_prints_statement_diff() {
+    let stdout = compare("base.mt940", "changed.mt940", &["--format", "json"]);
+
+    assert_eq!(
+        stdout.trim(),
+        r#"{"account_id":null,"currency":null,"matched":2,"mismatched":[{"index":1,"fields":["amount"]},{"index":2,"fields":["booking_date"]}],"only_in_first":[],"only_in_second":[]}"#
+    );
+}
+
+#[test]
+fn unified_format_marks_changed_transactions() {
+    let stdout = compare("base.mt940", "changed.mt940", &["--format", "unified"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(lines[..2], ["--- file1", "+++ file2"]);
+    let markers: String = lines[2..].iter().map(|l| &l[..1]).collect();
+    assert_eq!(markers, " -+-+ ");
+}
@@ -1,5 +1,8 @@
 use clap::{Parser, ValueEnum};
-use parser::{Camt053Data, CsvData, Mt940Data, ParseError, Statement};
+use parser::{
+    ConvertOptions, CsvWriteOptions, ParseError, SimpleCsvWriteOptions, Statement, detect_format,
+    open_maybe_gzip, parse_statement_with_warnings, write_statement,
+};
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::PathBuf;
@@ -13,21 +16,51 @@ use std::process;
     long_about = None,
 )]
 struct Args {
-    /// Входной файл
+    /// Входной файл (сжатый gzip распаковывается автоматически)
     #[arg(long)]
     input: PathBuf,
 
-    /// Формат входного файла
+    /// Формат входного файла. Если не указан, определяется по содержимому
     #[arg(long, value_enum)]
-    input_format: Format,
+    input_format: Option<Format>,
 
     /// Формат выходного файла
-    #[arg(long, value_enum)]
-    output_format: Format,
+    #[arg(long, value_enum, required_unless_present = "show")]
+    output_format: Option<Format>,
+
+    /// Вместо записи в выходной формат напечатать выписку в читаемом виде
+    #[arg(long, conflicts_with = "output_format")]
+    show: bool,
 
     /// Если указан, вывод будет записан в указанный файл вместо stdout
     #[arg(long)]
     to_file: Option<PathBuf>,
+
+    /// Разделитель полей для CSV-вывода (например, ';')
+    #[arg(long, default_value_t = ',')]
+    csv_delimiter: char,
+
+    /// Отсортировать транзакции по дате проводки перед записью
+    #[arg(long)]
+    sort: bool,
+
+    /// Удалить дублирующиеся транзакции перед записью (после сортировки)
+    #[arg(long)]
+    dedup: bool,
+
+    /// Пропустить первые N транзакций (после сортировки и удаления дублей).
+    /// Балансы пересчитываются под оставшиеся транзакции
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+
+    /// Записать не больше N транзакций (после --offset)
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Проверить выписку после разбора и ничего не записывать, если остатки
+    /// не сходятся с оборотом или даты операций выходят за период
+    #[arg(long)]
+    validate: bool,
 }
 
 /// Поддерживаемые форматы для CLI
@@ -36,24 +69,47 @@ enum Format {
     Csv,
     Camt053,
     Mt940,
+    /// Плоский CSV: дата, сумма, контрагент, описание (без счёта и остатков)
+    SimpleCsv,
+}
+
+impl From<Format> for parser::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Csv => parser::Format::Csv,
+            Format::Camt053 => parser::Format::Camt053,
+            Format::Mt940 => parser::Format::Mt940,
+            Format::SimpleCsv => parser::Format::SimpleCsv,
+        }
+    }
 }
 
 fn main() {
     if let Err(err) = run() {
         eprintln!("Error: {err}");
+        if let Some(excerpt) = err.excerpt() {
+            eprintln!("{excerpt}");
+        }
         process::exit(1);
     }
 }
 
+/// Пишет выписку в выходной формат, а без формата (`--show`) - в читаемом виде
 fn write_output<W: Write>(
     statement: &Statement,
-    output_format: Format,
-    writer: W,
+    mut writer: W,
+    output_format: Option<Format>,
+    options: &ConvertOptions,
 ) -> Result<(), ParseError> {
-    match output_format {
-        Format::Csv => statement.write_csv(writer)?,
-        Format::Camt053 => statement.write_camt053(writer)?,
-        Format::Mt940 => statement.write_mt940(writer)?,
+    let Some(output_format) = output_format else {
+        write!(writer, "{statement}")?;
+        writer.flush()?;
+        return Ok(());
+    };
+
+    let warnings = write_statement(statement, writer, output_format.into(), options)?;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
     }
 
     Ok(())
@@ -72,24 +128,60 @@ fn run() -> Result<(), ParseError> {
         process::exit(1);
     });
 
-    let reader = io::BufReader::new(file);
+    if !args.csv_delimiter.is_ascii() {
+        return Err(ParseError::BadInput(format!(
+            "csv delimiter must be a single ASCII character, got '{}'",
+            args.csv_delimiter
+        )));
+    }
+    let options = ConvertOptions {
+        csv: CsvWriteOptions {
+            delimiter: args.csv_delimiter as u8,
+            ..Default::default()
+        },
+        simple_csv: SimpleCsvWriteOptions {
+            delimiter: args.csv_delimiter as u8,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
 
-    // парсинг в общую структуру
-    let statement: Statement = match args.input_format {
-        Format::Csv => {
-            let data = CsvData::parse(reader)?;
-            Statement::try_from(data)?
-        }
-        Format::Camt053 => {
-            let data = Camt053Data::parse(reader)?;
-            Statement::try_from(data)?
-        }
-        Format::Mt940 => {
-            let data = Mt940Data::parse(reader)?;
-            Statement::try_from(data)?
-        }
+    let mut reader = open_maybe_gzip(io::BufReader::new(file))?;
+
+    let input_format = match args.input_format {
+        Some(format) => format.into(),
+        None => detect_format(&mut reader)
+            .map(parser::Format::from)
+            .ok_or_else(|| {
+                ParseError::BadInput(
+                    "failed to detect input format, please pass --input-format".into(),
+                )
+            })?,
     };
 
+    let (mut statement, warnings) = parse_statement_with_warnings(reader, input_format)?;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    if args.validate {
+        statement.validate()?;
+    }
+
+    // сначала сортировка, потом удаление дублей
+    if args.sort {
+        statement.sort_transactions_by_date();
+    }
+    if args.dedup {
+        let removed = statement.dedup();
+        if removed > 0 {
+            eprintln!("removed {removed} duplicate transaction(s)");
+        }
+    }
+    if args.offset > 0 || args.limit.is_some() {
+        statement.slice_transactions(args.offset, args.limit);
+    }
+
     match args.to_file {
         // в файл
         Some(path) => {
@@ -99,13 +191,13 @@ fn run() -> Result<(), ParseError> {
             });
 
             let writer = io::BufWriter::new(output_file);
-            write_output(&statement, args.output_format, writer)?;
+            write_output(&statement, writer, args.output_format, &options)?;
         }
         // в терминал
         None => {
             let stdout = io::stdout();
             let handle = stdout.lock();
-            write_output(&statement, args.output_format, handle)?;
+            write_output(&statement, handle, args.output_format, &options)?;
         }
     }
 
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn parse_error_prints_line_and_excerpt() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("bad_amount.mt940"))
+        .args(["--input-format", "mt940", "--output-format", "csv"])
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: line 6: invalid amount"), "{stderr}");
+    assert!(
+        stderr.contains("> 6 | :61:2301020102C50,555NTRFREF//BANK"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,38 @@
+use parser::{Mt940Data, Statement};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn convert(fixture: &str) -> Statement {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path(fixture))
+        .args(["--output-format", "mt940"])
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Mt940Data::parse(output.stdout.as_slice())
+        .expect("failed to parse converter output")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement")
+}
+
+#[test]
+fn gzip_input_is_decompressed_and_detected() {
+    assert_eq!(
+        convert("unsorted_duplicates.mt940.gz"),
+        convert("unsorted_duplicates.mt940")
+    );
+}
@@ -0,0 +1,51 @@
+use parser::{Mt940Data, Statement};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn convert_to_statement(extra_args: &[&str]) -> Statement {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .args(["--input-format", "mt940", "--output-format", "mt940"])
+        .args(extra_args)
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Mt940Data::parse(output.stdout.as_slice())
+        .expect("failed to parse converter output")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement")
+}
+
+#[test]
+fn offset_and_limit_write_only_requested_window() {
+    let stmt = convert_to_statement(&["--offset", "1", "--limit", "2"]);
+
+    let amounts: Vec<u64> = stmt.transactions.iter().map(|tx| tx.amount).collect();
+    assert_eq!(amounts, vec![10_000, 2_500]);
+
+    // открывающий баланс сдвинут на пропущенный дебет 50,00
+    assert_eq!(stmt.opening_balance, Some(95_000));
+}
+
+#[test]
+fn limit_alone_takes_first_transactions() {
+    let stmt = convert_to_statement(&["--limit", "1"]);
+
+    assert_eq!(stmt.transactions.len(), 1);
+    assert_eq!(stmt.transactions[0].amount, 5_000);
+    assert_eq!(stmt.opening_balance, Some(100_000));
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn show_prints_statement_summary() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .arg("--show")
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).expect("cli-converter output is not UTF-8");
+    let account_line = stdout.lines().next().expect("output must not be empty");
+    assert!(account_line.contains("DE89370400440532013000"), "{stdout}");
+    assert!(stdout.contains("Transactions:    4"), "{stdout}");
+}
+
+#[test]
+fn output_format_is_required_without_show() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(!output.status.success());
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn simple_csv_output_has_flat_columns() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .args(["--input-format", "mt940", "--output-format", "simple-csv"])
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).expect("output must be UTF-8");
+    let mut lines = stdout.lines();
+
+    assert_eq!(
+        lines.next(),
+        Some("booking_date,value_date,direction,amount,counterparty,counterparty_name,description")
+    );
+
+    let first: Vec<&str> = lines.next().expect("first row").split(',').collect();
+    assert_eq!(
+        &first[..5],
+        [
+            "2023-01-15",
+            "2023-01-15",
+            "debit",
+            "50.00",
+            "DE44500105175407324931"
+        ]
+    );
+
+    assert_eq!(lines.count(), 3);
+}
+
+#[test]
+fn simple_csv_is_accepted_as_input() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let flat = dir.join("simple_csv_input.csv");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .args(["--input-format", "mt940", "--output-format", "simple-csv"])
+        .arg("--to-file")
+        .arg(&flat)
+        .status()
+        .expect("failed to run cli-converter");
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(&flat)
+        .args([
+            "--input-format",
+            "simple-csv",
+            "--output-format",
+            "simple-csv",
+        ])
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        output.stdout,
+        std::fs::read(&flat).expect("failed to read simple CSV")
+    );
+}
@@ -0,0 +1,53 @@
+use parser::{Mt940Data, Statement};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn convert_to_statement(extra_args: &[&str]) -> Statement {
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path("unsorted_duplicates.mt940"))
+        .args(["--input-format", "mt940", "--output-format", "mt940"])
+        .args(extra_args)
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "cli-converter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Mt940Data::parse(output.stdout.as_slice())
+        .expect("failed to parse converter output")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement")
+}
+
+#[test]
+fn convert_without_flags_keeps_order_and_duplicates() {
+    let stmt = convert_to_statement(&[]);
+
+    assert_eq!(stmt.transactions.len(), 4);
+    assert_eq!(stmt.find_duplicates(), vec![3]);
+}
+
+#[test]
+fn convert_with_sort_and_dedup_orders_and_removes_duplicates() {
+    let stmt = convert_to_statement(&["--sort", "--dedup"]);
+
+    assert_eq!(stmt.transactions.len(), 3);
+    assert!(stmt.find_duplicates().is_empty());
+    assert!(
+        stmt.transactions
+            .windows(2)
+            .all(|w| w[0].booking_date <= w[1].booking_date),
+        "transactions must be sorted by booking date"
+    );
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn convert(fixture: &str, extra_args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(fixture_path(fixture))
+        .args(["--input-format", "mt940", "--output-format", "csv"])
+        .args(extra_args)
+        .output()
+        .expect("failed to run cli-converter")
+}
+
+#[test]
+fn validate_rejects_unbalanced_statement() {
+    let output = convert("unbalanced.mt940", &["--validate"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "nothing must be written");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unbalanced statement"), "{stderr}");
+}
+
+#[test]
+fn unbalanced_statement_is_written_without_validate() {
+    let output = convert("unbalanced.mt940", &[]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn validate_accepts_balanced_statement() {
+    let fixed = std::fs::read_to_string(fixture_path("unbalanced.mt940"))
+        .unwrap()
+        .replace(":62F:C230131EUR1100,00", ":62F:C230131EUR1075,00");
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("balanced.mt940");
+    std::fs::write(&path, fixed).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cli-converter"))
+        .arg("--input")
+        .arg(&path)
+        .args([
+            "--input-format",
+            "mt940",
+            "--output-format",
+            "csv",
+            "--validate",
+        ])
+        .output()
+        .expect("failed to run cli-converter");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!output.stdout.is_empty());
+}
@@ -1,9 +1,12 @@
 pub(crate) mod serde_models;
 mod utils;
 
-use crate::error::ParseError;
-use crate::model::{Direction, Statement, Transaction};
-use crate::utils::parse_amount;
+use crate::error::{ParseError, ParseWarning};
+use crate::model::{Direction, SourceFormat, Statement, Transaction};
+use crate::utils::{
+    MultipleStatementsPolicy, RoundingMode, RowErrors, collect_transactions, currency_for_iban,
+    ordered_period, parse_currency,
+};
 use quick_xml::de::from_str;
 use serde::{Deserialize, Serialize};
 use serde_models::*;
@@ -29,38 +32,188 @@ use utils::*;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Camt053Data {
     pub(crate) statement: Camt053Statement,
+    /// Политика округления, с которой будут разбираться суммы при конвертации
+    #[serde(skip)]
+    pub(crate) rounding: RoundingMode,
+    /// Менять ли местами границы перевёрнутого периода при конвертации
+    #[serde(skip)]
+    pub(crate) swap_inverted_period: bool,
+    /// Местный часовой пояс для дат с поясом, см. [`Camt053ParseOptions::utc_offset_minutes`]
+    #[serde(skip)]
+    pub(crate) utc_offset_minutes: Option<i32>,
+    /// Некритичные замечания, собранные при парсинге
+    #[serde(skip)]
+    pub warnings: Vec<ParseWarning>,
+    /// Следующие `<Stmt>` документа, если разбирали с [`MultipleStatementsPolicy::KeepAll`];
+    /// в остальных случаях пусто
+    #[serde(skip)]
+    pub rest: Vec<Camt053Data>,
+}
+
+/// Настройки парсинга CAMT.053
+#[derive(Debug, Clone, Default)]
+pub struct Camt053ParseOptions {
+    /// Что делать с суммами, у которых больше двух дробных знаков
+    pub rounding: RoundingMode,
+    /// Что делать, если в документе больше одного `<Stmt>`
+    pub on_multiple: MultipleStatementsPolicy,
+    /// Менять местами границы `<FrToDt>`, если начало позже конца;
+    /// по умолчанию такой период - [`ParseError::BadInput`]
+    pub swap_inverted_period: bool,
+    /// Если валюты нет ни у счёта, ни у сумм, угадать её по стране IBAN счёта
+    ///
+    /// Это догадка, поэтому добавляется [`ParseWarning::CurrencyInferred`].
+    /// Без опции такая выписка не преобразуется в [`Statement`].
+    pub infer_currency_from_iban: bool,
+    /// Смещение местного часового пояса от UTC в минутах (например, `180` для Москвы)
+    ///
+    /// Даты-время с поясом (`2023-04-20T23:59:59+02:00`, `...Z`) переводятся в
+    /// этот пояс перед тем, как взять дату. `None` (по умолчанию) - дата берётся
+    /// так, как записана. Даты без пояса не меняются.
+    pub utc_offset_minutes: Option<i32>,
 }
 
 impl Camt053Data {
+    /// Мягкое преобразование в [`Statement`]: проводки `Ntry` с ошибками пропускаются
+    ///
+    /// Возвращает выписку и список `(индекс проводки, ошибка)` для пропущенных.
+    /// Ошибки валюты и периода выписки по-прежнему фатальны
+    pub fn try_into_statement_lenient(
+        self,
+    ) -> Result<(Statement, Vec<(usize, ParseError)>), ParseError> {
+        statement_from_camt(
+            self.statement,
+            self.rounding,
+            self.swap_inverted_period,
+            self.utc_offset_minutes,
+            true,
+        )
+    }
+
     /// Парсит при помощи переданного reader данные  в [`Camt053Data`]
     ///
     /// При ошибке возвращает [`ParseError`]
     pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
-        let mut buf_reader = BufReader::new(reader);
-        let mut xml = String::new();
-        buf_reader.read_to_string(&mut xml)?;
-
-        // чистим неразрывные пробелы
-        let xml = xml.replace('\u{00A0}', " ");
+        Self::parse_with_options(reader, &Camt053ParseOptions::default())
+    }
 
-        // пытаемся читать как полноценный <Document>
-        if let Ok(doc) = from_str::<Camt053Document>(&xml) {
-            let mut stmt_iter = doc.bank_to_customer.statements.into_iter();
+    /// То же, что [`Camt053Data::parse`], но с дополнительными настройками
+    pub fn parse_with_options<R: Read>(
+        reader: R,
+        options: &Camt053ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let mut statements = Self::parse_all_with_options(reader, options)?.into_iter();
 
-            let stmt = stmt_iter
-                .next()
-                .ok_or_else(|| ParseError::BadInput("CAMT file has no <Stmt>".into()))?;
+        let mut data = statements
+            .next()
+            .ok_or_else(|| ParseError::BadInput("CAMT file has no <Stmt>".into()))?;
+        let rest: Vec<Self> = statements.collect();
 
-            if stmt_iter.next().is_some() {
-                eprintln!("more than one statement provided to camt053 parser. only reading first");
+        if !rest.is_empty() {
+            match options.on_multiple {
+                MultipleStatementsPolicy::KeepFirst => {
+                    data.warnings
+                        .push(ParseWarning::MultipleStatements { format: "camt053" });
+                }
+                MultipleStatementsPolicy::ErrorOut => {
+                    return Err(ParseError::MultipleStatements {
+                        format: "camt053",
+                        count: rest.len() + 1,
+                    });
+                }
+                MultipleStatementsPolicy::KeepAll => data.rest = rest,
             }
-
-            return Ok(Camt053Data { statement: stmt });
         }
 
-        // если не вышло - пробуем как <Stmt>
-        let stmt: Camt053Statement = from_str(&xml)?;
-        Ok(Camt053Data { statement: stmt })
+        Ok(data)
+    }
+
+    /// Все выписки по порядку: эта и [`Camt053Data::rest`]
+    pub fn into_all(mut self) -> Vec<Self> {
+        let rest = std::mem::take(&mut self.rest);
+        let mut all = Vec::with_capacity(rest.len() + 1);
+        all.push(self);
+        all.extend(rest);
+        all
+    }
+
+    /// Парсит все `<Stmt>` документа (например, по одной выписке на счёт)
+    ///
+    /// В отличие от [`Camt053Data::parse`], ни одна выписка не отбрасывается,
+    /// [`Camt053ParseOptions::on_multiple`] не учитывается.
+    /// Документ без `<Stmt>` даёт пустой список.
+    pub fn parse_all<R: Read>(reader: R) -> Result<Vec<Self>, ParseError> {
+        Self::parse_all_with_options(reader, &Camt053ParseOptions::default())
+    }
+
+    /// То же, что [`Camt053Data::parse_all`], но с дополнительными настройками
+    pub fn parse_all_with_options<R: Read>(
+        reader: R,
+        options: &Camt053ParseOptions,
+    ) -> Result<Vec<Self>, ParseError> {
+        Ok(read_statements(reader)?
+            .into_iter()
+            .map(|mut statement| {
+                let mut warnings = Vec::new();
+                if options.infer_currency_from_iban {
+                    infer_missing_currency(&mut statement, &mut warnings);
+                }
+                Camt053Data {
+                    statement,
+                    rounding: options.rounding,
+                    swap_inverted_period: options.swap_inverted_period,
+                    utc_offset_minutes: options.utc_offset_minutes,
+                    warnings,
+                    rest: Vec::new(),
+                }
+            })
+            .collect())
+    }
+
+    /// Парсит все `<Stmt>` документа и сразу преобразует их в [`Statement`]
+    pub fn statements_to_vec<R: Read>(reader: R) -> Result<Vec<Statement>, ParseError> {
+        Self::parse_all(reader)?
+            .into_iter()
+            .map(Statement::try_from)
+            .collect()
+    }
+}
+
+/// Читает все `<Stmt>`: из `<Document>` или из одиночного корневого `<Stmt>`
+fn read_statements<R: Read>(reader: R) -> Result<Vec<Camt053Statement>, ParseError> {
+    let mut buf_reader = BufReader::new(reader);
+    let mut xml = String::new();
+    buf_reader.read_to_string(&mut xml)?;
+
+    // чистим BOM и неразрывные пробелы
+    let xml = xml.strip_prefix('\u{feff}').unwrap_or(&xml);
+    let xml = xml.replace('\u{00A0}', " ");
+
+    // пытаемся читать как полноценный <Document>
+    if let Ok(doc) = from_str::<Camt053Document>(&xml) {
+        return Ok(doc.bank_to_customer.statements);
+    }
+
+    // если не вышло - пробуем как <Stmt>
+    let stmt: Camt053Statement = from_str(&xml)?;
+    Ok(vec![stmt])
+}
+
+/// Проставляет валюту счёта по стране IBAN, если валюты нет нигде в выписке
+fn infer_missing_currency(stmt: &mut Camt053Statement, warnings: &mut Vec<ParseWarning>) {
+    if detect_currency(stmt).is_ok() {
+        return;
+    }
+    let Some(iban) = stmt.account.id.iban.as_deref() else {
+        return;
+    };
+    if let Some(currency) = currency_for_iban(iban) {
+        stmt.account.currency = Some(currency.code().to_string());
+        warnings.push(ParseWarning::CurrencyInferred {
+            format: "camt053",
+            account: iban.to_string(),
+            currency,
+        });
     }
 }
 
@@ -68,44 +221,109 @@ impl TryFrom<&Camt053Entry> for Transaction {
     type Error = ParseError;
 
     fn try_from(entry: &Camt053Entry) -> Result<Self, Self::Error> {
-        // direction
-        let direction = match entry.cdt_dbt_ind.as_str() {
-            "CRDT" => Direction::Credit,
-            "DBIT" => Direction::Debit,
-            other => {
-                return Err(ParseError::InvalidAmount(format!(
-                    "unknown direction (CdtDbtInd): {other}"
-                )));
-            }
-        };
+        transaction_from_entry(entry, RoundingMode::default(), None)
+    }
+}
 
-        let amount = parse_amount(&entry.amount.value)?;
-        let booking_date = parse_camt_date_to_naive(&entry.booking_date.date)?;
-        let value_date = Some(parse_camt_date_to_naive(&entry.value_date.date)?);
+fn transaction_from_entry(
+    entry: &Camt053Entry,
+    rounding: RoundingMode,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Transaction, ParseError> {
+    let tx_dtls = entry.details.as_ref().and_then(|d| d.tx_details.first());
 
-        let tx_dtls = entry.details.as_ref().and_then(|d| d.tx_details.first());
+    // direction: CdtDbtInd из TxDtls, если он есть, иначе с уровня Ntry
+    let cdt_dbt_ind = tx_dtls
+        .and_then(|t| t.cdt_dbt_ind.as_deref())
+        .unwrap_or(&entry.cdt_dbt_ind);
+    let direction = match cdt_dbt_ind {
+        "" => return Err(ParseError::MissingField("CdtDbtInd")),
+        "CRDT" => Direction::Credit,
+        "DBIT" => Direction::Debit,
+        other => {
+            return Err(ParseError::InvalidAmount(format!(
+                "unknown direction (CdtDbtInd): {other}"
+            )));
+        }
+    };
 
-        let counterparty: Option<String>;
-        let counterparty_name: Option<String>;
-        let description: String;
+    if entry.amount.value.trim().is_empty() {
+        return Err(ParseError::MissingField("Amt"));
+    }
+    if entry.booking_date.date.trim().is_empty() {
+        return Err(ParseError::MissingField("BookgDt"));
+    }
 
-        if let Some(tx_details) = tx_dtls {
-            (counterparty, counterparty_name) = counterparty_from_tx(tx_details, direction);
-            description = description_from_tx(tx_details);
-        } else {
-            (counterparty, counterparty_name) = (None, None);
-            description = "".to_string();
-        }
+    let amount = parse_camt_amount(&entry.amount.value, rounding)?;
+    let booking_date = parse_camt_date_to_naive(&entry.booking_date.date, utc_offset_minutes)?;
+    // без ValDt дата валютирования совпадает с датой проводки, как и при записи
+    let value_date = match &entry.value_date {
+        Some(value_date) => parse_camt_date_to_naive(&value_date.date, utc_offset_minutes)?,
+        None => booking_date,
+    };
+    let value_date = Some(value_date);
 
-        Ok(Transaction::new(
-            booking_date,
-            value_date,
-            amount,
-            direction,
-            description,
-            counterparty,
-            counterparty_name,
-        ))
+    let counterparty: Option<String>;
+    let counterparty_name: Option<String>;
+    let description: String;
+
+    if let Some(tx_details) = tx_dtls {
+        (counterparty, counterparty_name) = counterparty_from_tx(tx_details, direction);
+        description = description_from_tx(tx_details);
+    } else {
+        (counterparty, counterparty_name) = (None, None);
+        description = "".to_string();
+    }
+
+    let mut tx = Transaction::new(
+        booking_date,
+        value_date,
+        amount,
+        direction,
+        description,
+        counterparty,
+        counterparty_name,
+    );
+    tx.counterparty_address =
+        tx_dtls.and_then(|tx_details| counterparty_address_from_tx(tx_details, direction));
+
+    tx.is_reversal = entry.reversal_indicator.unwrap_or(false);
+    tx.pending = entry.status.as_ref().and_then(|s| s.code()) == Some("PDNG");
+    tx.bank_reference = [&entry.account_servicer_reference, &entry.entry_reference]
+        .into_iter()
+        .flatten()
+        .map(|r| r.trim())
+        .find(|r| !r.is_empty())
+        .map(str::to_string);
+    tx.original_currency = Some(entry.amount.currency.trim())
+        .filter(|ccy| !ccy.is_empty())
+        .map(parse_currency);
+
+    if let Some(tx_details) = tx_dtls {
+        tx.reference = reference_from_tx(tx_details);
+        tx.purpose_code = purpose_code_from_tx(tx_details);
+        tx.fx = fx_from_tx(tx_details);
+        tx.charges = charges_from_tx(tx_details, rounding)?;
+    }
+
+    Ok(tx)
+}
+
+impl TryFrom<&str> for Camt053Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Camt053Data::parse`]
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input.as_bytes())
+    }
+}
+
+impl TryFrom<String> for Camt053Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Camt053Data::parse`]
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::try_from(input.as_str())
     }
 }
 
@@ -113,43 +331,94 @@ impl TryFrom<Camt053Data> for Statement {
     type Error = ParseError;
 
     fn try_from(data: Camt053Data) -> Result<Self, Self::Error> {
-        Statement::try_from(data.statement)
+        statement_from_camt(
+            data.statement,
+            data.rounding,
+            data.swap_inverted_period,
+            data.utc_offset_minutes,
+            false,
+        )
+        .map(|(statement, _)| statement)
     }
 }
 
 impl TryFrom<Camt053Statement> for Statement {
     type Error = ParseError;
     fn try_from(statement: Camt053Statement) -> Result<Self, Self::Error> {
-        let account_id = statement
-            .account
-            .id
-            .iban
-            .clone()
-            .unwrap_or_else(|| "not provided".to_string());
+        statement_from_camt(statement, RoundingMode::default(), false, None, false)
+            .map(|(statement, _)| statement)
+    }
+}
+
+fn statement_from_camt(
+    statement: Camt053Statement,
+    rounding: RoundingMode,
+    swap_inverted_period: bool,
+    utc_offset_minutes: Option<i32>,
+    lenient: bool,
+) -> Result<(Statement, RowErrors), ParseError> {
+    // без IBAN счёт бывает указан только в <Othr><Id>
+    let account_id = statement
+        .account
+        .id
+        .iban
+        .clone()
+        .or_else(|| {
+            statement
+                .account
+                .id
+                .other_id
+                .as_ref()
+                .and_then(|other| other.id.as_deref())
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "not provided".to_string());
 
-        let account_name = statement.account.name.clone();
+    let account_name = statement.account.name.clone();
 
-        let currency = detect_currency(&statement)?;
-        let (opening_balance, closing_balance) = extract_balances(&statement);
-        let (period_from, period_until) = detect_period(&statement)?;
+    let currency = detect_currency(&statement)?;
+    let balances = extract_balances(&statement, rounding)?;
+    let (period_from, period_until) = detect_period(&statement, utc_offset_minutes)?;
+    let (period_from, period_until) =
+        ordered_period(period_from, period_until, swap_inverted_period)?;
 
-        let transactions: Vec<Transaction> = statement
+    let (transactions, errors) = collect_transactions(
+        statement
             .entries
             .iter()
-            .map(|e| e.try_into())
-            .collect::<Result<_, ParseError>>()?;
+            .map(|e| transaction_from_entry(e, rounding, utc_offset_minutes)),
+        lenient,
+    )?;
 
-        Ok(Statement::new(
-            account_id,
-            account_name,
-            currency,
-            opening_balance,
-            closing_balance,
-            transactions,
-            period_from,
-            period_until,
-        ))
-    }
+    // валюту операции храним, только если она отличается от валюты выписки
+    let transactions = transactions
+        .into_iter()
+        .map(|mut tx| {
+            if tx.original_currency.as_ref() == Some(&currency) {
+                tx.original_currency = None;
+            }
+            tx
+        })
+        .collect();
+
+    let mut statement = Statement::new(
+        account_id,
+        account_name,
+        currency,
+        balances.opening,
+        balances.closing,
+        transactions,
+        period_from,
+        period_until,
+    );
+
+    statement.closing_available_balance = balances.closing_available;
+    statement.forward_available_balance = balances.forward_available;
+    statement.source_format = Some(SourceFormat::Camt053);
+
+    Ok((statement, errors))
 }
 
 #[cfg(test)]
@@ -209,6 +478,445 @@ mod tests {
         assert_eq!(stmt.account.currency.as_deref(), Some("EUR"));
     }
 
+    #[test]
+    fn parse_entry_amount_without_ccy_falls_back_to_balance_currency() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id>
+                  <IBAN>DE1234567890</IBAN>
+                </Id>
+              </Acct>
+              <Bal>
+                <Tp>
+                  <CdOrPrtry>
+                    <Cd>OPBD</Cd>
+                  </CdOrPrtry>
+                </Tp>
+                <Amt Ccy="EUR">100.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+              </Bal>
+              <Ntry>
+                <Amt>100.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt>
+                  <Dt>2023-01-05</Dt>
+                </BookgDt>
+                <ValDt>
+                  <Dt>2023-01-05</Dt>
+                </ValDt>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        assert_eq!(data.statement.entries[0].amount.currency, "");
+
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.currency, Currency::EUR);
+        assert_eq!(stmt.transactions.len(), 1);
+        assert_eq!(stmt.transactions[0].amount, 10_000);
+    }
+
+    #[test]
+    fn entry_in_foreign_currency_keeps_original_currency() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id>
+                  <IBAN>DE1234567890</IBAN>
+                </Id>
+                <Ccy>EUR</Ccy>
+              </Acct>
+              <Ntry>
+                <Amt Ccy="EUR">10.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+                <ValDt><Dt>2023-01-05</Dt></ValDt>
+              </Ntry>
+              <Ntry>
+                <Amt Ccy="USD">20.00</Amt>
+                <CdtDbtInd>DBIT</CdtDbtInd>
+                <BookgDt><Dt>2023-01-06</Dt></BookgDt>
+                <ValDt><Dt>2023-01-06</Dt></ValDt>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        assert_eq!(stmt.transactions[0].original_currency, None);
+        assert_eq!(stmt.transactions[1].original_currency, Some(Currency::USD));
+
+        let parts = stmt.split_by_currency();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].currency, Currency::EUR);
+        assert_eq!(parts[1].currency, Currency::USD);
+        assert_eq!(parts[1].transactions[0].amount, 2_000);
+    }
+
+    #[test]
+    fn entry_references_become_bank_reference() {
+        let mut entry = make_simple_entry("CRDT");
+        entry.entry_reference = Some("NTRY-1".to_string());
+        entry.account_servicer_reference = Some(" BANK-42 ".to_string());
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert_eq!(tx.bank_reference.as_deref(), Some("BANK-42"));
+
+        // без AcctSvcrRef берём NtryRef
+        entry.account_servicer_reference = None;
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert_eq!(tx.bank_reference.as_deref(), Some("NTRY-1"));
+    }
+
+    #[test]
+    fn parse_entry_with_acct_svcr_ref() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id><IBAN>DE1234567890</IBAN></Id>
+                <Ccy>EUR</Ccy>
+              </Acct>
+              <Ntry>
+                <NtryRef>1</NtryRef>
+                <Amt Ccy="EUR">10.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+                <ValDt><Dt>2023-01-05</Dt></ValDt>
+                <AcctSvcrRef>2023010500042</AcctSvcrRef>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        assert_eq!(
+            data.statement.entries[0].entry_reference.as_deref(),
+            Some("1")
+        );
+
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(
+            stmt.transactions[0].bank_reference.as_deref(),
+            Some("2023010500042")
+        );
+    }
+
+    #[test]
+    fn entry_without_val_dt_uses_booking_date() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id><IBAN>DE1234567890</IBAN></Id>
+                <Ccy>EUR</Ccy>
+              </Acct>
+              <Ntry>
+                <Amt Ccy="EUR">10.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        assert!(data.statement.entries[0].value_date.is_none());
+
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.transactions[0].booking_date, d(2023, 1, 5));
+        assert_eq!(stmt.transactions[0].value_date, Some(d(2023, 1, 5)));
+    }
+
+    #[test]
+    fn tx_details_cdt_dbt_ind_overrides_entry_direction() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id>
+                  <IBAN>DE1234567890</IBAN>
+                </Id>
+                <Ccy>EUR</Ccy>
+              </Acct>
+              <Ntry>
+                <Amt Ccy="EUR">25.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt>
+                  <Dt>2023-01-05</Dt>
+                </BookgDt>
+                <ValDt>
+                  <Dt>2023-01-05</Dt>
+                </ValDt>
+                <NtryDtls>
+                  <TxDtls>
+                    <CdtDbtInd>DBIT</CdtDbtInd>
+                    <RltdPties>
+                      <Cdtr>
+                        <Nm>Reversal Creditor</Nm>
+                      </Cdtr>
+                    </RltdPties>
+                  </TxDtls>
+                </NtryDtls>
+              </Ntry>
+              <Ntry>
+                <Amt Ccy="EUR">10.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt>
+                  <Dt>2023-01-06</Dt>
+                </BookgDt>
+                <ValDt>
+                  <Dt>2023-01-06</Dt>
+                </ValDt>
+                <NtryDtls>
+                  <TxDtls/>
+                </NtryDtls>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        assert_eq!(stmt.transactions[0].direction, Direction::Debit);
+        assert_eq!(
+            stmt.transactions[0].counterparty_name.as_deref(),
+            Some("Reversal Creditor")
+        );
+        // без CdtDbtInd в TxDtls берётся направление Ntry
+        assert_eq!(stmt.transactions[1].direction, Direction::Credit);
+    }
+
+    #[test]
+    fn entry_with_reversal_indicator_is_reversal() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id>
+              <IBAN>DE1234567890</IBAN>
+            </Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <Ntry>
+            <Amt Ccy="EUR">15.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <RvslInd>true</RvslInd>
+            <BookgDt>
+              <Dt>2023-01-05</Dt>
+            </BookgDt>
+            <ValDt>
+              <Dt>2023-01-05</Dt>
+            </ValDt>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        assert!(stmt.transactions[0].is_reversal);
+        assert_eq!(stmt.transactions[0].direction, Direction::Credit);
+
+        let xml_out = stmt.to_camt053_string().unwrap();
+        assert!(xml_out.contains("<RvslInd>true</RvslInd>"), "{xml_out}");
+    }
+
+    #[test]
+    fn entry_status_pdng_marks_transaction_pending() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id>
+              <IBAN>DE1234567890</IBAN>
+            </Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <Ntry>
+            <Amt Ccy="EUR">15.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <Sts>BOOK</Sts>
+            <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+            <ValDt><Dt>2023-01-05</Dt></ValDt>
+          </Ntry>
+          <Ntry>
+            <Amt Ccy="EUR">7.00</Amt>
+            <CdtDbtInd>DBIT</CdtDbtInd>
+            <Sts>PDNG</Sts>
+            <BookgDt><Dt>2023-01-06</Dt></BookgDt>
+            <ValDt><Dt>2023-01-06</Dt></ValDt>
+          </Ntry>
+          <Ntry>
+            <Amt Ccy="EUR">3.00</Amt>
+            <CdtDbtInd>DBIT</CdtDbtInd>
+            <Sts><Cd>PDNG</Cd></Sts>
+            <BookgDt><Dt>2023-01-07</Dt></BookgDt>
+            <ValDt><Dt>2023-01-07</Dt></ValDt>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let mut stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        let pending: Vec<bool> = stmt.transactions.iter().map(|tx| tx.pending).collect();
+        assert_eq!(pending, vec![false, true, true]);
+
+        let xml_out = stmt.to_camt053_string().unwrap();
+        assert!(xml_out.contains("<Sts>BOOK</Sts>"), "{xml_out}");
+        assert!(xml_out.contains("<Sts>PDNG</Sts>"), "{xml_out}");
+
+        assert_eq!(stmt.remove_pending(), 2);
+        assert_eq!(stmt.transactions.len(), 1);
+        assert_eq!(stmt.transactions[0].amount, 1_500);
+    }
+
+    #[test]
+    fn ustrd_entities_and_cdata_are_decoded() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id>
+              <IBAN>DE1234567890</IBAN>
+            </Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <Ntry>
+            <Amt Ccy="EUR">15.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+            <ValDt><Dt>2023-01-05</Dt></ValDt>
+            <NtryDtls><TxDtls><RmtInf>
+              <Ustrd>A &amp; B &lt;note&gt;</Ustrd>
+            </RmtInf></TxDtls></NtryDtls>
+          </Ntry>
+          <Ntry>
+            <Amt Ccy="EUR">7.00</Amt>
+            <CdtDbtInd>DBIT</CdtDbtInd>
+            <BookgDt><Dt>2023-01-06</Dt></BookgDt>
+            <ValDt><Dt>2023-01-06</Dt></ValDt>
+            <NtryDtls><TxDtls><RmtInf>
+              <Ustrd><![CDATA[C & D <raw>]]></Ustrd>
+            </RmtInf></TxDtls></NtryDtls>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        let descriptions: Vec<&str> = stmt
+            .transactions
+            .iter()
+            .map(|tx| tx.description.as_str())
+            .collect();
+        assert_eq!(descriptions, vec!["A & B <note>", "C & D <raw>"]);
+    }
+
+    #[test]
+    fn strd_creditor_reference_becomes_transaction_reference() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id>
+              <IBAN>DE1234567890</IBAN>
+            </Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <Ntry>
+            <Amt Ccy="EUR">15.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <BookgDt><Dt>2023-01-05</Dt></BookgDt>
+            <ValDt><Dt>2023-01-05</Dt></ValDt>
+            <NtryDtls><TxDtls>
+              <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+              <RmtInf>
+                <Ustrd>Invoice payment</Ustrd>
+                <Strd>
+                  <CdtrRefInf><Ref>INV-2023-001</Ref></CdtrRefInf>
+                  <AddtlRmtInf>second line</AddtlRmtInf>
+                </Strd>
+              </RmtInf>
+            </TxDtls></NtryDtls>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        let tx = &stmt.transactions[0];
+        assert_eq!(tx.reference.as_deref(), Some("INV-2023-001"));
+        assert_eq!(tx.description, "Invoice payment");
+    }
+
+    #[test]
+    fn statement_with_prcd_instead_of_opbd_gets_opening_and_available_balances() {
+        let xml = r#"
+        <Document>
+          <BkToCstmrStmt>
+            <Stmt>
+              <Acct>
+                <Id>
+                  <IBAN>DE1234567890</IBAN>
+                </Id>
+                <Ccy>EUR</Ccy>
+              </Acct>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>PRCD</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="EUR">100.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+              </Bal>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="EUR">150.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+              </Bal>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>CLAV</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="EUR">140.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+              </Bal>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>FWAV</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="EUR">5.00</Amt>
+                <CdtDbtInd>DBIT</CdtDbtInd>
+              </Bal>
+              <FrToDt>
+                <FrDtTm>2023-01-01T00:00:00</FrDtTm>
+                <ToDtTm>2023-01-31T00:00:00</ToDtTm>
+              </FrToDt>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).unwrap();
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        assert_eq!(stmt.opening_balance, Some(10_000));
+        assert_eq!(stmt.closing_balance, Some(15_000));
+        assert_eq!(stmt.closing_available_balance, Some(14_000));
+        assert_eq!(stmt.forward_available_balance, Some(-500));
+    }
+
     #[test]
     fn parse_root_stmt_without_document() {
         let xml = r#"
@@ -234,6 +942,17 @@ mod tests {
         assert_eq!(data.statement.account.currency.as_deref(), Some("USD"));
     }
 
+    #[test]
+    fn parse_document_with_utf8_bom() {
+        let xml = "\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <Document><BkToCstmrStmt><Stmt><Acct><Id><IBAN>DE0000000000</IBAN></Id>\
+            <Ccy>USD</Ccy></Acct></Stmt></BkToCstmrStmt></Document>";
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+
+        assert_eq!(data.statement.account.currency.as_deref(), Some("USD"));
+    }
+
     #[test]
     fn parse_document_without_stmt_returns_error() {
         let xml = r#"
@@ -256,21 +975,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn camt053_data_try_from_str_and_string() {
+        let xml = r#"<Stmt><Acct><Id><IBAN>DE0000000000</IBAN></Id><Ccy>EUR</Ccy></Acct></Stmt>"#;
+
+        let from_str = Camt053Data::try_from(xml).expect("parse must succeed");
+        assert_eq!(
+            from_str.statement.account.id.iban.as_deref(),
+            Some("DE0000000000")
+        );
+
+        let from_string = Camt053Data::try_from(xml.to_string()).expect("parse must succeed");
+        assert_eq!(
+            from_string.statement.account.currency.as_deref(),
+            Some("EUR")
+        );
+
+        assert!(Camt053Data::try_from("<Document></Document>").is_err());
+    }
+
     // TryFrom<&Camt053Entry> for Transaction
 
     fn make_simple_entry(cdt_dbt: &str) -> Camt053Entry {
         Camt053Entry {
+            entry_reference: None,
             amount: CamtAmtXml {
                 currency: "EUR".to_string(),
                 value: "123.45".to_string(),
             },
             cdt_dbt_ind: cdt_dbt.to_string(),
+            reversal_indicator: None,
+            status: None,
             booking_date: CamtDateXml {
                 date: "2023-01-10".to_string(),
             },
-            value_date: CamtDateXml {
+            value_date: Some(CamtDateXml {
                 date: "2023-01-11".to_string(),
-            },
+            }),
+            account_servicer_reference: None,
             details: None,
         }
     }
@@ -319,22 +1061,157 @@ mod tests {
         }
     }
 
+    #[test]
+    fn entry_without_required_elements_returns_missing_field() {
+        let mut entry = make_simple_entry("");
+        let err = Transaction::try_from(&entry).unwrap_err();
+        assert!(
+            matches!(err, ParseError::MissingField("CdtDbtInd")),
+            "{err:?}"
+        );
+
+        entry.cdt_dbt_ind = "CRDT".to_string();
+        entry.booking_date.date.clear();
+        let err = Transaction::try_from(&entry).unwrap_err();
+        assert!(
+            matches!(err, ParseError::MissingField("BookgDt")),
+            "{err:?}"
+        );
+
+        entry.amount = CamtAmtXml::default();
+        let err = Transaction::try_from(&entry).unwrap_err();
+        assert!(matches!(err, ParseError::MissingField("Amt")), "{err:?}");
+    }
+
+    #[test]
+    fn parse_entry_without_cdt_dbt_ind_reports_missing_field() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id><IBAN>DE0000000000</IBAN></Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <FrToDt>
+            <FrDtTm>2023-01-01T00:00:00</FrDtTm>
+            <ToDtTm>2023-01-31T23:59:59</ToDtTm>
+          </FrToDt>
+          <Ntry>
+            <Amt Ccy="EUR">10.00</Amt>
+            <BookgDt><Dt>2023-01-10</Dt></BookgDt>
+            <ValDt><Dt>2023-01-10</Dt></ValDt>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let err = Statement::try_from(data).unwrap_err();
+
+        assert!(
+            matches!(err, ParseError::MissingField("CdtDbtInd")),
+            "{err:?}"
+        );
+        assert_eq!(err.to_string(), "missing field: CdtDbtInd");
+    }
+
+    #[test]
+    fn datetimes_with_timezone_are_parsed_and_optionally_shifted() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id><IBAN>DE0000000000</IBAN></Id>
+            <Ccy>EUR</Ccy>
+          </Acct>
+          <FrToDt>
+            <FrDtTm>2023-01-01T00:00:00+02:00</FrDtTm>
+            <ToDtTm>2023-01-31T23:59:59Z</ToDtTm>
+          </FrToDt>
+          <Ntry>
+            <Amt Ccy="EUR">10.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <BookgDt><DtTm>2023-01-10T22:30:00Z</DtTm></BookgDt>
+            <ValDt><Dt>2023-01-10</Dt></ValDt>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.period_from, d(2023, 1, 1));
+        assert_eq!(stmt.period_until, d(2023, 1, 31));
+        assert_eq!(stmt.transactions[0].booking_date, d(2023, 1, 10));
+
+        // в Москве (UTC+3) те же моменты времени приходятся на другие даты
+        let options = Camt053ParseOptions {
+            utc_offset_minutes: Some(180),
+            ..Default::default()
+        };
+        let data = Camt053Data::parse_with_options(Cursor::new(xml.as_bytes()), &options)
+            .expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.period_from, d(2023, 1, 1));
+        assert_eq!(stmt.period_until, d(2023, 2, 1));
+        assert_eq!(stmt.transactions[0].booking_date, d(2023, 1, 11));
+        assert_eq!(stmt.transactions[0].value_date, Some(d(2023, 1, 10)));
+    }
+
+    #[test]
+    fn missing_currency_is_inferred_from_iban_only_on_request() {
+        let xml = r#"
+        <Stmt>
+          <Acct>
+            <Id><IBAN>DE89370400440532013000</IBAN></Id>
+          </Acct>
+          <Ntry>
+            <Amt>10.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <BookgDt><Dt>2023-01-10</Dt></BookgDt>
+          </Ntry>
+        </Stmt>
+        "#;
+
+        let data = Camt053Data::parse(Cursor::new(xml.as_bytes())).expect("parse must succeed");
+        assert!(data.warnings.is_empty());
+        let err = Statement::try_from(data).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidCurrency(_)), "{err:?}");
+
+        let options = Camt053ParseOptions {
+            infer_currency_from_iban: true,
+            ..Default::default()
+        };
+        let data = Camt053Data::parse_with_options(Cursor::new(xml.as_bytes()), &options)
+            .expect("parse must succeed");
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::CurrencyInferred {
+                format: "camt053",
+                account: "DE89370400440532013000".to_string(),
+                currency: Currency::EUR,
+            }]
+        );
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.currency, Currency::EUR);
+    }
+
     // TryFrom<Camt053Statement> / Camt053Data for Statement
 
     fn sample_camt_statement() -> Camt053Statement {
         // Один entry, чтобы была хотя бы 1 транзакция
         let entry = Camt053Entry {
+            entry_reference: None,
             amount: CamtAmtXml {
                 currency: "EUR".to_string(),
                 value: "10.00".to_string(),
             },
             cdt_dbt_ind: "CRDT".to_string(),
+            reversal_indicator: None,
+            status: None,
             booking_date: CamtDateXml {
                 date: "2023-01-05".to_string(),
             },
-            value_date: CamtDateXml {
+            value_date: Some(CamtDateXml {
                 date: "2023-01-06".to_string(),
-            },
+            }),
+            account_servicer_reference: None,
             details: None,
         };
 
@@ -346,6 +1223,7 @@ mod tests {
             account: Camt053Account {
                 id: Camt053AccountId {
                     iban: Some("DE1111222233334444".to_string()),
+                    other_id: None,
                 },
                 name: Some("Sample Account".to_string()),
                 currency: Some("EUR".to_string()),
@@ -379,6 +1257,11 @@ mod tests {
         let camt_stmt = sample_camt_statement();
         let data = Camt053Data {
             statement: camt_stmt,
+            rounding: RoundingMode::default(),
+            swap_inverted_period: false,
+            utc_offset_minutes: None,
+            warnings: Vec::new(),
+            rest: Vec::new(),
         };
 
         let stmt = Statement::try_from(data).expect("conversion must succeed");
@@ -387,6 +1270,58 @@ mod tests {
         assert_eq!(stmt.transactions.len(), 1);
     }
 
+    #[test]
+    fn statement_from_camt_data_applies_rounding_mode() {
+        let mut camt_stmt = sample_camt_statement();
+        camt_stmt.entries[0].amount.value = "123.456".to_string();
+
+        let data = Camt053Data {
+            statement: camt_stmt,
+            rounding: RoundingMode::HalfEven,
+            swap_inverted_period: false,
+            utc_offset_minutes: None,
+            warnings: Vec::new(),
+            rest: Vec::new(),
+        };
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+        assert_eq!(stmt.transactions[0].amount, 12346);
+
+        // по умолчанию лишние знаки - ошибка
+        let mut camt_stmt = sample_camt_statement();
+        camt_stmt.entries[0].amount.value = "123.456".to_string();
+        let err = Statement::try_from(camt_stmt).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidAmount(_)));
+    }
+
+    #[test]
+    fn inverted_period_is_error_unless_swapped() {
+        let mut camt_stmt = sample_camt_statement();
+        camt_stmt.period = Some(Camt053Period {
+            from: Some("2023-02-01T00:00:00".to_string()),
+            to: Some("2023-01-01T00:00:00".to_string()),
+        });
+
+        let err = Statement::try_from(camt_stmt).unwrap_err();
+        assert!(matches!(err, ParseError::BadInput(_)));
+
+        let mut camt_stmt = sample_camt_statement();
+        camt_stmt.period = Some(Camt053Period {
+            from: Some("2023-02-01T00:00:00".to_string()),
+            to: Some("2023-01-01T00:00:00".to_string()),
+        });
+        let data = Camt053Data {
+            statement: camt_stmt,
+            rounding: RoundingMode::default(),
+            swap_inverted_period: true,
+            utc_offset_minutes: None,
+            warnings: Vec::new(),
+            rest: Vec::new(),
+        };
+        let stmt = Statement::try_from(data).expect("inverted period must be swapped");
+        assert_eq!(stmt.period_from, d(2023, 1, 1));
+        assert_eq!(stmt.period_until, d(2023, 2, 1));
+    }
+
     #[test]
     fn statement_from_camt_statement_uses_not_provided_when_no_iban() {
         let mut camt_stmt = sample_camt_statement();
@@ -396,4 +1331,35 @@ mod tests {
 
         assert_eq!(stmt.account_id, "not provided");
     }
+
+    #[test]
+    fn camt_account_with_only_other_id_uses_it_as_account_id() {
+        let xml = r#"
+        <Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+          <BkToCstmrStmt>
+            <Stmt>
+              <Id>STMT1</Id>
+              <Acct>
+                <Id>
+                  <Othr>
+                    <Id>40702810900000001234</Id>
+                  </Othr>
+                </Id>
+                <Ccy>RUB</Ccy>
+              </Acct>
+              <Ntry>
+                <Amt Ccy="RUB">10.00</Amt>
+                <CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt><Dt>2024-01-05</Dt></BookgDt>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>
+        "#;
+
+        let data = Camt053Data::parse(xml.as_bytes()).expect("parse must succeed");
+        let stmt = Statement::try_from(data).expect("conversion must succeed");
+
+        assert_eq!(stmt.account_id, "40702810900000001234");
+    }
 }
@@ -2,22 +2,63 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct Camt053Entry {
-    #[serde(rename = "Amt")]
+    /// <NtryRef> - референс записи в выписке
+    #[serde(rename = "NtryRef", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) entry_reference: Option<String>,
+
+    // обязательные элементы читаются с default, а их наличие проверяется
+    // при преобразовании в Transaction, чтобы вернуть MissingField с понятным именем
+    #[serde(rename = "Amt", default)]
     pub(crate) amount: CamtAmtXml,
 
-    #[serde(rename = "CdtDbtInd")]
+    #[serde(rename = "CdtDbtInd", default)]
     pub(crate) cdt_dbt_ind: String,
 
-    #[serde(rename = "BookgDt")]
+    /// <RvslInd>true</RvslInd> - признак сторно
+    #[serde(rename = "RvslInd", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reversal_indicator: Option<bool>,
+
+    /// <Sts>BOOK</Sts> - статус проводки (BOOK / PDNG / INFO)
+    #[serde(rename = "Sts", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<CamtEntryStatus>,
+
+    #[serde(rename = "BookgDt", default)]
     pub(crate) booking_date: CamtDateXml,
 
-    #[serde(rename = "ValDt")]
-    pub(crate) value_date: CamtDateXml,
+    /// <ValDt> - дата валютирования; если её нет, берётся `BookgDt`
+    #[serde(rename = "ValDt", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) value_date: Option<CamtDateXml>,
+
+    /// <AcctSvcrRef> - референс операции, присвоенный банком
+    #[serde(
+        rename = "AcctSvcrRef",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) account_servicer_reference: Option<String>,
 
     #[serde(rename = "NtryDtls")]
     pub(crate) details: Option<CamtEntryDetails>,
 }
 
+/// Статус проводки: `<Sts>BOOK</Sts>` в camt.053.001.02
+/// или `<Sts><Cd>BOOK</Cd></Sts>` в более новых версиях
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CamtEntryStatus {
+    #[serde(rename = "$text", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) text: Option<String>,
+
+    #[serde(rename = "Cd", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<String>,
+}
+
+impl CamtEntryStatus {
+    /// Код статуса независимо от версии схемы
+    pub(crate) fn code(&self) -> Option<&str> {
+        self.code.as_deref().or(self.text.as_deref()).map(str::trim)
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct Camt053Statement {
     /// <Id>...</Id> - идентификатор выписки (может быть None)
@@ -81,7 +122,8 @@ pub(crate) struct Camt053GroupHeader {
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtAmtXml {
-    #[serde(rename = "@Ccy")]
+    /// Атрибут Ccy; в урезанных файлах бывает опущен - тогда пустая строка
+    #[serde(rename = "@Ccy", default)]
     pub(crate) currency: String,
 
     #[serde(rename = "$text")]
@@ -90,7 +132,8 @@ pub(crate) struct CamtAmtXml {
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtDateXml {
-    #[serde(rename = "Dt")]
+    /// <Dt> или <DtTm>; пишется всегда как <Dt>
+    #[serde(rename = "Dt", alias = "DtTm")]
     pub(crate) date: String,
 }
 
@@ -136,8 +179,8 @@ pub(crate) struct CamtTransactionAmount {
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtMoney {
-    /// Атрибут Ccy="EUR"/"DKK"
-    #[serde(rename = "@Ccy")]
+    /// Атрибут Ccy="EUR"/"DKK", может отсутствовать
+    #[serde(rename = "@Ccy", default)]
     pub(crate) currency: String,
 
     #[serde(rename = "$text")]
@@ -193,7 +236,7 @@ pub(crate) struct CamtParty {
     pub(crate) name: Option<String>,
 
     /// <PstlAdr>
-    #[serde(rename = "PstlAdr")]
+    #[serde(rename = "PstlAdr", skip_serializing_if = "Option::is_none")]
     pub(crate) postal_address: Option<CamtPostalAddress>,
 
     /// <Id>
@@ -214,20 +257,28 @@ pub(crate) struct CamtAccountId {
     pub(crate) iban: Option<String>,
 }
 
-/// Пока можно сделать очень простой адрес
+/// <PstlAdr> - только те элементы, которые попадают в [`crate::PostalAddress`]
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtPostalAddress {
-    #[serde(rename = "StrtNm")]
+    /// <StrtNm>
+    #[serde(rename = "StrtNm", skip_serializing_if = "Option::is_none")]
     pub(crate) street: Option<String>,
 
-    #[serde(rename = "PstCdId")]
+    /// <PstCd>
+    #[serde(rename = "PstCd", skip_serializing_if = "Option::is_none")]
     pub(crate) postcode: Option<String>,
 
-    #[serde(rename = "TwnNm")]
+    /// <TwnNm>
+    #[serde(rename = "TwnNm", skip_serializing_if = "Option::is_none")]
     pub(crate) town: Option<String>,
 
-    #[serde(rename = "Ctry")]
+    /// <Ctry>
+    #[serde(rename = "Ctry", skip_serializing_if = "Option::is_none")]
     pub(crate) country: Option<String>,
+
+    /// <AdrLine>
+    #[serde(rename = "AdrLine", default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) lines: Vec<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -244,8 +295,40 @@ pub(crate) struct CamtRemittanceInfo {
     pub(crate) structured: Vec<CamtStructuredRemittance>,
 }
 
+/// <Strd> - структурированные реквизиты платежа (ссылка на счёт-фактуру и т.п.)
 #[derive(Debug, Default, Serialize, Deserialize)]
-pub(crate) struct CamtStructuredRemittance {}
+pub(crate) struct CamtStructuredRemittance {
+    /// <CdtrRefInf>
+    #[serde(
+        rename = "CdtrRefInf",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) creditor_reference: Option<CamtCreditorReferenceInfo>,
+
+    /// <AddtlRmtInf>
+    #[serde(rename = "AddtlRmtInf", default)]
+    pub(crate) additional_remittance: Vec<String>,
+}
+
+impl CamtStructuredRemittance {
+    /// Ссылка получателя `<CdtrRefInf><Ref>`, пустая считается отсутствующей
+    pub(crate) fn creditor_reference(&self) -> Option<&str> {
+        self.creditor_reference
+            .as_ref()
+            .and_then(|info| info.reference.as_deref())
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+    }
+}
+
+/// <CdtrRefInf>
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CamtCreditorReferenceInfo {
+    /// <Ref> - например, номер счёта на оплату или RF-ссылка
+    #[serde(rename = "Ref", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reference: Option<String>,
+}
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtRelatedDates {
@@ -259,12 +342,22 @@ pub(crate) struct CamtTxDtls {
     #[serde(rename = "Refs")]
     pub(crate) refs: Option<CamtRefs>,
 
+    /// <CdtDbtInd> на уровне транзакции (например, для сторно) - важнее, чем у `Ntry`
+    #[serde(rename = "CdtDbtInd", skip_serializing_if = "Option::is_none")]
+    pub(crate) cdt_dbt_ind: Option<String>,
+
     #[serde(rename = "AmtDtls")]
     pub(crate) amount_details: Option<CamtAmountDetails>,
 
+    #[serde(rename = "Chrgs", skip_serializing_if = "Option::is_none")]
+    pub(crate) charges: Option<CamtCharges>,
+
     #[serde(rename = "RltdPties")]
     pub(crate) related_parties: Option<CamtRelatedParties>,
 
+    #[serde(rename = "Purp", skip_serializing_if = "Option::is_none")]
+    pub(crate) purpose: Option<CamtPurpose>,
+
     #[serde(rename = "RmtInf")]
     pub(crate) rmt_inf: Option<CamtRemittanceInfo>,
 
@@ -272,6 +365,26 @@ pub(crate) struct CamtTxDtls {
     pub(crate) related_datetimes: Option<CamtRelatedDates>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CamtCharges {
+    /// <Rcrd> - отдельные комиссии
+    #[serde(rename = "Rcrd", default)]
+    pub(crate) records: Vec<CamtChargesRecord>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CamtChargesRecord {
+    #[serde(rename = "Amt")]
+    pub(crate) amount: CamtMoney,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CamtPurpose {
+    /// <Purp><Cd>SALA</Cd></Purp>
+    #[serde(rename = "Cd")]
+    pub(crate) code: Option<String>,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct CamtEntryDetails {
     #[serde(rename = "TxDtls")]
@@ -298,6 +411,17 @@ pub(crate) struct Camt053AccountId {
     /// <IBAN>
     #[serde(rename = "IBAN")]
     pub(crate) iban: Option<String>,
+
+    /// <Othr><Id>...</Id></Othr> - BBAN или внутренний номер банка, если IBAN нет
+    #[serde(rename = "Othr", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) other_id: Option<Camt053OtherAccountId>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Camt053OtherAccountId {
+    /// <Othr><Id>
+    #[serde(rename = "Id")]
+    pub(crate) id: Option<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
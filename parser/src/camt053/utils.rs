@@ -1,8 +1,33 @@
 use super::serde_models::*;
+use crate::date::{self, Date};
 use crate::error::ParseError;
-use crate::model::{Balance, Currency, Direction};
-use crate::utils::{parse_currency, parse_signed_balance};
-use chrono::NaiveDate;
+use crate::model::{Balance, Currency, Direction, FxDetails, PostalAddress};
+use crate::utils::{RoundingMode, parse_amount_with, parse_currency, parse_signed_balance};
+
+/// Проверяет, что сумма записана строго по ISO 20022
+///
+/// Допустимы только цифры и необязательная точка (`1234567.89`): без разделителей
+/// групп, запятых, знака и экспоненты. Такие значения отклоняются сразу,
+/// а не угадываются, как в общем [`parse_amount_with`].
+fn camt_decimal(raw: &str) -> Result<&str, ParseError> {
+    let value = raw.trim();
+
+    let is_decimal = value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && value.matches('.').count() <= 1;
+    if !is_decimal {
+        return Err(ParseError::InvalidAmount(format!(
+            "CAMT amount must be digits with an optional '.' decimal point: '{raw}'"
+        )));
+    }
+
+    Ok(value)
+}
+
+/// Строгий разбор суммы CAMT, см. [`camt_decimal`]
+pub(super) fn parse_camt_amount(raw: &str, rounding: RoundingMode) -> Result<u64, ParseError> {
+    parse_amount_with(camt_decimal(raw)?, rounding)
+}
 
 pub(super) fn detect_currency(stmt: &Camt053Statement) -> Result<Currency, ParseError> {
     // Пробуем валюту счёта
@@ -10,24 +35,33 @@ pub(super) fn detect_currency(stmt: &Camt053Statement) -> Result<Currency, Parse
         return Ok(parse_currency(ccy));
     }
 
-    // Пробуем валюту из балансa
+    // Пробуем валюту из балансов; `Amt` без `Ccy` пропускаем
     if let Some(bal_ccy) = stmt
         .balances
         .iter()
-        .find_map(|bal| Some(bal.amount.currency.as_str()))
+        .map(|bal| bal.amount.currency.trim())
+        .find(|ccy| !ccy.is_empty())
     {
         return Ok(parse_currency(bal_ccy));
     }
 
-    // Пробуем валюту из первой операции
-    if let Some(entry) = stmt.entries.first() {
-        return Ok(parse_currency(&entry.amount.currency));
+    // Пробуем валюту из операций
+    if let Some(entry_ccy) = stmt
+        .entries
+        .iter()
+        .map(|entry| entry.amount.currency.trim())
+        .find(|ccy| !ccy.is_empty())
+    {
+        return Ok(parse_currency(entry_ccy));
     }
 
     Err(ParseError::InvalidCurrency("no currency found".into()))
 }
 
-pub(super) fn balance_from_camt(bal: &Camt053Balance) -> Result<Balance, ParseError> {
+pub(super) fn balance_from_camt(
+    bal: &Camt053Balance,
+    rounding: RoundingMode,
+) -> Result<Balance, ParseError> {
     let dir = match bal.cdt_dbt_ind.as_deref() {
         Some("CRDT") => Direction::Credit,
         Some("DBIT") => Direction::Debit,
@@ -39,55 +73,125 @@ pub(super) fn balance_from_camt(bal: &Camt053Balance) -> Result<Balance, ParseEr
         }
     };
 
-    parse_signed_balance(&bal.amount.value, dir)
+    parse_signed_balance(camt_decimal(&bal.amount.value)?, dir, rounding)
 }
 
-pub(super) fn extract_balances(stmt: &Camt053Statement) -> (Option<Balance>, Option<Balance>) {
-    let mut opening = None;
-    let mut closing = None;
+/// Балансы выписки, извлечённые из блоков `Bal`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct CamtBalances {
+    /// `OPBD`, а при его отсутствии `PRCD`
+    pub(super) opening: Option<Balance>,
+    /// `CLBD`
+    pub(super) closing: Option<Balance>,
+    /// `CLAV`
+    pub(super) closing_available: Option<Balance>,
+    /// `FWAV`
+    pub(super) forward_available: Option<Balance>,
+}
+
+/// Баланс вместе с его датой `Bal/Dt`, если она есть и разбирается
+type DatedBalance = (Option<Date>, Option<Balance>);
+
+/// Нужно ли заменить уже найденный баланс того же типа на `candidate`
+///
+/// Если даты есть у обоих, выбираем более раннюю (`earliest`) или более
+/// позднюю; иначе, как и раньше, побеждает последний в документе.
+fn replaces(current: &Option<DatedBalance>, candidate: &DatedBalance, earliest: bool) -> bool {
+    match (current, candidate.0) {
+        (Some((Some(current), _)), Some(candidate)) if earliest => candidate < *current,
+        (Some((Some(current), _)), Some(candidate)) => candidate >= *current,
+        _ => true,
+    }
+}
+
+/// Выбирает балансы по типам
+///
+/// Многодневные выписки могут содержать несколько `OPBD`/`CLBD` с разными
+/// датами: открывающим считается самый ранний `OPBD`, закрывающим - самый
+/// поздний `CLBD`.
+///
+/// Сумма или направление известного баланса, которые не удалось разобрать,
+/// дают ошибку, а не пропавший баланс; балансы прочих типов не разбираются.
+pub(super) fn extract_balances(
+    stmt: &Camt053Statement,
+    rounding: RoundingMode,
+) -> Result<CamtBalances, ParseError> {
+    let mut balances = CamtBalances::default();
+    let mut previously_closed = None;
+    let mut opening: Option<DatedBalance> = None;
+    let mut closing: Option<DatedBalance> = None;
 
     for bal in &stmt.balances {
         let code = bal.balance_type.code_or_proprietary.code.as_deref();
+        if !matches!(code, Some("OPBD" | "PRCD" | "CLBD" | "CLAV" | "FWAV")) {
+            continue;
+        }
 
-        let parsed = balance_from_camt(bal).ok();
+        let parsed = Some(balance_from_camt(bal, rounding)?);
+        let date = bal
+            .date
+            .as_ref()
+            // дата нужна только для выбора последнего баланса, пояс не важен
+            .and_then(|d| parse_camt_date_to_naive(&d.date, None).ok());
 
         match code {
-            Some("OPBD") => opening = parsed,
-            Some("CLBD") => closing = parsed,
+            Some("OPBD") if replaces(&opening, &(date, parsed), true) => {
+                opening = Some((date, parsed));
+            }
+            Some("PRCD") => previously_closed = parsed,
+            Some("CLBD") if replaces(&closing, &(date, parsed), false) => {
+                closing = Some((date, parsed));
+            }
+            Some("CLAV") => balances.closing_available = parsed,
+            Some("FWAV") => balances.forward_available = parsed,
             _ => {}
         }
     }
 
-    (opening, closing)
-}
+    balances.opening = opening.and_then(|(_, value)| value);
+    balances.closing = closing.and_then(|(_, value)| value);
 
-pub(super) fn parse_camt_date_to_naive(s: &str) -> Result<NaiveDate, ParseError> {
-    // CAMT может прислать "2023-04-20" или "2023-04-20T23:59:59"
-    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-        return Ok(d);
-    }
-    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
-        return Ok(dt.date());
+    // PRCD (закрывающий баланс прошлой выписки) используем как открывающий,
+    // только если банк не прислал OPBD
+    if balances.opening.is_none() {
+        balances.opening = previously_closed;
     }
-    Err(ParseError::BadInput(format!("invalid CAMT date: {s}")))
+
+    Ok(balances)
 }
 
-pub(super) fn detect_period(stmt: &Camt053Statement) -> Result<(NaiveDate, NaiveDate), ParseError> {
+/// Дата из CAMT: "2023-04-20", "2023-04-20T23:59:59" или с поясом ("...+02:00", "...Z")
+///
+/// См. [`date::parse_iso_date_in_zone`] про `utc_offset_minutes`.
+pub(super) fn parse_camt_date_to_naive(
+    s: &str,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Date, ParseError> {
+    date::parse_iso_date_in_zone(s, utc_offset_minutes).map_err(|err| match err {
+        err @ ParseError::BadInput(_) if utc_offset_minutes.is_some() => err,
+        _ => ParseError::BadInput(format!("invalid CAMT date: {s}")),
+    })
+}
+
+pub(super) fn detect_period(
+    stmt: &Camt053Statement,
+    utc_offset_minutes: Option<i32>,
+) -> Result<(Date, Date), ParseError> {
     // Пытаемся извлечь из FrToDt
     if let Some(period) = &stmt.period
         && let (Some(raw_from), Some(raw_to)) = (&period.from, &period.to)
     {
-        let from = parse_camt_date_to_naive(raw_from)?;
-        let to = parse_camt_date_to_naive(raw_to)?;
+        let from = parse_camt_date_to_naive(raw_from, utc_offset_minutes)?;
+        let to = parse_camt_date_to_naive(raw_to, utc_offset_minutes)?;
 
         return Ok((from, to));
     }
     // не получилось - идём искать min/max из транзакций
-    let mut min_date: Option<NaiveDate> = None;
-    let mut max_date: Option<NaiveDate> = None;
+    let mut min_date: Option<Date> = None;
+    let mut max_date: Option<Date> = None;
 
     for entry in &stmt.entries {
-        let d = parse_camt_date_to_naive(&entry.booking_date.date)?;
+        let d = parse_camt_date_to_naive(&entry.booking_date.date, utc_offset_minutes)?;
 
         min_date = Some(match min_date {
             Some(cur) => cur.min(d),
@@ -106,6 +210,17 @@ pub(super) fn detect_period(stmt: &Camt053Statement) -> Result<(NaiveDate, Naive
     }
 }
 
+/// "Персона" контрагента: сначала Ultmt*, если есть, иначе обычный
+fn counterparty_party(parties: &CamtRelatedParties, direction: Direction) -> Option<&CamtParty> {
+    match direction {
+        Direction::Debit => parties
+            .ultimate_creditor
+            .as_ref()
+            .or(parties.creditor.as_ref()),
+        Direction::Credit => parties.ultimate_debtor.as_ref().or(parties.debtor.as_ref()),
+    }
+}
+
 pub(super) fn counterparty_from_tx(
     tx: &CamtTxDtls,
     direction: Direction,
@@ -115,16 +230,7 @@ pub(super) fn counterparty_from_tx(
         None => return (None, None),
     };
 
-    // Выбираем "персону" контрагента: сначала Ultmt*, если есть, иначе обычный
-    let party_opt = match direction {
-        Direction::Debit => parties
-            .ultimate_creditor
-            .as_ref()
-            .or(parties.creditor.as_ref()),
-        Direction::Credit => parties.ultimate_debtor.as_ref().or(parties.debtor.as_ref()),
-    };
-
-    let counterparty_name = party_opt.and_then(|p| p.name.clone());
+    let counterparty_name = counterparty_party(parties, direction).and_then(|p| p.name.clone());
 
     // Счёт контрагента (IBAN)
     let account_opt = match direction {
@@ -137,13 +243,111 @@ pub(super) fn counterparty_from_tx(
     (counterparty_id, counterparty_name)
 }
 
+/// Адрес той же "персоны" контрагента, что и в [`counterparty_from_tx`]
+///
+/// Пустые элементы отбрасываются; адрес без единого заполненного поля - `None`.
+pub(super) fn counterparty_address_from_tx(
+    tx: &CamtTxDtls,
+    direction: Direction,
+) -> Option<PostalAddress> {
+    let address = counterparty_party(tx.related_parties.as_ref()?, direction)?
+        .postal_address
+        .as_ref()?;
+
+    let clean = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let address = PostalAddress {
+        street: clean(&address.street),
+        postcode: clean(&address.postcode),
+        town: clean(&address.town),
+        country: clean(&address.country),
+        lines: address
+            .lines
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+    };
+
+    (!address.is_empty()).then_some(address)
+}
+
+/// Описание из `<Ustrd>`, а без него - из `<Strd><AddtlRmtInf>`
 pub(super) fn description_from_tx(tx: &CamtTxDtls) -> String {
-    if let Some(rmt) = &tx.rmt_inf
-        && !rmt.unstructured.is_empty()
-    {
+    let Some(rmt) = &tx.rmt_inf else {
+        return String::new();
+    };
+    if !rmt.unstructured.is_empty() {
         return rmt.unstructured.join("\n");
     }
-    String::new()
+
+    rmt.structured
+        .iter()
+        .flat_map(|strd| strd.additional_remittance.iter())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Сквозной идентификатор платежа. Заглушка `NOTPROVIDED` считается отсутствием значения
+///
+/// Без `EndToEndId` берётся ссылка получателя из `<Strd><CdtrRefInf><Ref>`.
+pub(super) fn reference_from_tx(tx: &CamtTxDtls) -> Option<String> {
+    let end_to_end = tx
+        .refs
+        .as_ref()
+        .and_then(|r| r.end_to_end_id.clone())
+        .filter(|id| !id.trim().is_empty() && id != "NOTPROVIDED");
+
+    end_to_end.or_else(|| {
+        tx.rmt_inf
+            .as_ref()?
+            .structured
+            .iter()
+            .find_map(CamtStructuredRemittance::creditor_reference)
+            .map(str::to_string)
+    })
+}
+
+pub(super) fn purpose_code_from_tx(tx: &CamtTxDtls) -> Option<String> {
+    tx.purpose.as_ref().and_then(|p| p.code.clone())
+}
+
+pub(super) fn fx_from_tx(tx: &CamtTxDtls) -> Option<FxDetails> {
+    let fx = tx
+        .amount_details
+        .as_ref()
+        .and_then(|a| a.transaction.as_ref())
+        .and_then(|t| t.fx.as_ref())?;
+
+    Some(FxDetails {
+        source_currency: fx.src_ccy.as_deref().map(parse_currency),
+        target_currency: fx.trgt_ccy.as_deref().map(parse_currency),
+        rate: fx.rate.clone(),
+    })
+}
+
+/// Сумма всех комиссий по операции. `None`, если комиссий нет
+pub(super) fn charges_from_tx(
+    tx: &CamtTxDtls,
+    rounding: RoundingMode,
+) -> Result<Option<u64>, ParseError> {
+    let Some(charges) = tx.charges.as_ref().filter(|c| !c.records.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut total: u64 = 0;
+    for record in &charges.records {
+        total = total.saturating_add(parse_camt_amount(&record.amount.value, rounding)?);
+    }
+
+    Ok(Some(total))
 }
 
 #[cfg(test)]
@@ -154,7 +358,7 @@ mod tests {
     fn empty_statement() -> Camt053Statement {
         Camt053Statement {
             account: Camt053Account {
-                id: Camt053AccountId { iban: None },
+                id: Camt053AccountId::default(),
                 name: None,
                 currency: None,
             },
@@ -162,6 +366,44 @@ mod tests {
         }
     }
 
+    // parse_camt_amount
+
+    #[test]
+    fn parse_camt_amount_accepts_plain_decimal() {
+        assert_eq!(
+            parse_camt_amount("1234567.89", RoundingMode::Error).unwrap(),
+            123_456_789
+        );
+        assert_eq!(
+            parse_camt_amount(" 15 ", RoundingMode::Error).unwrap(),
+            1_500
+        );
+    }
+
+    #[test]
+    fn parse_camt_amount_rejects_grouping_and_commas() {
+        for raw in [
+            "1,234,567.89",
+            "1.234.567,89",
+            "12,50",
+            "1 234.56",
+            "1e3",
+            "-5.00",
+            "",
+        ] {
+            let err = parse_camt_amount(raw, RoundingMode::Error).unwrap_err();
+            match err {
+                ParseError::InvalidAmount(msg) => {
+                    assert!(
+                        msg.contains("CAMT amount"),
+                        "unexpected msg for {raw:?}: {msg}"
+                    )
+                }
+                other => panic!("expected InvalidAmount for {raw:?}, got {other:?}"),
+            }
+        }
+    }
+
     // detect_currency
 
     #[test]
@@ -213,6 +455,29 @@ mod tests {
         assert_eq!(ccy, Currency::CNY);
     }
 
+    #[test]
+    fn detect_currency_skips_amounts_without_ccy() {
+        let mut stmt = empty_statement();
+
+        stmt.balances.push(Camt053Balance {
+            amount: CamtAmtXml {
+                currency: String::new(),
+                value: "100.00".to_string(),
+            },
+            ..Default::default()
+        });
+        stmt.entries.push(Camt053Entry {
+            amount: CamtAmtXml {
+                currency: "CNY".to_string(),
+                value: "50.00".to_string(),
+            },
+            ..Default::default()
+        });
+
+        let ccy = detect_currency(&stmt).unwrap();
+        assert_eq!(ccy, Currency::CNY);
+    }
+
     #[test]
     fn detect_currency_fails_if_no_sources() {
         let stmt = empty_statement();
@@ -242,7 +507,7 @@ mod tests {
             date: None,
         };
 
-        let value = balance_from_camt(&bal).unwrap();
+        let value = balance_from_camt(&bal, RoundingMode::Error).unwrap();
         assert!(value > 0, "credit balance should be positive, got {value}");
     }
 
@@ -260,7 +525,7 @@ mod tests {
             date: None,
         };
 
-        let value = balance_from_camt(&bal).unwrap();
+        let value = balance_from_camt(&bal, RoundingMode::Error).unwrap();
         assert!(value < 0, "debit balance should be negative, got {value}");
     }
 
@@ -278,7 +543,7 @@ mod tests {
             date: None,
         };
 
-        let err = balance_from_camt(&bal).unwrap_err();
+        let err = balance_from_camt(&bal, RoundingMode::Error).unwrap_err();
         match err {
             ParseError::InvalidAmount(msg) => {
                 assert!(msg.contains("unknown CdtDbtInd"));
@@ -301,7 +566,7 @@ mod tests {
             date: None,
         };
 
-        let value = balance_from_camt(&bal).unwrap();
+        let value = balance_from_camt(&bal, RoundingMode::Error).unwrap();
         assert_eq!(value, 12_345);
     }
 
@@ -319,7 +584,7 @@ mod tests {
             date: None,
         };
 
-        let value = balance_from_camt(&bal).unwrap();
+        let value = balance_from_camt(&bal, RoundingMode::Error).unwrap();
         assert_eq!(value, -98_765);
     }
 
@@ -360,10 +625,10 @@ mod tests {
         stmt.balances.push(opening_bal);
         stmt.balances.push(closing_bal);
 
-        let (opening, closing) = extract_balances(&stmt);
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
 
-        assert!(opening.is_some());
-        assert!(closing.is_some());
+        assert!(balances.opening.is_some());
+        assert!(balances.closing.is_some());
     }
 
     #[test]
@@ -386,29 +651,153 @@ mod tests {
 
         stmt.balances.push(other_bal);
 
-        let (opening, closing) = extract_balances(&stmt);
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
 
-        assert!(opening.is_none());
-        assert!(closing.is_none());
+        assert_eq!(balances, CamtBalances::default());
+    }
+
+    fn balance(code: &str, value: &str) -> Camt053Balance {
+        Camt053Balance {
+            balance_type: Camt053BalanceType {
+                code_or_proprietary: Camt053BalanceCodeOrProprietary {
+                    code: Some(code.to_string()),
+                },
+            },
+            amount: CamtAmtXml {
+                currency: "EUR".to_string(),
+                value: value.to_string(),
+            },
+            cdt_dbt_ind: Some("CRDT".to_string()),
+            date: None,
+        }
+    }
+
+    #[test]
+    fn extract_balances_uses_prcd_as_opening_when_opbd_missing() {
+        let mut stmt = empty_statement();
+        stmt.balances.push(balance("PRCD", "10.00"));
+        stmt.balances.push(balance("CLBD", "20.00"));
+        stmt.balances.push(balance("CLAV", "15.00"));
+        stmt.balances.push(balance("FWAV", "12.50"));
+
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
+
+        assert_eq!(
+            balances,
+            CamtBalances {
+                opening: Some(1_000),
+                closing: Some(2_000),
+                closing_available: Some(1_500),
+                forward_available: Some(1_250),
+            }
+        );
+    }
+
+    #[test]
+    fn extract_balances_prefers_opbd_over_prcd() {
+        let mut stmt = empty_statement();
+        stmt.balances.push(balance("PRCD", "10.00"));
+        stmt.balances.push(balance("OPBD", "11.00"));
+
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
+
+        assert_eq!(balances.opening, Some(1_100));
+    }
+
+    fn dated_balance(code: &str, value: &str, date: &str) -> Camt053Balance {
+        Camt053Balance {
+            date: Some(CamtDateXml {
+                date: date.to_string(),
+            }),
+            ..balance(code, value)
+        }
+    }
+
+    #[test]
+    fn extract_balances_picks_earliest_opbd_and_latest_clbd() {
+        let mut stmt = empty_statement();
+        stmt.balances
+            .push(dated_balance("OPBD", "20.00", "2023-01-02"));
+        stmt.balances
+            .push(dated_balance("CLBD", "25.00", "2023-01-02"));
+        stmt.balances
+            .push(dated_balance("OPBD", "10.00", "2023-01-01"));
+        stmt.balances
+            .push(dated_balance("CLBD", "30.00", "2023-01-03"));
+        stmt.balances
+            .push(dated_balance("CLBD", "20.00", "2023-01-01"));
+
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
+
+        assert_eq!(balances.opening, Some(1_000));
+        assert_eq!(balances.closing, Some(3_000));
+    }
+
+    #[test]
+    fn extract_balances_without_dates_keeps_last() {
+        let mut stmt = empty_statement();
+        stmt.balances.push(balance("OPBD", "10.00"));
+        stmt.balances.push(balance("OPBD", "11.00"));
+
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
+
+        assert_eq!(balances.opening, Some(1_100));
+    }
+
+    #[test]
+    fn extract_balances_fails_on_grouped_opbd_amount() {
+        let mut stmt = empty_statement();
+        stmt.balances.push(balance("OPBD", "1,234.56"));
+        stmt.balances.push(balance("CLBD", "20.00"));
+
+        let err = extract_balances(&stmt, RoundingMode::Error).unwrap_err();
+
+        assert!(
+            matches!(err, ParseError::InvalidAmount(ref msg) if msg.contains("1,234.56")),
+            "unexpected error: {err:?}"
+        );
+    }
+
+    #[test]
+    fn extract_balances_does_not_parse_unknown_balance_types() {
+        let mut stmt = empty_statement();
+        stmt.balances.push(balance("INFO", "1e3"));
+
+        let balances = extract_balances(&stmt, RoundingMode::Error).unwrap();
+
+        assert_eq!(balances, CamtBalances::default());
     }
 
     // parse_camt_date_to_naive
 
     #[test]
     fn parse_camt_date_handles_plain_date() {
-        let d = parse_camt_date_to_naive("2023-04-20").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 4, 20).unwrap());
+        let d = parse_camt_date_to_naive("2023-04-20", None).unwrap();
+        assert_eq!(d, crate::date::ymd(2023, 4, 20).unwrap());
     }
 
     #[test]
     fn parse_camt_date_handles_datetime() {
-        let d = parse_camt_date_to_naive("2023-04-20T23:59:59").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 4, 20).unwrap());
+        let d = parse_camt_date_to_naive("2023-04-20T23:59:59", None).unwrap();
+        assert_eq!(d, crate::date::ymd(2023, 4, 20).unwrap());
+    }
+
+    #[test]
+    fn parse_camt_date_handles_timezone_suffix() {
+        let d = parse_camt_date_to_naive("2023-04-20T23:59:59+02:00", None).unwrap();
+        assert_eq!(d, crate::date::ymd(2023, 4, 20).unwrap());
+
+        let d = parse_camt_date_to_naive("2023-04-20T23:59:59Z", None).unwrap();
+        assert_eq!(d, crate::date::ymd(2023, 4, 20).unwrap());
+
+        // 21:59:59 UTC - это уже 21 апреля в Москве
+        let d = parse_camt_date_to_naive("2023-04-20T23:59:59+02:00", Some(180)).unwrap();
+        assert_eq!(d, crate::date::ymd(2023, 4, 21).unwrap());
     }
 
     #[test]
     fn parse_camt_date_fails_on_invalid_string() {
-        let err = parse_camt_date_to_naive("not-a-date").unwrap_err();
+        let err = parse_camt_date_to_naive("not-a-date", None).unwrap_err();
         match err {
             ParseError::BadInput(msg) => {
                 assert!(msg.contains("invalid CAMT date"));
@@ -427,10 +816,10 @@ mod tests {
             to: Some("2023-01-31T23:59:59".to_string()),
         });
 
-        let (from, to) = detect_period(&stmt).unwrap();
+        let (from, to) = detect_period(&stmt, None).unwrap();
 
-        assert_eq!(from, NaiveDate::from_ymd_opt(2023, 1, 1).unwrap());
-        assert_eq!(to, NaiveDate::from_ymd_opt(2023, 1, 31).unwrap());
+        assert_eq!(from, crate::date::ymd(2023, 1, 1).unwrap());
+        assert_eq!(to, crate::date::ymd(2023, 1, 31).unwrap());
     }
 
     #[test]
@@ -458,16 +847,16 @@ mod tests {
             ..Default::default()
         });
 
-        let (from, to) = detect_period(&stmt).unwrap();
+        let (from, to) = detect_period(&stmt, None).unwrap();
 
-        assert_eq!(from, NaiveDate::from_ymd_opt(2023, 2, 5).unwrap());
-        assert_eq!(to, NaiveDate::from_ymd_opt(2023, 2, 15).unwrap());
+        assert_eq!(from, crate::date::ymd(2023, 2, 5).unwrap());
+        assert_eq!(to, crate::date::ymd(2023, 2, 15).unwrap());
     }
 
     #[test]
     fn detect_period_fails_if_no_period_and_no_entries() {
         let stmt = empty_statement();
-        let err = detect_period(&stmt).unwrap_err();
+        let err = detect_period(&stmt, None).unwrap_err();
 
         match err {
             ParseError::BadInput(msg) => {
@@ -568,6 +957,53 @@ mod tests {
         assert!(cp_name.is_none());
     }
 
+    // counterparty_address_from_tx
+
+    #[test]
+    fn counterparty_address_from_tx_takes_town_and_country_of_counterparty() {
+        let mut debtor = make_party("Debtor");
+        debtor.postal_address = Some(CamtPostalAddress {
+            town: Some(" Stockholm ".to_string()),
+            country: Some("SE".to_string()),
+            street: Some("  ".to_string()),
+            ..Default::default()
+        });
+        let tx = CamtTxDtls {
+            related_parties: Some(CamtRelatedParties {
+                debtor: Some(debtor),
+                creditor: Some(make_party("Creditor")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let address = counterparty_address_from_tx(&tx, Direction::Credit).unwrap();
+        assert_eq!(address.town.as_deref(), Some("Stockholm"));
+        assert_eq!(address.country.as_deref(), Some("SE"));
+        assert_eq!(address.street, None);
+
+        // у кредитора адреса нет
+        assert_eq!(counterparty_address_from_tx(&tx, Direction::Debit), None);
+    }
+
+    #[test]
+    fn counterparty_address_from_tx_ignores_blank_address() {
+        let mut creditor = make_party("Creditor");
+        creditor.postal_address = Some(CamtPostalAddress {
+            lines: vec![" ".to_string()],
+            ..Default::default()
+        });
+        let tx = CamtTxDtls {
+            related_parties: Some(CamtRelatedParties {
+                creditor: Some(creditor),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(counterparty_address_from_tx(&tx, Direction::Debit), None);
+    }
+
     // description_from_tx
 
     #[test]
@@ -617,4 +1053,105 @@ mod tests {
         let desc = description_from_tx(&tx);
         assert_eq!(desc, "");
     }
+
+    // reference / purpose / fx / charges
+
+    #[test]
+    fn reference_from_tx_ignores_notprovided() {
+        let mut tx = CamtTxDtls {
+            refs: Some(CamtRefs {
+                end_to_end_id: Some("E2E-1".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(reference_from_tx(&tx), Some("E2E-1".to_string()));
+
+        tx.refs.as_mut().unwrap().end_to_end_id = Some("NOTPROVIDED".to_string());
+        assert_eq!(reference_from_tx(&tx), None);
+    }
+
+    #[test]
+    fn reference_from_tx_falls_back_to_creditor_reference() {
+        let structured = CamtStructuredRemittance {
+            creditor_reference: Some(CamtCreditorReferenceInfo {
+                reference: Some("RF18539007547034".to_string()),
+            }),
+            additional_remittance: vec!["Invoice 42".to_string()],
+        };
+        let mut tx = CamtTxDtls {
+            rmt_inf: Some(CamtRemittanceInfo {
+                unstructured: Vec::new(),
+                structured: vec![structured],
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(reference_from_tx(&tx), Some("RF18539007547034".to_string()));
+        assert_eq!(description_from_tx(&tx), "Invoice 42");
+
+        // EndToEndId важнее
+        tx.refs = Some(CamtRefs {
+            end_to_end_id: Some("E2E-1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(reference_from_tx(&tx), Some("E2E-1".to_string()));
+    }
+
+    #[test]
+    fn fx_and_purpose_from_tx_are_extracted() {
+        let tx = CamtTxDtls {
+            amount_details: Some(CamtAmountDetails {
+                instructed: None,
+                transaction: Some(CamtTransactionAmount {
+                    amount: CamtMoney {
+                        currency: "DKK".to_string(),
+                        value: "74.74".to_string(),
+                    },
+                    fx: Some(CamtCurrencyExchange {
+                        src_ccy: Some("EUR".to_string()),
+                        trgt_ccy: Some("DKK".to_string()),
+                        unit_ccy: None,
+                        rate: Some("7.4738000".to_string()),
+                    }),
+                }),
+            }),
+            purpose: Some(CamtPurpose {
+                code: Some("SALA".to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let fx = fx_from_tx(&tx).expect("fx must be present");
+        assert_eq!(fx.source_currency, Some(Currency::EUR));
+        assert_eq!(fx.target_currency, Some(Currency::Other("DKK".to_string())));
+        assert_eq!(fx.rate.as_deref(), Some("7.4738000"));
+
+        assert_eq!(purpose_code_from_tx(&tx), Some("SALA".to_string()));
+    }
+
+    #[test]
+    fn charges_from_tx_sums_records() {
+        let record = |v: &str| CamtChargesRecord {
+            amount: CamtMoney {
+                currency: "EUR".to_string(),
+                value: v.to_string(),
+            },
+        };
+        let tx = CamtTxDtls {
+            charges: Some(CamtCharges {
+                records: vec![record("1.50"), record("0.25")],
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            charges_from_tx(&tx, RoundingMode::Error).unwrap(),
+            Some(175)
+        );
+        assert_eq!(
+            charges_from_tx(&CamtTxDtls::default(), RoundingMode::Error).unwrap(),
+            None
+        );
+    }
 }
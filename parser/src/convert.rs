@@ -0,0 +1,179 @@
+use crate::camt053::Camt053Data;
+use crate::csv_parser::CsvData;
+use crate::detect::DetectedFormat;
+use crate::error::{ParseError, ParseWarning};
+use crate::model::Statement;
+use crate::mt940::Mt940Data;
+use crate::serialization::{
+    Camt053WriteOptions, CsvWriteOptions, Mt940WriteOptions, SimpleCsvWriteOptions,
+};
+use crate::simple_csv::SimpleCsvData;
+use std::io::{Read, Write};
+
+/// Поддерживаемые форматы выписок
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Табличная CSV-выписка
+    Csv,
+    /// CAMT.053 XML
+    Camt053,
+    /// SWIFT MT940
+    Mt940,
+    /// Плоский CSV (`booking_date,value_date,...`),
+    /// см. [`Statement::write_simple_csv`] и [`SimpleCsvData`]
+    SimpleCsv,
+}
+
+impl From<DetectedFormat> for Format {
+    fn from(format: DetectedFormat) -> Self {
+        match format {
+            DetectedFormat::Csv => Format::Csv,
+            DetectedFormat::Camt053 => Format::Camt053,
+            DetectedFormat::Mt940 => Format::Mt940,
+        }
+    }
+}
+
+/// Настройки записи для [`convert_with`]
+///
+/// Для каждого выходного формата используются только его собственные настройки.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Настройки записи CSV
+    pub csv: CsvWriteOptions,
+    /// Настройки записи CAMT.053
+    pub camt053: Camt053WriteOptions,
+    /// Настройки записи MT940
+    pub mt940: Mt940WriteOptions,
+    /// Настройки записи плоского CSV
+    pub simple_csv: SimpleCsvWriteOptions,
+}
+
+/// Парсит выписку указанного формата в [`Statement`]
+///
+/// Предупреждения парсинга отбрасываются, см. [`parse_statement_with_warnings`].
+pub fn parse_statement<R: Read>(reader: R, format: Format) -> Result<Statement, ParseError> {
+    parse_statement_with_warnings(reader, format).map(|(statement, _)| statement)
+}
+
+/// То же, что [`parse_statement`], но дополнительно возвращает предупреждения парсинга
+pub fn parse_statement_with_warnings<R: Read>(
+    reader: R,
+    format: Format,
+) -> Result<(Statement, Vec<ParseWarning>), ParseError> {
+    match format {
+        Format::Csv => {
+            let mut data = CsvData::parse(reader)?;
+            let warnings = std::mem::take(&mut data.warnings);
+            Ok((Statement::try_from(data)?, warnings))
+        }
+        Format::Camt053 => {
+            let mut data = Camt053Data::parse(reader)?;
+            let warnings = std::mem::take(&mut data.warnings);
+            Ok((Statement::try_from(data)?, warnings))
+        }
+        Format::Mt940 => {
+            let mut data = Mt940Data::parse(reader)?;
+            let warnings = std::mem::take(&mut data.warnings);
+            Ok((Statement::try_from(data)?, warnings))
+        }
+        Format::SimpleCsv => {
+            let data = SimpleCsvData::parse(reader)?;
+            Ok((Statement::try_from(data)?, Vec::new()))
+        }
+    }
+}
+
+/// Записывает выписку в указанном формате
+///
+/// Возвращает предупреждения записи: о валюте, для которой пришлось записать
+/// плейсхолдер, и (для MT940) о полях, которые в формате не представимы.
+pub fn write_statement<W: Write>(
+    statement: &Statement,
+    writer: W,
+    format: Format,
+    options: &ConvertOptions,
+) -> Result<Vec<ParseWarning>, ParseError> {
+    match format {
+        Format::Csv => {
+            statement.write_csv_with(writer, &options.csv)?;
+            Ok(Vec::new())
+        }
+        Format::Camt053 => statement.write_camt053_with(writer, &options.camt053),
+        Format::Mt940 => statement.write_mt940_with(writer, &options.mt940),
+        Format::SimpleCsv => {
+            statement.write_simple_csv_with(writer, &options.simple_csv)?;
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Конвертирует выписку из формата `input` в формат `output` одним вызовом
+///
+/// ```
+/// use parser::{Format, convert};
+///
+/// let csv = std::fs::read("tests/fixtures/csv/example.csv").unwrap();
+/// let mut out = Vec::new();
+/// convert(csv.as_slice(), Format::Csv, &mut out, Format::Mt940).unwrap();
+/// assert!(String::from_utf8(out).unwrap().contains(":60F:"));
+/// ```
+pub fn convert<R: Read, W: Write>(
+    reader: R,
+    input: Format,
+    writer: W,
+    output: Format,
+) -> Result<(), ParseError> {
+    convert_with(reader, input, writer, output, &ConvertOptions::default())?;
+    Ok(())
+}
+
+/// То же, что [`convert`], но с настройками записи
+///
+/// Возвращает предупреждения парсинга, за которыми идут предупреждения записи,
+/// см. [`parse_statement_with_warnings`] и [`write_statement`].
+pub fn convert_with<R: Read, W: Write>(
+    reader: R,
+    input: Format,
+    writer: W,
+    output: Format,
+    options: &ConvertOptions,
+) -> Result<Vec<ParseWarning>, ParseError> {
+    let (statement, mut warnings) = parse_statement_with_warnings(reader, input)?;
+    warnings.extend(write_statement(&statement, writer, output, options)?);
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_detected_format() {
+        assert_eq!(Format::from(DetectedFormat::Csv), Format::Csv);
+        assert_eq!(Format::from(DetectedFormat::Camt053), Format::Camt053);
+        assert_eq!(Format::from(DetectedFormat::Mt940), Format::Mt940);
+    }
+
+    #[test]
+    fn convert_propagates_parse_errors() {
+        let mut out = Vec::new();
+        let res = convert(&b"garbage"[..], Format::Mt940, &mut out, Format::Csv);
+        assert!(res.is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn simple_csv_converts_both_ways() {
+        let csv = std::fs::read("tests/fixtures/csv/example.csv").unwrap();
+        let original = parse_statement(csv.as_slice(), Format::Csv).unwrap();
+
+        let mut out = Vec::new();
+        convert(csv.as_slice(), Format::Csv, &mut out, Format::SimpleCsv).unwrap();
+        assert!(out.starts_with(b"booking_date,"));
+
+        let parsed = parse_statement(out.as_slice(), Format::SimpleCsv).unwrap();
+        assert_eq!(parsed.transactions.len(), original.transactions.len());
+        assert_eq!(parsed.net_amount(), original.net_amount());
+    }
+}
@@ -1,14 +1,19 @@
 mod utils;
 
-use crate::error::ParseError;
-use crate::model::{Balance, Statement, Transaction};
-use crate::utils::parse_currency;
-use chrono::NaiveDate;
+use crate::date::{self, Date};
+use crate::error::{ParseError, ParseWarning};
+use crate::model::{Balance, Direction, OperationType, SourceFormat, Statement, Transaction};
+use crate::utils::{
+    RowErrors, collect_transactions, excerpt_around, ordered_period, parse_currency, strip_utf8_bom,
+};
 use csv::{ReaderBuilder, StringRecord};
 use std::io::Read;
 use utils::*;
 
 /// Структура с данными из заголовка CSV-выписки
+///
+/// Часть полей пока не используется при конвертации, но сохраняется как есть
+#[allow(dead_code)]
 #[derive(Debug, Default)]
 pub(crate) struct CsvHeader {
     creation_date: String,
@@ -61,6 +66,7 @@ impl CsvHeader {
 }
 
 /// Операция из CSV-выписки
+#[allow(dead_code)]
 #[derive(Debug, Default)]
 pub(crate) struct CsvRecord {
     // дата проводки
@@ -73,6 +79,8 @@ pub(crate) struct CsvRecord {
     operation_type: String,
     bank: String,
     transaction_purpose: Option<String>,
+    /// номер строки во входных данных и отрывок вокруг неё, см. [`ParseError::AtLine`]
+    location: Option<(usize, String)>,
 }
 
 impl CsvRecord {
@@ -88,13 +96,14 @@ impl CsvRecord {
         let booking_date = get(layout.booking_date_col);
         let debit_account = get(layout.debit_account_col);
         let credit_account = get(layout.credit_account_col);
-        let debit_amount = row
-            .get(layout.debit_amount_col)
-            .map(|s| s.trim().to_string());
-
-        let credit_amount = row
-            .get(layout.credit_amount_col)
-            .map(|s| s.trim().to_string());
+        let (debit_amount, credit_amount) = match layout.amounts {
+            AmountCols::DebitCredit { debit, credit } => (
+                row.get(debit).map(|s| s.trim().to_string()),
+                row.get(credit).map(|s| s.trim().to_string()),
+            ),
+            // знак определяет сторону, дальше сумма идёт как в раздельных колонках
+            AmountCols::Signed(col) => split_signed_amount(row.get(col).unwrap_or("")),
+        };
         let doc_number = get(layout.doc_number_col);
         let operation_type = get(layout.operation_type_col);
         let bank = get(layout.bank_col);
@@ -112,29 +121,60 @@ impl CsvRecord {
             operation_type,
             bank,
             transaction_purpose,
+            location: None,
         }
     }
 
-    fn into_transaction(self, our_account: &str) -> Result<Transaction, ParseError> {
-        let booking_date = NaiveDate::parse_from_str(&self.booking_date, "%d.%m.%Y")?;
-        let value_date: Option<NaiveDate> = None;
-        let (amount, direction) = parse_amount_and_direction(
+    /// Преобразует запись в транзакции: обычно одну, две - при
+    /// [`BothSidesPolicy::Split`] и заполненных обеих суммах
+    ///
+    /// `our_name` (владелец счёта из шапки) помогает найти наш блок, если
+    /// номер счёта записан иначе, чем в шапке.
+    fn into_transactions(
+        self,
+        our_account: &str,
+        our_name: Option<&str>,
+        both_sides: BothSidesPolicy,
+        date_formats: &[String],
+    ) -> Result<Vec<Transaction>, ParseError> {
+        let booking_date = date::parse_with_formats(&self.booking_date, date_formats)?;
+        let value_date: Option<Date> = None;
+        let sides = parse_amount_sides(
             self.debit_amount.as_deref(),
             self.credit_amount.as_deref(),
+            both_sides,
         )?;
         let description = self.transaction_purpose.unwrap_or_default();
-        let (counterparty, counterparty_name) =
-            extract_counterparty_account(&self.debit_account, &self.credit_account, our_account);
+        let (counterparty, counterparty_name) = extract_counterparty_account(
+            &self.debit_account,
+            &self.credit_account,
+            our_account,
+            our_name,
+        );
+        let operation_code: Option<OperationType> = if self.operation_type.is_empty() {
+            None
+        } else {
+            self.operation_type.parse().ok()
+        };
 
-        Ok(Transaction::new(
-            booking_date,
-            value_date,
-            amount,
-            direction,
-            description,
-            counterparty,
-            counterparty_name,
-        ))
+        let transactions = sides
+            .into_iter()
+            .map(|(amount, direction)| {
+                let mut tx = Transaction::new(
+                    booking_date,
+                    value_date,
+                    amount,
+                    direction,
+                    description.clone(),
+                    counterparty.clone(),
+                    counterparty_name.clone(),
+                );
+                tx.operation_code = operation_code.clone();
+                tx
+            })
+            .collect();
+
+        Ok(transactions)
     }
 }
 
@@ -142,22 +182,31 @@ impl CsvRecord {
 pub struct CsvFooter {
     opening_balance: Balance,
     closing_balance: Balance,
+    /// "Итого оборотов": `(дебет, кредит)`, если строка есть
+    turnovers: Option<(u64, u64)>,
 }
 
 impl CsvFooter {
-    fn from_string_records(rows: &[StringRecord]) -> Result<Self, ParseError> {
+    fn from_string_records(
+        rows: &[StringRecord],
+        account_kind: AccountKind,
+    ) -> Result<Self, ParseError> {
         let mut opening: Option<Balance> = None;
         let mut closing: Option<Balance> = None;
+        let mut turnovers: Option<(u64, u64)> = None;
 
         for row in rows {
             let title = row.get(1).unwrap_or("").trim();
 
             match title {
                 "Входящий остаток" => {
-                    opening = Some(parse_footer_balance(row)?);
+                    opening = Some(parse_footer_balance(row, account_kind)?);
                 }
                 "Исходящий остаток" => {
-                    closing = Some(parse_footer_balance(row)?);
+                    closing = Some(parse_footer_balance(row, account_kind)?);
+                }
+                "Итого оборотов" => {
+                    turnovers = Some(parse_footer_turnovers(row)?);
                 }
                 _ => {}
             }
@@ -172,6 +221,7 @@ impl CsvFooter {
         Ok(CsvFooter {
             opening_balance,
             closing_balance,
+            turnovers,
         })
     }
 }
@@ -183,8 +233,7 @@ struct TableLayout {
     booking_date_col: usize,
     debit_account_col: usize,
     credit_account_col: usize,
-    debit_amount_col: usize,
-    credit_amount_col: usize,
+    amounts: AmountCols,
     doc_number_col: usize,
     operation_type_col: usize,
     bank_col: usize,
@@ -193,29 +242,47 @@ struct TableLayout {
 
 impl TableLayout {
     /// По паттернам строк определяет индексы необходимых колонок
+    ///
+    /// Без строки подзаголовков счета дебета и кредита ищутся в основной строке.
     fn from_string_records(
         headers_row: &StringRecord,
-        subheaders_row: &StringRecord,
+        subheaders_row: Option<&StringRecord>,
+        config: &CsvLayoutConfig,
     ) -> Result<Self, ParseError> {
         // первая строка заголовков - основные
         let booking_date_col = find_col(headers_row, "Дата проводки")?;
-        let debit_account_col = find_col(subheaders_row, "Дебет")?;
-        let credit_account_col = find_col(subheaders_row, "Кредит")?;
+        let (debit_account_col, credit_account_col) = match subheaders_row {
+            Some(row) => (find_col(row, "Дебет")?, find_col(row, "Кредит")?),
+            None => (
+                find_any_col(headers_row, &["Дебет", "Счет по дебету", "Счет дебета"])?,
+                find_any_col(headers_row, &["Кредит", "Счет по кредиту", "Счет кредита"])?,
+            ),
+        };
         let doc_number_col = find_col(headers_row, "№ документа")?;
         let operation_type_col = find_col(headers_row, "ВО")?;
         let bank_col = find_col(headers_row, "Банк")?;
         let transaction_purpose_col = find_col(headers_row, "Назначение платежа")?;
 
-        // вторая строка с подзаголовками: под «Сумма» стоят "Дебет" и "Кредит"
-        let debit_amount_col = find_col(headers_row, "Сумма по дебету")?;
-        let credit_amount_col = find_col(headers_row, "Сумма по кредиту")?;
+        let split_cols = || -> Result<AmountCols, ParseError> {
+            Ok(AmountCols::DebitCredit {
+                debit: find_col(headers_row, "Сумма по дебету")?,
+                credit: find_col(headers_row, "Сумма по кредиту")?,
+            })
+        };
+        let signed_col = || find_col(headers_row, "Сумма").map(AmountCols::Signed);
+
+        let amounts = match config.amounts {
+            AmountLayout::DebitCredit => split_cols()?,
+            AmountLayout::Signed => signed_col()?,
+            // раздельные колонки в приоритете, одна "Сумма" - запасной вариант
+            AmountLayout::Auto => split_cols().or_else(|_| signed_col())?,
+        };
 
         Ok(TableLayout {
             booking_date_col,
             debit_account_col,
             credit_account_col,
-            debit_amount_col,
-            credit_amount_col,
+            amounts,
             doc_number_col,
             operation_type_col,
             bank_col,
@@ -224,6 +291,116 @@ impl TableLayout {
     }
 }
 
+/// Колонки сумм в таблице операций
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AmountCols {
+    /// раздельные колонки "Сумма по дебету" / "Сумма по кредиту"
+    DebitCredit { debit: usize, credit: usize },
+    /// одна колонка "Сумма" со знаком
+    Signed(usize),
+}
+
+/// Как в таблице операций записаны суммы
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmountLayout {
+    /// Определить по заголовкам: раздельные колонки, а если их нет - одна колонка "Сумма"
+    #[default]
+    Auto,
+    /// Раздельные колонки "Сумма по дебету" и "Сумма по кредиту"
+    DebitCredit,
+    /// Одна колонка "Сумма" со знаком: минус - дебет, иначе кредит
+    Signed,
+}
+
+/// Что делать со строкой, где заполнены и "Сумма по дебету", и "Сумма по кредиту"
+///
+/// Так бывает, например, во внутренних переводах.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BothSidesPolicy {
+    /// Вернуть [`ParseError::AmountSideConflict`]
+    #[default]
+    Error,
+    /// Одна транзакция на разницу сумм: кредит, если кредит не меньше дебета, иначе дебет
+    ///
+    /// С `verify_turnovers` обороты из подвала при этом не сойдутся.
+    Net,
+    /// Две транзакции: дебетовая и кредитовая
+    Split,
+}
+
+/// Есть ли под строкой заголовков таблицы строка подзаголовков "Дебет"/"Кредит"
+///
+/// Без неё колонки счетов дебета и кредита ищутся в основной строке заголовков
+/// (`Дебет`/`Кредит`, `Счет по дебету`/`Счет по кредиту`, `Счет дебета`/`Счет кредита`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubheaderRow {
+    /// Определить по содержимому: строка с ячейкой `Дебет` или `Кредит` - подзаголовки
+    #[default]
+    Auto,
+    /// Строка подзаголовков обязательна
+    Present,
+    /// Строки подзаголовков нет, данные идут сразу после заголовков
+    Absent,
+}
+
+/// Настройки раскладки колонок таблицы операций
+#[derive(Debug, Clone)]
+pub struct CsvLayoutConfig {
+    /// Раскладка колонок сумм
+    pub amounts: AmountLayout,
+    /// Форматы даты проводки (синтаксис chrono), пробуются по порядку;
+    /// по умолчанию `%d.%m.%Y`
+    pub date_formats: Vec<String>,
+    /// Форматы дат периода в шапке (синтаксис chrono); пустой список (по умолчанию) -
+    /// русская текстовая дата вида `01 января 2024 г.`
+    pub header_date_formats: Vec<String>,
+    /// Строка подзаголовков со счетами дебета и кредита
+    pub subheader_row: SubheaderRow,
+}
+
+impl Default for CsvLayoutConfig {
+    fn default() -> Self {
+        CsvLayoutConfig {
+            amounts: AmountLayout::default(),
+            date_formats: vec!["%d.%m.%Y".to_string()],
+            header_date_formats: Vec::new(),
+            subheader_row: SubheaderRow::default(),
+        }
+    }
+}
+
+/// Тип счёта по плану счетов: определяет знак остатков в футере
+///
+/// В футере выписки это отмечено индикатором `(А)` / `(П)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccountKind {
+    /// Активный счёт: дебетовый остаток отрицательный, кредитовый положительный
+    #[default]
+    Active,
+    /// Пассивный счёт: знаки остатков инвертируются
+    Passive,
+}
+
+/// Настройки парсинга CSV
+#[derive(Debug, Clone, Default)]
+pub struct CsvParseOptions {
+    /// Тип счёта, влияет на знак остатков из футера
+    pub account_kind: AccountKind,
+    /// Разделитель полей; `None` (по умолчанию) - определить по содержимому
+    /// среди `,`, `;` и табуляции
+    pub delimiter: Option<u8>,
+    /// Раскладка колонок таблицы операций
+    pub layout: CsvLayoutConfig,
+    /// Сверять суммы операций с "Итого оборотов" из подвала при преобразовании в
+    /// [`Statement`]; при расхождении возвращается [`ParseError::TurnoverMismatch`]
+    pub verify_turnovers: bool,
+    /// Обработка строк с заполненными суммами и по дебету, и по кредиту
+    pub both_sides: BothSidesPolicy,
+    /// Менять местами границы периода из шапки, если начало позже конца;
+    /// по умолчанию такой период - [`ParseError::BadInput`]
+    pub swap_inverted_period: bool,
+}
+
 /// Структура с сырыми данными формата CSV.
 ///
 /// Для парсинга используйте [`CsvData::parse`].
@@ -243,57 +420,193 @@ pub struct CsvData {
     header: CsvHeader,
     records: Vec<CsvRecord>,
     footer: CsvFooter,
+    verify_turnovers: bool,
+    both_sides: BothSidesPolicy,
+    date_formats: Vec<String>,
+    header_date_formats: Vec<String>,
+    swap_inverted_period: bool,
+
+    /// Некритичные замечания, собранные при парсинге
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl TryFrom<&str> for CsvData {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`CsvData::parse`]
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input.as_bytes())
+    }
+}
+
+impl TryFrom<String> for CsvData {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`CsvData::parse`]
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::try_from(input.as_str())
+    }
 }
 
 impl TryFrom<CsvData> for Statement {
     type Error = ParseError;
     fn try_from(data: CsvData) -> Result<Self, Self::Error> {
-        let account_id = data.header.client_account;
-        let account_name = Some(data.header.client_name);
-        let currency = parse_currency(&data.header.currency);
-        let opening_balance: Option<Balance> = Some(data.footer.opening_balance);
-        let closing_balance: Option<Balance> = Some(data.footer.closing_balance);
-        let period_from = data
-            .header
-            .period_from
-            .trim_start_matches("за период с")
-            .trim();
-        let period_until = data.header.period_until.trim_start_matches("по").trim();
-
-        let period_from = parse_rus_date(period_from)?;
-        let period_until = parse_rus_date(period_until)?;
-
-        let transactions = data
-            .records
-            .into_iter()
-            .map(|rec: CsvRecord| rec.into_transaction(&account_id))
-            .collect::<Result<Vec<Transaction>, ParseError>>()?;
+        statement_from_csv(data, false, None).map(|(statement, _)| statement)
+    }
+}
 
-        Ok(Statement::new(
-            account_id,
-            account_name,
-            currency,
-            opening_balance,
-            closing_balance,
-            transactions,
-            period_from,
-            period_until,
-        ))
+fn statement_from_csv(
+    data: CsvData,
+    lenient: bool,
+    our_account: Option<&str>,
+) -> Result<(Statement, RowErrors), ParseError> {
+    let account_id = data.header.client_account;
+    let our_account = our_account.unwrap_or(&account_id);
+    let account_name = Some(data.header.client_name);
+    let currency = parse_currency(&data.header.currency);
+    let opening_balance: Option<Balance> = Some(data.footer.opening_balance);
+    let closing_balance: Option<Balance> = Some(data.footer.closing_balance);
+    let period_from = data
+        .header
+        .period_from
+        .trim_start_matches("за период с")
+        .trim();
+    let period_until = data.header.period_until.trim_start_matches("по").trim();
+
+    let parse_header_date = |raw: &str| {
+        if data.header_date_formats.is_empty() {
+            parse_rus_date(raw)
+        } else {
+            date::parse_with_formats(raw, &data.header_date_formats)
+        }
+    };
+    let period_from = parse_header_date(period_from)?;
+    let period_until = parse_header_date(period_until)?;
+    let (period_from, period_until) =
+        ordered_period(period_from, period_until, data.swap_inverted_period)?;
+
+    let both_sides = data.both_sides;
+    let date_formats = &data.date_formats;
+    let (transactions, errors) = collect_transactions(
+        data.records.into_iter().map(|mut rec: CsvRecord| {
+            let location = rec.location.take();
+            rec.into_transactions(
+                our_account,
+                account_name.as_deref(),
+                both_sides,
+                date_formats,
+            )
+            .map_err(|err| match location {
+                Some((line, excerpt)) => err.at_line(line, excerpt),
+                None => err,
+            })
+        }),
+        lenient,
+    )?;
+    let transactions: Vec<Transaction> = transactions.into_iter().flatten().collect();
+
+    // при пропущенных строках обороты заведомо не сойдутся
+    if data.verify_turnovers
+        && errors.is_empty()
+        && let Some(turnovers) = data.footer.turnovers
+    {
+        verify_turnovers(&transactions, turnovers)?;
+    }
+
+    let mut statement = Statement::new(
+        account_id,
+        account_name,
+        currency,
+        opening_balance,
+        closing_balance,
+        transactions,
+        period_from,
+        period_until,
+    );
+
+    statement.source_format = Some(SourceFormat::Csv);
+
+    Ok((statement, errors))
+}
+
+/// Сверяет суммы дебетовых и кредитовых операций с оборотами из подвала
+fn verify_turnovers(
+    transactions: &[Transaction],
+    (footer_debit, footer_credit): (u64, u64),
+) -> Result<(), ParseError> {
+    let mut rows_debit = 0u64;
+    let mut rows_credit = 0u64;
+    for tx in transactions {
+        match tx.direction {
+            Direction::Debit => rows_debit = rows_debit.saturating_add(tx.amount),
+            Direction::Credit => rows_credit = rows_credit.saturating_add(tx.amount),
+        }
+    }
+
+    if (rows_debit, rows_credit) != (footer_debit, footer_credit) {
+        return Err(ParseError::TurnoverMismatch {
+            footer_debit,
+            footer_credit,
+            rows_debit,
+            rows_credit,
+        });
     }
+
+    Ok(())
 }
 
 impl CsvData {
+    /// Мягкое преобразование в [`Statement`]: строки с ошибками пропускаются
+    ///
+    /// Возвращает выписку из корректных строк и список `(индекс записи, ошибка)`
+    /// для пропущенных. Ошибки шапки и подвала по-прежнему фатальны
+    pub fn try_into_statement_lenient(
+        self,
+    ) -> Result<(Statement, Vec<(usize, ParseError)>), ParseError> {
+        statement_from_csv(self, true, None)
+    }
+
+    /// Преобразование в [`Statement`] с явно заданным номером нашего счёта
+    ///
+    /// По умолчанию свой счёт в таблице операций ищется по номеру из шапки.
+    /// Если в шапке он записан иначе, чем в строках таблицы, контрагенты не
+    /// определяются; `our_account` задаёт номер в том виде, как он встречается
+    /// в таблице. [`Statement::account_id`] по-прежнему берётся из шапки.
+    pub fn into_statement_with_account(self, our_account: &str) -> Result<Statement, ParseError> {
+        statement_from_csv(self, false, Some(our_account)).map(|(statement, _)| statement)
+    }
+
     /// Парсит при помощи переданного reader данные  в [`CsvData`]
     ///
     /// При ошибке возвращает [`ParseError`]
     pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
-        let mut rdr = ReaderBuilder::new().has_headers(false).from_reader(reader);
+        Self::parse_with_options(reader, &CsvParseOptions::default())
+    }
+
+    /// То же, что [`CsvData::parse`], но с дополнительными настройками
+    pub fn parse_with_options<R: Read>(
+        mut reader: R,
+        options: &CsvParseOptions,
+    ) -> Result<Self, ParseError> {
+        // все записи всё равно собираются в память, так что читаем целиком:
+        // это нужно, чтобы заглянуть в начало файла и выбрать разделитель
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+        let input = strip_utf8_bom(&input);
+        let delimiter = options.delimiter.unwrap_or_else(|| sniff_delimiter(input));
+
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(delimiter)
+            .from_reader(input);
 
         let mut header_rows: Vec<StringRecord> = Vec::new();
         let mut data_rows: Vec<StringRecord> = Vec::new();
         let mut footer_rows: Vec<StringRecord> = Vec::new();
 
         let mut in_data_section = false;
+        // следующая после заголовков строка может оказаться подзаголовками
+        let mut expect_subheaders = false;
 
         // строки с заголовками
         let mut headers_row: Option<StringRecord> = None;
@@ -309,20 +622,20 @@ impl CsvData {
                 // если наткнулись на заголовки таблицы - значит, заголовок файла закончился
                 if record.iter().any(|field| field.contains("Дата проводки")) {
                     headers_row = Some(record);
-                    if let Some(next_result) = records_iter.next() {
-                        let r = next_result?;
-                        subheaders_row = Some(r);
-                    } else {
-                        return Err(ParseError::Header(
-                            "unexpected EOF: second header row missing".into(),
-                        ));
-                    }
-
+                    expect_subheaders = options.layout.subheader_row != SubheaderRow::Absent;
                     in_data_section = true;
                 } else {
                     header_rows.push(record);
                 }
             } else {
+                if std::mem::take(&mut expect_subheaders)
+                    && (options.layout.subheader_row == SubheaderRow::Present
+                        || is_subheaders_row(&record))
+                {
+                    subheaders_row = Some(record);
+                    continue;
+                }
+
                 // footer
                 if is_footer_row(&record) {
                     footer_rows.push(record);
@@ -340,15 +653,30 @@ impl CsvData {
 
         let headers_row =
             headers_row.ok_or_else(|| ParseError::Header("table headers row not found".into()))?;
-        let subheaders_row = subheaders_row
-            .ok_or_else(|| ParseError::Header("table subheaders row not found".into()))?;
+        if options.layout.subheader_row == SubheaderRow::Present && subheaders_row.is_none() {
+            return Err(ParseError::Header("table subheaders row not found".into()));
+        }
 
         if footer_rows.is_empty() {
             return Err(ParseError::Header("footer rows not found".into()));
         }
 
         let header = CsvHeader::from_string_records(&header_rows)?;
-        let layout = TableLayout::from_string_records(&headers_row, &subheaders_row)?;
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            subheaders_row.as_ref(),
+            &options.layout,
+        )?;
+
+        // исходные строки файла нужны только для отрывков в ошибках
+        let text = String::from_utf8_lossy(input);
+        let lines: Vec<&str> = text.lines().collect();
+        let numbered_lines = |first: usize, last: usize| {
+            // строки записи и по одной соседней: номер с единицы, индекс в lines - с нуля
+            let from = first.saturating_sub(2);
+            let until = (last + 1).min(lines.len());
+            (from..until).map(|idx| (idx + 1, lines[idx]))
+        };
 
         let mut records = Vec::new();
         for row in data_rows {
@@ -356,16 +684,49 @@ impl CsvData {
                 continue;
             }
 
-            let rec = CsvRecord::from_string_record(&row, &layout);
+            let mut rec = CsvRecord::from_string_record(&row, &layout);
+            rec.location = row.position().map(|pos| {
+                // запись может занимать несколько строк: переводы строк внутри кавычек
+                let first = pos.line() as usize;
+                let last = first + row.iter().map(|f| f.matches('\n').count()).sum::<usize>();
+                (
+                    first,
+                    excerpt_around(numbered_lines(first, last), first..=last),
+                )
+            });
             records.push(rec);
         }
 
-        let footer = CsvFooter::from_string_records(&footer_rows)?;
+        let footer = CsvFooter::from_string_records(&footer_rows, options.account_kind)?;
+
+        let mut warnings = Vec::new();
+        let statement_currency = parse_currency(&header.currency);
+        for (i, rec) in records.iter().enumerate() {
+            let amounts = [rec.debit_amount.as_deref(), rec.credit_amount.as_deref()];
+            for amount in amounts.into_iter().flatten() {
+                if let (_, Some(currency)) = split_amount_currency(amount)
+                    && currency != statement_currency
+                {
+                    warnings.push(ParseWarning::AmountCurrencyMismatch {
+                        record: i,
+                        amount: amount.to_string(),
+                        currency,
+                        statement_currency: statement_currency.clone(),
+                    });
+                }
+            }
+        }
 
         Ok(CsvData {
             header,
             records,
             footer,
+            verify_turnovers: options.verify_turnovers,
+            both_sides: options.both_sides,
+            date_formats: options.layout.date_formats.clone(),
+            header_date_formats: options.layout.header_date_formats.clone(),
+            swap_inverted_period: options.swap_inverted_period,
+            warnings,
         })
     }
 }
@@ -377,6 +738,10 @@ mod tests {
     use chrono::NaiveDate;
     use csv::StringRecord;
 
+    fn default_date_formats() -> Vec<String> {
+        CsvLayoutConfig::default().date_formats
+    }
+
     // CsvHeader
 
     #[test]
@@ -508,20 +873,58 @@ mod tests {
             StringRecord::from(v)
         };
 
-        let layout = TableLayout::from_string_records(&headers_row, &subheaders_row)
-            .expect("layout must succeed");
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            Some(&subheaders_row),
+            &CsvLayoutConfig::default(),
+        )
+        .expect("layout must succeed");
 
         assert_eq!(layout.booking_date_col, 0);
         assert_eq!(layout.doc_number_col, 1);
         assert_eq!(layout.operation_type_col, 2);
         assert_eq!(layout.bank_col, 3);
-        assert_eq!(layout.debit_amount_col, 4);
-        assert_eq!(layout.credit_amount_col, 5);
+        assert_eq!(
+            layout.amounts,
+            AmountCols::DebitCredit {
+                debit: 4,
+                credit: 5
+            }
+        );
         assert_eq!(layout.transaction_purpose_col, 6);
         assert_eq!(layout.debit_account_col, 1);
         assert_eq!(layout.credit_account_col, 2);
     }
 
+    #[test]
+    fn table_layout_without_subheaders_uses_main_header() {
+        let headers_row = StringRecord::from(vec![
+            "Дата проводки",
+            "Счет по дебету",
+            "Счет по кредиту",
+            "Сумма по дебету",
+            "Сумма по кредиту",
+            "№ документа",
+            "ВО",
+            "Банк",
+            "Назначение платежа",
+        ]);
+
+        let layout =
+            TableLayout::from_string_records(&headers_row, None, &CsvLayoutConfig::default())
+                .expect("layout must succeed");
+
+        assert_eq!(layout.debit_account_col, 1);
+        assert_eq!(layout.credit_account_col, 2);
+        assert_eq!(
+            layout.amounts,
+            AmountCols::DebitCredit {
+                debit: 3,
+                credit: 4
+            }
+        );
+    }
+
     #[test]
     fn csv_record_from_string_record_extracts_trimmed_fields() {
         // layout из предыдущего теста
@@ -542,8 +945,12 @@ mod tests {
             v[2] = "Кредит".to_string();
             StringRecord::from(v)
         };
-        let layout = TableLayout::from_string_records(&headers_row, &subheaders_row)
-            .expect("layout must succeed");
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            Some(&subheaders_row),
+            &CsvLayoutConfig::default(),
+        )
+        .expect("layout must succeed");
 
         let row = {
             let mut v = vec![String::new(); 7];
@@ -588,8 +995,12 @@ mod tests {
             v[2] = "Кредит".to_string();
             StringRecord::from(v)
         };
-        let layout = TableLayout::from_string_records(&headers_row, &subheaders_row)
-            .expect("layout must succeed");
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            Some(&subheaders_row),
+            &CsvLayoutConfig::default(),
+        )
+        .expect("layout must succeed");
 
         // одна строка таблицы
         let row = {
@@ -606,8 +1017,14 @@ mod tests {
 
         let rec = CsvRecord::from_string_record(&row, &layout);
         let tx = rec
-            .into_transaction("OUR_ACC")
-            .expect("into_transaction must succeed");
+            .into_transactions(
+                "OUR_ACC",
+                None,
+                BothSidesPolicy::Error,
+                &default_date_formats(),
+            )
+            .expect("into_transactions must succeed")
+            .remove(0);
 
         assert_eq!(
             tx.booking_date,
@@ -619,6 +1036,116 @@ mod tests {
         assert_eq!(tx.description, "Платёж контрагенту");
     }
 
+    #[test]
+    fn single_signed_amount_column_is_auto_detected() {
+        let headers_row = StringRecord::from(vec![
+            "Дата проводки",
+            "№ документа",
+            "ВО",
+            "Банк",
+            "Сумма",
+            "Назначение платежа",
+        ]);
+        let subheaders_row = StringRecord::from(vec!["", "Дебет", "Кредит", "", "", ""]);
+
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            Some(&subheaders_row),
+            &CsvLayoutConfig::default(),
+        )
+        .expect("layout must succeed");
+        assert_eq!(layout.amounts, AmountCols::Signed(4));
+
+        // раздельные колонки явно требуются, но их нет
+        let strict = CsvLayoutConfig {
+            amounts: AmountLayout::DebitCredit,
+            ..Default::default()
+        };
+        assert!(
+            TableLayout::from_string_records(&headers_row, Some(&subheaders_row), &strict).is_err()
+        );
+
+        let row = |amount: &str| {
+            StringRecord::from(vec!["10.01.2023", "OUR", "CP", "БАНК", amount, "Оплата"])
+        };
+
+        let credit = CsvRecord::from_string_record(&row("+100"), &layout)
+            .into_transactions("OUR", None, BothSidesPolicy::Error, &default_date_formats())
+            .unwrap()
+            .remove(0);
+        assert_eq!(credit.direction, Direction::Credit);
+        assert_eq!(credit.amount, 10_000);
+
+        let debit = CsvRecord::from_string_record(&row("-100"), &layout)
+            .into_transactions("OUR", None, BothSidesPolicy::Error, &default_date_formats())
+            .unwrap()
+            .remove(0);
+        assert_eq!(debit.direction, Direction::Debit);
+        assert_eq!(debit.amount, 10_000);
+    }
+
+    #[test]
+    fn both_filled_amounts_follow_policy() {
+        let headers_row = StringRecord::from(vec![
+            "Дата проводки",
+            "№ документа",
+            "ВО",
+            "Банк",
+            "Сумма по дебету",
+            "Сумма по кредиту",
+            "Назначение платежа",
+        ]);
+        let subheaders_row = StringRecord::from(vec!["", "Дебет", "Кредит", "", "", "", ""]);
+        let layout = TableLayout::from_string_records(
+            &headers_row,
+            Some(&subheaders_row),
+            &CsvLayoutConfig::default(),
+        )
+        .expect("layout must succeed");
+
+        let row = StringRecord::from(vec![
+            "10.01.2023",
+            "OUR",
+            "OUR",
+            "БАНК",
+            "30.00",
+            "100.00",
+            "Внутренний перевод",
+        ]);
+        let convert = |policy| {
+            CsvRecord::from_string_record(&row, &layout).into_transactions(
+                "OUR",
+                None,
+                policy,
+                &default_date_formats(),
+            )
+        };
+
+        assert!(matches!(
+            convert(BothSidesPolicy::Error),
+            Err(ParseError::AmountSideConflict)
+        ));
+
+        let net = convert(BothSidesPolicy::Net).unwrap();
+        assert_eq!(net.len(), 1);
+        assert_eq!(
+            (net[0].amount, net[0].direction),
+            (7_000, Direction::Credit)
+        );
+
+        let split = convert(BothSidesPolicy::Split).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(
+            (split[0].amount, split[0].direction),
+            (3_000, Direction::Debit)
+        );
+        assert_eq!(
+            (split[1].amount, split[1].direction),
+            (10_000, Direction::Credit)
+        );
+        assert_eq!(split[1].description, "Внутренний перевод");
+    }
+
     // CsvFooter
 
     #[test]
@@ -637,13 +1164,40 @@ mod tests {
             StringRecord::from(v)
         };
 
-        let footer = CsvFooter::from_string_records(&[opening_row, closing_row])
-            .expect("footer parse must succeed");
+        let footer =
+            CsvFooter::from_string_records(&[opening_row, closing_row], AccountKind::Active)
+                .expect("footer parse must succeed");
 
         assert_eq!(footer.opening_balance, 10_000);
         assert_eq!(footer.closing_balance, 15_000);
     }
 
+    #[test]
+    fn csv_footer_passive_account_inverts_balance_signs() {
+        let opening_row = {
+            let mut v = vec![String::new(); 21];
+            v[1] = "Входящий остаток".to_string();
+            v[11] = "100.00".to_string();
+            v[17] = "(П)".to_string();
+            StringRecord::from(v)
+        };
+
+        let closing_row = {
+            let mut v = vec![String::new(); 21];
+            v[1] = "Исходящий остаток".to_string();
+            v[7] = "50.00".to_string();
+            v[17] = "(П)".to_string();
+            StringRecord::from(v)
+        };
+
+        let footer =
+            CsvFooter::from_string_records(&[opening_row, closing_row], AccountKind::Passive)
+                .expect("footer parse must succeed");
+
+        assert_eq!(footer.opening_balance, -10_000);
+        assert_eq!(footer.closing_balance, 5_000);
+    }
+
     #[test]
     fn csv_footer_errors_if_balances_missing() {
         let row = {
@@ -652,7 +1206,7 @@ mod tests {
             StringRecord::from(v)
         };
 
-        let err = CsvFooter::from_string_records(&[row]).unwrap_err();
+        let err = CsvFooter::from_string_records(&[row], AccountKind::Active).unwrap_err();
         match err {
             ParseError::Header(msg) => {
                 assert!(
@@ -1,10 +1,34 @@
+use super::{AccountKind, BothSidesPolicy};
+use crate::date::{self, Date};
 use crate::error::ParseError;
-use crate::model::{Balance, Direction};
-use crate::utils::parse_amount;
-use chrono::NaiveDate;
-use csv::StringRecord;
+use crate::model::{Balance, Currency, Direction};
+use crate::utils::{parse_amount, parse_currency};
+use csv::{ReaderBuilder, StringRecord};
+use std::collections::HashMap;
 
-pub(super) fn parse_footer_balance(row: &StringRecord) -> Result<Balance, ParseError> {
+/// Разделители, среди которых выбирает [`sniff_delimiter`]
+const DELIMITER_CANDIDATES: [u8; 3] = [b',', b';', b'\t'];
+
+/// Сколько первых записей смотреть при определении разделителя
+const SNIFF_RECORDS: usize = 20;
+
+/// Разбирает баланс из строки футера.
+///
+/// Для активного счёта дебетовый остаток считается отрицательным, кредитовый - положительным,
+/// для пассивного - наоборот.
+pub(super) fn parse_footer_balance(
+    row: &StringRecord,
+    account_kind: AccountKind,
+) -> Result<Balance, ParseError> {
+    let balance = parse_active_footer_balance(row)?;
+
+    Ok(match account_kind {
+        AccountKind::Active => balance,
+        AccountKind::Passive => -balance,
+    })
+}
+
+fn parse_active_footer_balance(row: &StringRecord) -> Result<Balance, ParseError> {
     let debit_raw = row.get(7).map(str::trim).unwrap_or("");
     let credit_raw = row.get(11).map(str::trim).unwrap_or("");
 
@@ -34,6 +58,21 @@ pub(super) fn parse_footer_balance(row: &StringRecord) -> Result<Balance, ParseE
     }
 }
 
+/// Обороты из строки "Итого оборотов": `(дебет, кредит)` в "копейках"
+///
+/// Пустая колонка считается нулём.
+pub(super) fn parse_footer_turnovers(row: &StringRecord) -> Result<(u64, u64), ParseError> {
+    let parse_col = |idx: usize| -> Result<u64, ParseError> {
+        let raw = row.get(idx).map(str::trim).unwrap_or("");
+        if raw.is_empty() {
+            return Ok(0);
+        }
+        parse_amount(&raw.replace(',', "."))
+    };
+
+    Ok((parse_col(7)?, parse_col(11)?))
+}
+
 /// Возвращает:
 /// - 1-ю непустую строку как номер счёта
 /// - 3-ю непустую строку как имя контрагента
@@ -50,33 +89,111 @@ pub(super) fn extract_account_and_name(block: &str) -> (Option<String>, Option<S
     (account, name)
 }
 
+/// Минимальная длина хвоста счёта, по которому счета ещё считаются совпадающими
+const MIN_ACCOUNT_SUFFIX: usize = 8;
+
+/// Оставляет в строке только буквы и цифры в верхнем регистре
+fn normalize_alnum(raw: &str) -> String {
+    raw.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Нестрогое сравнение счетов: без пробелов и дефисов, а если длина разная -
+/// по хвосту длиной хотя бы [`MIN_ACCOUNT_SUFFIX`] символов
+pub(super) fn accounts_match(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_alnum(a), normalize_alnum(b));
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+
+    let (short, long) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    short == long || (short.len() >= MIN_ACCOUNT_SUFFIX && long.ends_with(short.as_str()))
+}
+
 /// Определяет счёт и имя контрагента:
 /// - если наш счёт в дебете - контрагент = (счёт, имя) из кредитового блока
 /// - если наш счёт в кредите - контрагент = (счёт, имя) из дебетового блока
+/// - иначе - то же по нестрогому совпадению счёта ([`accounts_match`]),
+///   а затем по совпадению имени владельца `our_name`
 /// - иначе - (None, None)
 pub(super) fn extract_counterparty_account(
     debit_block: &str,
     credit_block: &str,
     our_account: &str,
+    our_name: Option<&str>,
 ) -> (Option<String>, Option<String>) {
     let (debit_acc, debit_name) = extract_account_and_name(debit_block);
     let (credit_acc, credit_name) = extract_account_and_name(credit_block);
 
-    // наш счёт в дебете - к нам пришли деньги
-    if let Some(acc) = debit_acc.as_deref()
-        && acc == our_account
-    {
-        return (credit_acc, credit_name);
+    let exact = |acc: &Option<String>| acc.as_deref() == Some(our_account);
+    let fuzzy = |acc: &Option<String>| {
+        acc.as_deref()
+            .is_some_and(|acc| accounts_match(acc, our_account))
+    };
+    let by_name = |name: &Option<String>| match (name.as_deref(), our_name) {
+        (Some(name), Some(our_name)) => {
+            let (name, our_name) = (normalize_alnum(name), normalize_alnum(our_name));
+            !name.is_empty() && name == our_name
+        }
+        _ => false,
+    };
+
+    // наш счёт в дебете - к нам пришли деньги, в кредите - от нас ушли
+    let ours_is_debit = if exact(&debit_acc) || exact(&credit_acc) {
+        exact(&debit_acc)
+    } else if fuzzy(&debit_acc) != fuzzy(&credit_acc) {
+        fuzzy(&debit_acc)
+    } else if by_name(&debit_name) != by_name(&credit_name) {
+        by_name(&debit_name)
+    } else {
+        return (None, None);
+    };
+
+    if ours_is_debit {
+        (credit_acc, credit_name)
+    } else {
+        (debit_acc, debit_name)
+    }
+}
+
+/// Отделяет от суммы завершающий символ или код валюты: "100,00 ₽" -> ("100,00", Some(RUB))
+///
+/// Срезаются только известные символы (₽, $, €, ¥), известные названия и
+/// 3-буквенные ISO-коды, чтобы не прятать действительно битые значения.
+pub(super) fn split_amount_currency(raw: &str) -> (&str, Option<Currency>) {
+    let raw = raw.trim();
+
+    let symbols = [
+        ("₽", Currency::RUB),
+        ("$", Currency::USD),
+        ("€", Currency::EUR),
+        ("¥", Currency::CNY),
+    ];
+    for (symbol, currency) in symbols {
+        if let Some(amount) = raw.strip_suffix(symbol) {
+            return (amount.trim_end(), Some(currency));
+        }
     }
 
-    // наш счёт в кредите - от нас ушли деньги
-    if let Some(acc) = credit_acc.as_deref()
-        && acc == our_account
-    {
-        return (debit_acc, debit_name);
+    let amount = raw.trim_end_matches(|c: char| c.is_alphabetic() || c == '.');
+    let code = &raw[amount.len()..];
+    let amount = amount.trim_end();
+
+    if amount.is_empty() || !code.chars().any(char::is_alphabetic) {
+        return (raw, None);
     }
 
-    (None, None)
+    let is_iso_code = code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase());
+    match parse_currency(code) {
+        Currency::Other(_) if !is_iso_code => (raw, None),
+        currency => (amount, Some(currency)),
+    }
 }
 
 pub(super) fn parse_amount_and_direction(
@@ -94,13 +211,13 @@ pub(super) fn parse_amount_and_direction(
     match (debit, credit) {
         // дебет: значение есть и непустое, кредит пустой/отсутствует
         (Some(d), c) if !d.trim().is_empty() && is_empty(c) => {
-            let amount = parse_amount(d)?;
+            let amount = parse_amount(split_amount_currency(d).0)?;
             let direction = Direction::Debit;
             Ok((amount, direction))
         }
         // кредит: значение есть и непустое, дебет пустой/отсутствует
         (d, Some(c)) if !c.trim().is_empty() && is_empty(d) => {
-            let amount = parse_amount(c)?;
+            let amount = parse_amount(split_amount_currency(c).0)?;
             let direction = Direction::Credit;
             Ok((amount, direction))
         }
@@ -108,6 +225,93 @@ pub(super) fn parse_amount_and_direction(
     }
 }
 
+/// Как [`parse_amount_and_direction`], но строка с заполненными обеими суммами
+/// обрабатывается по `policy`
+///
+/// Возвращает одну сторону, а при [`BothSidesPolicy::Split`] - две: сначала дебет, потом кредит.
+pub(super) fn parse_amount_sides(
+    debit: Option<&str>,
+    credit: Option<&str>,
+    policy: BothSidesPolicy,
+) -> Result<Vec<(u64, Direction)>, ParseError> {
+    fn filled(val: Option<&str>) -> Option<&str> {
+        val.map(str::trim).filter(|s| !s.is_empty())
+    }
+    let parse = |raw: &str| parse_amount(split_amount_currency(raw).0);
+
+    match (policy, filled(debit), filled(credit)) {
+        (BothSidesPolicy::Net, Some(d), Some(c)) => {
+            let (debit, credit) = (parse(d)?, parse(c)?);
+            // нулевой итог считаем кредитом, как и сумму без знака
+            if credit >= debit {
+                Ok(vec![(credit - debit, Direction::Credit)])
+            } else {
+                Ok(vec![(debit - credit, Direction::Debit)])
+            }
+        }
+        (BothSidesPolicy::Split, Some(d), Some(c)) => Ok(vec![
+            (parse(d)?, Direction::Debit),
+            (parse(c)?, Direction::Credit),
+        ]),
+        _ => parse_amount_and_direction(debit, credit).map(|side| vec![side]),
+    }
+}
+
+/// Раскладывает сумму из колонки со знаком на (дебет, кредит)
+///
+/// Минус (в том числе `−`) - дебет, плюс или отсутствие знака - кредит.
+/// Пустое значение даёт `(None, None)`.
+pub(super) fn split_signed_amount(raw: &str) -> (Option<String>, Option<String>) {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return (None, None);
+    }
+
+    if let Some(abs) = raw.strip_prefix(['-', '−']) {
+        (Some(abs.trim().to_string()), None)
+    } else {
+        let abs = raw.strip_prefix('+').unwrap_or(raw);
+        (None, Some(abs.trim().to_string()))
+    }
+}
+
+/// Определяет разделитель полей по началу файла
+///
+/// Для каждого кандидата (`,`, `;`, `\t`) разбирает первые записи и считает,
+/// сколько из них имеют одинаковое число колонок (больше одной).
+/// Побеждает самый "ровный" вариант, при равенстве - с большим числом колонок,
+/// затем `,`.
+pub(super) fn sniff_delimiter(input: &[u8]) -> u8 {
+    // max_by_key берёт последний из равных, поэтому идём с конца: запятая выигрывает ничью
+    DELIMITER_CANDIDATES
+        .into_iter()
+        .rev()
+        .max_by_key(|delimiter| delimiter_score(input, *delimiter))
+        .unwrap_or(b',')
+}
+
+/// Оценка разделителя: (число записей с самым частым числом колонок, это число колонок)
+fn delimiter_score(input: &[u8], delimiter: u8) -> (usize, usize) {
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(input);
+
+    let mut widths: HashMap<usize, usize> = HashMap::new();
+    for record in rdr.records().take(SNIFF_RECORDS).map_while(Result::ok) {
+        if record.len() > 1 {
+            *widths.entry(record.len()).or_default() += 1;
+        }
+    }
+
+    widths
+        .into_iter()
+        .map(|(width, count)| (count, width))
+        .max()
+        .unwrap_or((0, 0))
+}
+
 pub(super) fn is_footer_row(row: &StringRecord) -> bool {
     row.iter().any(|field| {
         let field = field.trim();
@@ -119,6 +323,12 @@ pub(super) fn is_footer_row(row: &StringRecord) -> bool {
     })
 }
 
+/// Строка подзаголовков таблицы: в ней есть ячейка ровно `Дебет` или `Кредит`
+pub(super) fn is_subheaders_row(row: &StringRecord) -> bool {
+    row.iter()
+        .any(|field| matches!(field.trim(), "Дебет" | "Кредит"))
+}
+
 /// Ищет индекс колонки, содержащей текст
 ///
 /// Возвращает первый найденный, если не находит - возвращает ошибку
@@ -138,7 +348,19 @@ pub(super) fn find_col(row: &StringRecord, needle: &str) -> Result<usize, ParseE
     )))
 }
 
-pub(super) fn parse_rus_date(raw: &str) -> Result<NaiveDate, ParseError> {
+/// Как [`find_col`], но перебирает несколько вариантов заголовка по порядку
+pub(super) fn find_any_col(row: &StringRecord, needles: &[&str]) -> Result<usize, ParseError> {
+    needles
+        .iter()
+        .find_map(|needle| find_col(row, needle).ok())
+        .ok_or_else(|| {
+            ParseError::Header(format!(
+                "column with header equal to or containing any of {needles:?} not found"
+            ))
+        })
+}
+
+pub(super) fn parse_rus_date(raw: &str) -> Result<Date, ParseError> {
     let s = raw.trim();
     let s = s
         .trim_end_matches(|c: char| c.is_whitespace() || c == '.' || c == 'г')
@@ -178,8 +400,7 @@ pub(super) fn parse_rus_date(raw: &str) -> Result<NaiveDate, ParseError> {
         _ => return Err(ParseError::Header(format!("unknown month in date: {raw}"))),
     };
 
-    NaiveDate::from_ymd_opt(year, month, day)
-        .ok_or_else(|| ParseError::Header(format!("invalid date: {raw}")))
+    date::ymd(year, month, day).ok_or_else(|| ParseError::Header(format!("invalid date: {raw}")))
 }
 
 #[cfg(test)]
@@ -187,6 +408,25 @@ mod tests {
     use super::*;
     use csv::StringRecord;
 
+    #[test]
+    fn sniff_delimiter_picks_most_consistent_column_count() {
+        // запятые внутри полей не должны сбивать выбор
+        let semicolon = "a;b;c\n1,5;2;Оплата, в т.ч. НДС\n3;4;5\n";
+        assert_eq!(sniff_delimiter(semicolon.as_bytes()), b';');
+
+        let tab = "a\tb\tc\n1\t2\t3\n";
+        assert_eq!(sniff_delimiter(tab.as_bytes()), b'\t');
+
+        let comma = "a,b,c\n\"x;y\",2,3\n";
+        assert_eq!(sniff_delimiter(comma.as_bytes()), b',');
+    }
+
+    #[test]
+    fn sniff_delimiter_defaults_to_comma() {
+        assert_eq!(sniff_delimiter(b""), b',');
+        assert_eq!(sniff_delimiter(b"one column\nonly\n"), b',');
+    }
+
     // вспомогательные функции для тестов
 
     fn row_with_debit_credit(debit: &str, credit: &str) -> StringRecord {
@@ -197,12 +437,26 @@ mod tests {
         StringRecord::from(fields)
     }
 
+    // parse_footer_turnovers
+
+    #[test]
+    fn parse_footer_turnovers_reads_debit_and_credit() {
+        let row = row_with_debit_credit("152357.50", "157000,00");
+        assert_eq!(
+            parse_footer_turnovers(&row).unwrap(),
+            (15_235_750, 15_700_000)
+        );
+
+        let row = row_with_debit_credit("", "10.00");
+        assert_eq!(parse_footer_turnovers(&row).unwrap(), (0, 1_000));
+    }
+
     // parse_footer_balance
 
     #[test]
     fn parse_footer_balance_uses_debit_when_non_zero() {
         let row = row_with_debit_credit("100", "0,00");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         // дебетовая сумма в футере трактуется как отрицательный баланс
         assert_eq!(balance, -10000);
     }
@@ -210,7 +464,7 @@ mod tests {
     #[test]
     fn parse_footer_balance_uses_credit_when_debit_zero() {
         let row = row_with_debit_credit("0,00", "100");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         // кредитовая сумма = положительный баланс
         assert_eq!(balance, 10000);
     }
@@ -218,7 +472,7 @@ mod tests {
     #[test]
     fn parse_footer_balance_treats_zero_and_empty_as_zero() {
         let row = row_with_debit_credit("", "0.00");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, 0);
     }
 
@@ -226,7 +480,7 @@ mod tests {
     fn parse_footer_balance_handles_comma_fraction_in_debit() {
         // 100,50 в дебете -> -10050
         let row = row_with_debit_credit("100,50", "0,00");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, -10050);
     }
 
@@ -234,7 +488,7 @@ mod tests {
     fn parse_footer_balance_handles_dot_fraction_in_debit() {
         // 123.45 в дебете -> -12345
         let row = row_with_debit_credit("123.45", "0.00");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, -12345);
     }
 
@@ -242,7 +496,7 @@ mod tests {
     fn parse_footer_balance_handles_comma_fraction_in_credit() {
         // 250,75 в кредите -> +25075
         let row = row_with_debit_credit("0,00", "250,75");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, 25075);
     }
 
@@ -250,15 +504,28 @@ mod tests {
     fn parse_footer_balance_handles_dot_fraction_in_credit() {
         // 999.99 в кредите -> +99999
         let row = row_with_debit_credit("0.00", "999.99");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, 99999);
     }
 
+    #[test]
+    fn parse_footer_balance_flips_sign_for_passive_account() {
+        // для пассивного счёта кредитовый остаток - отрицательный
+        let row = row_with_debit_credit("0,00", "250,75");
+        let balance = parse_footer_balance(&row, AccountKind::Passive).unwrap();
+        assert_eq!(balance, -25075);
+
+        // а дебетовый - положительный
+        let row = row_with_debit_credit("100,50", "0,00");
+        let balance = parse_footer_balance(&row, AccountKind::Passive).unwrap();
+        assert_eq!(balance, 10050);
+    }
+
     #[test]
     fn parse_footer_balance_treats_both_empty_as_zero() {
         // обе колонки пустые/пробелы -> 0
         let row = row_with_debit_credit("   ", "   ");
-        let balance = parse_footer_balance(&row).unwrap();
+        let balance = parse_footer_balance(&row, AccountKind::Active).unwrap();
         assert_eq!(balance, 0);
     }
 
@@ -305,7 +572,7 @@ mod tests {
         "#;
 
         let (cp_acc, cp_name) =
-            extract_counterparty_account(debit_block, credit_block, our_account);
+            extract_counterparty_account(debit_block, credit_block, our_account, None);
 
         assert_eq!(cp_acc.as_deref(), Some("CP_ACC"));
         assert_eq!(cp_name.as_deref(), Some("Контрагент"));
@@ -328,7 +595,7 @@ mod tests {
         "#;
 
         let (cp_acc, cp_name) =
-            extract_counterparty_account(debit_block, credit_block, our_account);
+            extract_counterparty_account(debit_block, credit_block, our_account, None);
 
         assert_eq!(cp_acc.as_deref(), Some("CP_ACC"));
         assert_eq!(cp_name.as_deref(), Some("Контрагент"));
@@ -351,12 +618,47 @@ mod tests {
         "#;
 
         let (cp_acc, cp_name) =
-            extract_counterparty_account(debit_block, credit_block, our_account);
+            extract_counterparty_account(debit_block, credit_block, our_account, None);
 
         assert!(cp_acc.is_none());
         assert!(cp_name.is_none());
     }
 
+    #[test]
+    fn extract_counterparty_account_matches_account_with_different_spacing() {
+        let debit_block = "40702 810-4400 0003 0888\nИНН\nНаше юрлицо";
+        let credit_block = "40702810600014448120\nИНН\nКонтрагент";
+
+        let (cp_acc, cp_name) =
+            extract_counterparty_account(debit_block, credit_block, "40702810440000030888", None);
+
+        assert_eq!(cp_acc.as_deref(), Some("40702810600014448120"));
+        assert_eq!(cp_name.as_deref(), Some("Контрагент"));
+    }
+
+    #[test]
+    fn extract_counterparty_account_falls_back_to_owner_name() {
+        let debit_block = "CP_ACC\nИНН\nКонтрагент";
+        let credit_block = "OTHER_FORMAT\nИНН\nООО \"Ромашка\"";
+
+        let (cp_acc, cp_name) =
+            extract_counterparty_account(debit_block, credit_block, "OUR_ACC", Some("ООО Ромашка"));
+
+        assert_eq!(cp_acc.as_deref(), Some("CP_ACC"));
+        assert_eq!(cp_name.as_deref(), Some("Контрагент"));
+    }
+
+    #[test]
+    fn accounts_match_compares_long_enough_suffixes() {
+        assert!(accounts_match(
+            "DE89 3704 0044 0532 0130 00",
+            "DE89370400440532013000"
+        ));
+        assert!(accounts_match("0532013000", "DE89370400440532013000"));
+        assert!(!accounts_match("3000", "DE89370400440532013000"));
+        assert!(!accounts_match("", ""));
+    }
+
     // parse_amount_and_direction
 
     #[test]
@@ -380,6 +682,73 @@ mod tests {
         assert_eq!(res.1, Direction::Debit);
     }
 
+    #[test]
+    fn parse_amount_and_direction_strips_trailing_currency() {
+        let res = parse_amount_and_direction(Some("100,00 ₽"), None).unwrap();
+        assert_eq!(res, (10000, Direction::Debit));
+
+        let res = parse_amount_and_direction(None, Some("100.00 RUB")).unwrap();
+        assert_eq!(res, (10000, Direction::Credit));
+    }
+
+    // split_signed_amount
+
+    #[test]
+    fn split_signed_amount_uses_sign_for_direction() {
+        assert_eq!(split_signed_amount("+100"), (None, Some("100".to_string())));
+        assert_eq!(
+            split_signed_amount("100,00"),
+            (None, Some("100,00".to_string()))
+        );
+        assert_eq!(split_signed_amount("-100"), (Some("100".to_string()), None));
+        assert_eq!(
+            split_signed_amount(" − 5.50 "),
+            (Some("5.50".to_string()), None)
+        );
+        assert_eq!(split_signed_amount("  "), (None, None));
+    }
+
+    #[test]
+    fn split_signed_amount_feeds_parse_amount_and_direction() {
+        let (d, c) = split_signed_amount("-100");
+        let res = parse_amount_and_direction(d.as_deref(), c.as_deref()).unwrap();
+        assert_eq!(res, (10_000, Direction::Debit));
+
+        let (d, c) = split_signed_amount("+100");
+        let res = parse_amount_and_direction(d.as_deref(), c.as_deref()).unwrap();
+        assert_eq!(res, (10_000, Direction::Credit));
+    }
+
+    // split_amount_currency
+
+    #[test]
+    fn split_amount_currency_recognizes_symbols_and_codes() {
+        assert_eq!(
+            split_amount_currency("100,00 ₽"),
+            ("100,00", Some(Currency::RUB))
+        );
+        assert_eq!(
+            split_amount_currency("100.00 RUB"),
+            ("100.00", Some(Currency::RUB))
+        );
+        assert_eq!(
+            split_amount_currency("1 500,00 руб."),
+            ("1 500,00", Some(Currency::RUB))
+        );
+        assert_eq!(
+            split_amount_currency("5.00 GBP"),
+            ("5.00", Some(Currency::Other("GBP".to_string())))
+        );
+        assert_eq!(split_amount_currency("100,00"), ("100,00", None));
+    }
+
+    #[test]
+    fn split_amount_currency_keeps_unknown_suffix() {
+        // мусор не срезаем - пусть parse_amount вернёт ошибку
+        assert_eq!(split_amount_currency("100abc"), ("100abc", None));
+        assert_eq!(split_amount_currency("RUB"), ("RUB", None));
+    }
+
     #[test]
     fn parse_amount_and_direction_conflict_both_sides_filled() {
         let res = parse_amount_and_direction(Some("100"), Some("200"));
@@ -392,6 +761,31 @@ mod tests {
         assert!(matches!(res, Err(ParseError::AmountSideConflict)));
     }
 
+    #[test]
+    fn parse_amount_sides_nets_to_larger_side() {
+        let res = parse_amount_sides(Some("200"), Some("50"), BothSidesPolicy::Net).unwrap();
+        assert_eq!(res, vec![(15_000, Direction::Debit)]);
+
+        let res = parse_amount_sides(Some("50"), Some("50"), BothSidesPolicy::Net).unwrap();
+        assert_eq!(res, vec![(0, Direction::Credit)]);
+    }
+
+    #[test]
+    fn parse_amount_sides_split_and_single_side() {
+        let res = parse_amount_sides(Some("200"), Some("50"), BothSidesPolicy::Split).unwrap();
+        assert_eq!(
+            res,
+            vec![(20_000, Direction::Debit), (5_000, Direction::Credit)]
+        );
+
+        // одна сторона - политика не влияет
+        let res = parse_amount_sides(Some(""), Some("50"), BothSidesPolicy::Split).unwrap();
+        assert_eq!(res, vec![(5_000, Direction::Credit)]);
+
+        let res = parse_amount_sides(Some("1"), Some("2"), BothSidesPolicy::Error);
+        assert!(matches!(res, Err(ParseError::AmountSideConflict)));
+    }
+
     // is_footer_row
 
     #[test]
@@ -443,28 +837,28 @@ mod tests {
     #[test]
     fn parse_rus_date_parses_normal_russian_date_with_g_dot() {
         let d = parse_rus_date("01 января 2023 г.").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2023, 1, 1).unwrap());
+        assert_eq!(d, crate::date::ymd(2023, 1, 1).unwrap());
     }
 
     #[test]
     fn parse_rus_date_parses_without_g_or_dot_and_with_extra_spaces() {
         let d = parse_rus_date("  31 декабря 1999   ").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(1999, 12, 31).unwrap());
+        assert_eq!(d, crate::date::ymd(1999, 12, 31).unwrap());
 
         let d = parse_rus_date("31 декабря 1999 г").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(1999, 12, 31).unwrap());
+        assert_eq!(d, crate::date::ymd(1999, 12, 31).unwrap());
 
         let d = parse_rus_date("31 декабря 1999.   ").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(1999, 12, 31).unwrap());
+        assert_eq!(d, crate::date::ymd(1999, 12, 31).unwrap());
     }
 
     #[test]
     fn parse_rus_date_parses_with_mixed_case_month() {
         let d = parse_rus_date("15 Мая 2020 г.").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2020, 5, 15).unwrap());
+        assert_eq!(d, crate::date::ymd(2020, 5, 15).unwrap());
 
         let d = parse_rus_date("15 МАЯ 2020").unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2020, 5, 15).unwrap());
+        assert_eq!(d, crate::date::ymd(2020, 5, 15).unwrap());
     }
 
     #[test]
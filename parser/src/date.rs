@@ -0,0 +1,259 @@
+//! Граница между крейтом и библиотекой дат.
+//!
+//! Весь разбор, форматирование и арифметика дат идут через функции этого
+//! модуля, а в публичном API используется псевдоним [`Date`]. Бэкенд пока
+//! один - chrono; фичи для крейта `time` нет. Чтобы перейти на другой
+//! бэкенд, достаточно поменять реализацию здесь.
+
+use crate::error::ParseError;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, Utc};
+
+/// Календарная дата без времени и часового пояса
+pub type Date = chrono::NaiveDate;
+
+/// Момент времени (UTC) для служебных полей при записи выписок
+pub type Timestamp = chrono::DateTime<Utc>;
+
+/// Дата по году, месяцу и дню; `None`, если такой даты нет
+///
+/// ```
+/// let date = parser::ymd(2024, 2, 29).unwrap();
+/// assert_eq!(date.to_string(), "2024-02-29");
+/// assert!(parser::ymd(2023, 2, 29).is_none());
+/// ```
+pub fn ymd(year: i32, month: u32, day: u32) -> Option<Date> {
+    Date::from_ymd_opt(year, month, day)
+}
+
+/// Число дней от `from` до `to`; отрицательное, если `to` раньше
+pub(crate) fn days_between(from: Date, to: Date) -> i64 {
+    (to - from).num_days()
+}
+
+/// Дата через `days` дней; `None` при выходе за поддерживаемый диапазон
+#[cfg(any(test, feature = "testkit"))]
+pub(crate) fn add_days(date: Date, days: u64) -> Option<Date> {
+    date.checked_add_days(chrono::Days::new(days))
+}
+
+/// Год даты
+pub(crate) fn year(date: Date) -> i32 {
+    date.year()
+}
+
+/// Месяц даты (1..=12)
+pub(crate) fn month(date: Date) -> u32 {
+    date.month()
+}
+
+/// День месяца (1..=31)
+pub(crate) fn day(date: Date) -> u32 {
+    date.day()
+}
+
+/// Разбирает дату, пробуя форматы chrono (`%Y-%m-%d` и т.п.) по порядку
+///
+/// Возвращает ошибку последнего формата, если не подошёл ни один.
+pub(crate) fn parse_with_formats<S: AsRef<str>>(
+    raw: &str,
+    formats: &[S],
+) -> Result<Date, ParseError> {
+    let mut last_err = None;
+    for format in formats {
+        match Date::parse_from_str(raw, format.as_ref()) {
+            Ok(date) => return Ok(date),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(match last_err {
+        Some(err) => err.into(),
+        None => ParseError::BadInput(format!("no date formats to parse '{raw}'")),
+    })
+}
+
+/// Разбирает ISO-дату `YYYY-MM-DD` или дату-время `YYYY-MM-DDTHH:MM:SS`
+pub(crate) fn parse_iso_date(raw: &str) -> Result<Date, ParseError> {
+    if let Ok(date) = Date::parse_from_str(raw, "%Y-%m-%d") {
+        return Ok(date);
+    }
+
+    Ok(NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S")?.date())
+}
+
+/// Разбирает ISO-дату или дату-время, в том числе с часовым поясом
+///
+/// Понимает `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM:SS[.fff]` и RFC 3339 со смещением
+/// (`2023-04-20T23:59:59+02:00`, `...Z`). Без `utc_offset_minutes` берётся дата
+/// в том поясе, в котором она записана; иначе дата-время с поясом сначала
+/// переводится в пояс со смещением `utc_offset_minutes` минут от UTC.
+/// Время без пояса считается уже местным.
+pub(crate) fn parse_iso_date_in_zone(
+    raw: &str,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Date, ParseError> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(raw) {
+        let Some(minutes) = utc_offset_minutes else {
+            return Ok(datetime.date_naive());
+        };
+        let offset = minutes
+            .checked_mul(60)
+            .and_then(FixedOffset::east_opt)
+            .ok_or_else(|| {
+                ParseError::BadInput(format!("invalid UTC offset: {minutes} minutes"))
+            })?;
+        return Ok(datetime.with_timezone(&offset).date_naive());
+    }
+
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Ok(datetime.date());
+    }
+
+    parse_iso_date(raw)
+}
+
+/// `DD.MM.YYYY`
+pub(crate) fn format_dd_mm_yyyy(date: Date) -> String {
+    date.format("%d.%m.%Y").to_string()
+}
+
+/// `YYYY-MM-DD`
+pub(crate) fn format_iso(date: Date) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// `YYMMDD`
+pub(crate) fn format_yymmdd(date: Date) -> String {
+    date.format("%y%m%d").to_string()
+}
+
+/// `MMDD`
+pub(crate) fn format_mmdd(date: Date) -> String {
+    date.format("%m%d").to_string()
+}
+
+/// Текущий момент
+pub(crate) fn now() -> Timestamp {
+    Utc::now()
+}
+
+/// Дата момента времени
+pub(crate) fn timestamp_date(ts: Timestamp) -> Date {
+    ts.date_naive()
+}
+
+/// `YYYYMMDDHHMMSS` - для идентификаторов
+pub(crate) fn format_timestamp_compact(ts: Timestamp) -> String {
+    ts.format("%Y%m%d%H%M%S").to_string()
+}
+
+/// `YYYY-MM-DDTHH:MM:SS`
+pub(crate) fn format_timestamp_iso(ts: Timestamp) -> String {
+    ts.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// `HH:MM:SS`
+pub(crate) fn format_time_hms(ts: Timestamp) -> String {
+    ts.format("%H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn ymd_rejects_invalid_dates() {
+        assert!(ymd(2024, 2, 29).is_some());
+        assert!(ymd(2023, 2, 29).is_none());
+    }
+
+    #[test]
+    fn parse_and_format_roundtrip() {
+        let date = ymd(2024, 3, 7).unwrap();
+
+        assert_eq!(
+            parse_with_formats("07.03.2024", &["%d.%m.%Y"]).unwrap(),
+            date
+        );
+        assert_eq!(parse_iso_date("2024-03-07").unwrap(), date);
+        assert_eq!(parse_iso_date("2024-03-07T10:20:30").unwrap(), date);
+        assert!(parse_iso_date("07.03.2024").is_err());
+
+        assert_eq!(format_dd_mm_yyyy(date), "07.03.2024");
+        assert_eq!(format_iso(date), "2024-03-07");
+        assert_eq!(format_yymmdd(date), "240307");
+        assert_eq!(format_mmdd(date), "0307");
+        assert_eq!((year(date), month(date), day(date)), (2024, 3, 7));
+        assert_eq!(days_between(ymd(2024, 2, 28).unwrap(), date), 8);
+        assert_eq!(days_between(date, ymd(2024, 2, 28).unwrap()), -8);
+    }
+
+    #[test]
+    fn parse_iso_date_in_zone_handles_offsets() {
+        let date = ymd(2023, 4, 20).unwrap();
+        let next = ymd(2023, 4, 21).unwrap();
+
+        assert_eq!(parse_iso_date_in_zone("2023-04-20", None).unwrap(), date);
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59.123", None).unwrap(),
+            date
+        );
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59+02:00", None).unwrap(),
+            date
+        );
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59Z", None).unwrap(),
+            date
+        );
+
+        // перевод в заданный пояс
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59Z", Some(180)).unwrap(),
+            next
+        );
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-21T00:30:00+02:00", Some(0)).unwrap(),
+            date
+        );
+        // время без пояса уже местное
+        assert_eq!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59", Some(180)).unwrap(),
+            date
+        );
+
+        assert!(matches!(
+            parse_iso_date_in_zone("2023-04-20T23:59:59Z", Some(24 * 60)),
+            Err(ParseError::BadInput(_))
+        ));
+        assert!(parse_iso_date_in_zone("2023-04-20T23:59:59+25:00", None).is_err());
+    }
+
+    #[test]
+    fn parse_with_formats_tries_formats_in_order() {
+        let date = ymd(2024, 3, 7).unwrap();
+        let formats = ["%d.%m.%Y", "%Y-%m-%d", "%d/%m/%Y"];
+
+        assert_eq!(parse_with_formats("2024-03-07", &formats).unwrap(), date);
+        assert_eq!(parse_with_formats("07/03/2024", &formats).unwrap(), date);
+        assert!(matches!(
+            parse_with_formats("2024/03/07", &formats),
+            Err(ParseError::Date(_))
+        ));
+        assert!(matches!(
+            parse_with_formats::<&str>("07.03.2024", &[]),
+            Err(ParseError::BadInput(_))
+        ));
+    }
+
+    #[test]
+    fn timestamp_formats() {
+        let ts = Utc.with_ymd_and_hms(2024, 3, 7, 9, 5, 1).unwrap();
+
+        assert_eq!(timestamp_date(ts), ymd(2024, 3, 7).unwrap());
+        assert_eq!(format_timestamp_compact(ts), "20240307090501");
+        assert_eq!(format_timestamp_iso(ts), "2024-03-07T09:05:01");
+        assert_eq!(format_time_hms(ts), "09:05:01");
+    }
+}
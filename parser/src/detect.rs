@@ -0,0 +1,132 @@
+use std::io::{Read, Seek, SeekFrom};
+
+/// Сколько байт с начала входа просматривается при определении формата
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// Маркеры шапки CSV-выписки
+const CSV_MARKERS: [&str; 3] = ["Дата проводки", "Сумма по дебету", "ВЫПИСКА ОПЕРАЦИЙ"];
+
+/// Формат выписки, определённый по содержимому
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    /// Табличная CSV-выписка
+    Csv,
+    /// CAMT.053 XML
+    Camt053,
+    /// SWIFT MT940
+    Mt940,
+}
+
+/// Определяет формат выписки по первым байтам входа
+///
+/// - `{4:`/`(4:` вместе с `:20:` - MT940;
+/// - MT940 без конверта: строки, начинающиеся с `:20:` и с `:25:` или `:60F:`;
+/// - `<Document`/`<Stmt` - CAMT.053;
+/// - русские заголовки таблицы ("Дата проводки" и т.п.) - CSV.
+///
+/// После проверки reader возвращается на исходную позицию, так что его можно
+/// сразу передавать в `parse`. Если формат распознать не удалось (или чтение
+/// упало), возвращает `None`.
+pub fn detect_format<R: Read + Seek>(mut reader: R) -> Option<DetectedFormat> {
+    let start = reader.stream_position().ok()?;
+
+    let mut buf = Vec::new();
+    let read = (&mut reader).take(SNIFF_LEN).read_to_end(&mut buf);
+    reader.seek(SeekFrom::Start(start)).ok()?;
+    read.ok()?;
+
+    // окно может обрезать многобайтовый символ, поэтому lossy
+    let text = String::from_utf8_lossy(&buf);
+
+    if (text.contains("{4:") || text.contains("(4:")) && text.contains(":20:") {
+        return Some(DetectedFormat::Mt940);
+    }
+    if text.contains("<Document") || text.contains("<Stmt") {
+        return Some(DetectedFormat::Camt053);
+    }
+    if has_tag_line(&text, ":20:") && (has_tag_line(&text, ":25:") || has_tag_line(&text, ":60F:"))
+    {
+        return Some(DetectedFormat::Mt940);
+    }
+    if CSV_MARKERS.iter().any(|m| text.contains(m)) {
+        return Some(DetectedFormat::Csv);
+    }
+
+    None
+}
+
+/// Есть ли строка, которая (без отступа) начинается с тега `tag`
+fn has_tag_line(text: &str, tag: &str) -> bool {
+    text.lines().any(|line| line.trim_start().starts_with(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn detect_format_recognizes_mt940() {
+        let input = "{1:F01FOOBARBAAXXX0000000000}{2:I940BAZQUXCCXXXXN}{4:\n:20:REF\n:25:ACC\n-}";
+        assert_eq!(
+            detect_format(Cursor::new(input)),
+            Some(DetectedFormat::Mt940)
+        );
+
+        let paren = "(4:\n:20:REF\n:25:ACC\n-)";
+        assert_eq!(
+            detect_format(Cursor::new(paren)),
+            Some(DetectedFormat::Mt940)
+        );
+    }
+
+    #[test]
+    fn detect_format_recognizes_camt053() {
+        let document =
+            r#"<?xml version="1.0"?><Document xmlns="urn:iso"><BkToCstmrStmt/></Document>"#;
+        assert_eq!(
+            detect_format(Cursor::new(document)),
+            Some(DetectedFormat::Camt053)
+        );
+
+        let stmt = "<Stmt><Acct/></Stmt>";
+        assert_eq!(
+            detect_format(Cursor::new(stmt)),
+            Some(DetectedFormat::Camt053)
+        );
+    }
+
+    #[test]
+    fn detect_format_recognizes_csv() {
+        let input = ",,,\n,ВЫПИСКА ОПЕРАЦИЙ ПО ЛИЦЕВОМУ СЧЕТУ,,\n,Дата проводки,,Счет\n";
+        assert_eq!(detect_format(Cursor::new(input)), Some(DetectedFormat::Csv));
+    }
+
+    #[test]
+    fn detect_format_returns_none_for_unknown_input() {
+        assert_eq!(detect_format(Cursor::new("hello, world")), None);
+        assert_eq!(detect_format(Cursor::new("")), None);
+    }
+
+    #[test]
+    fn detect_format_recognizes_envelope_less_mt940() {
+        let input = ":20:REF\n:25:DE11112222333344445555\n:28C:1/1\n:60F:C230101EUR100,00\n";
+        assert_eq!(
+            detect_format(Cursor::new(input)),
+            Some(DetectedFormat::Mt940)
+        );
+
+        // тег не в начале строки - не MT940
+        assert_eq!(detect_format(Cursor::new("note :20: and :25:")), None);
+    }
+
+    #[test]
+    fn detect_format_rewinds_reader() {
+        let mut cursor = Cursor::new("<Stmt></Stmt>");
+        detect_format(&mut cursor);
+
+        let mut rest = String::new();
+        cursor.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "<Stmt></Stmt>");
+    }
+}
@@ -0,0 +1,577 @@
+//! Сравнение двух выписок.
+//!
+//! Транзакции сопоставляются по позиции: `a.transactions[i]` с `b.transactions[i]`.
+//! Для каждой пары фиксируется список различающихся полей, а лишние транзакции
+//! одной из сторон перечисляются отдельно. Допуск на расхождение сумм задаётся
+//! через [`DiffOptions`].
+
+use crate::model::{Currency, Statement, Transaction};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Поле транзакции, по которому может быть найдено расхождение
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TransactionField {
+    /// дата проводки
+    BookingDate,
+    /// дата валютирования
+    ValueDate,
+    /// сумма
+    Amount,
+    /// направление (дебет/кредит)
+    Direction,
+    /// назначение платежа
+    Description,
+    /// счёт контрагента
+    Counterparty,
+    /// имя контрагента
+    CounterpartyName,
+    /// адрес контрагента
+    CounterpartyAddress,
+    /// референс
+    Reference,
+    /// референс банка
+    BankReference,
+    /// код назначения
+    PurposeCode,
+    /// детали конвертации
+    Fx,
+    /// исходная валюта операции
+    OriginalCurrency,
+    /// комиссии
+    Charges,
+    /// признак сторно
+    Reversal,
+    /// признак непроведённой операции
+    Pending,
+    /// вид операции
+    OperationCode,
+    /// категория
+    Category,
+}
+
+impl TransactionField {
+    /// Все поля в порядке объявления
+    pub const ALL: [TransactionField; 18] = [
+        TransactionField::BookingDate,
+        TransactionField::ValueDate,
+        TransactionField::Amount,
+        TransactionField::Direction,
+        TransactionField::Description,
+        TransactionField::Counterparty,
+        TransactionField::CounterpartyName,
+        TransactionField::CounterpartyAddress,
+        TransactionField::Reference,
+        TransactionField::BankReference,
+        TransactionField::PurposeCode,
+        TransactionField::Fx,
+        TransactionField::OriginalCurrency,
+        TransactionField::Charges,
+        TransactionField::Reversal,
+        TransactionField::Pending,
+        TransactionField::OperationCode,
+        TransactionField::Category,
+    ];
+
+    /// Короткое имя поля для вывода
+    pub fn name(self) -> &'static str {
+        match self {
+            TransactionField::BookingDate => "date",
+            TransactionField::ValueDate => "value date",
+            TransactionField::Amount => "amount",
+            TransactionField::Direction => "direction",
+            TransactionField::Description => "description",
+            TransactionField::Counterparty => "counterparty",
+            TransactionField::CounterpartyName => "counterparty name",
+            TransactionField::CounterpartyAddress => "counterparty address",
+            TransactionField::Reference => "reference",
+            TransactionField::BankReference => "bank reference",
+            TransactionField::PurposeCode => "purpose code",
+            TransactionField::Fx => "fx",
+            TransactionField::OriginalCurrency => "original currency",
+            TransactionField::Charges => "charges",
+            TransactionField::Reversal => "reversal",
+            TransactionField::Pending => "pending",
+            TransactionField::OperationCode => "operation code",
+            TransactionField::Category => "category",
+        }
+    }
+
+    /// Имя поля для машинного вывода (snake_case), см. [`StatementDiff::to_json`]
+    pub fn key(self) -> &'static str {
+        match self {
+            TransactionField::BookingDate => "booking_date",
+            TransactionField::ValueDate => "value_date",
+            TransactionField::Amount => "amount",
+            TransactionField::Direction => "direction",
+            TransactionField::Description => "description",
+            TransactionField::Counterparty => "counterparty",
+            TransactionField::CounterpartyName => "counterparty_name",
+            TransactionField::CounterpartyAddress => "counterparty_address",
+            TransactionField::Reference => "reference",
+            TransactionField::BankReference => "bank_reference",
+            TransactionField::PurposeCode => "purpose_code",
+            TransactionField::Fx => "fx",
+            TransactionField::OriginalCurrency => "original_currency",
+            TransactionField::Charges => "charges",
+            TransactionField::Reversal => "reversal",
+            TransactionField::Pending => "pending",
+            TransactionField::OperationCode => "operation_code",
+            TransactionField::Category => "category",
+        }
+    }
+
+    fn differs(self, a: &Transaction, b: &Transaction, options: &DiffOptions) -> bool {
+        match self {
+            TransactionField::BookingDate => a.booking_date != b.booking_date,
+            TransactionField::ValueDate => a.value_date != b.value_date,
+            TransactionField::Amount => a.amount.abs_diff(b.amount) > options.amount_tolerance,
+            TransactionField::Direction => a.direction != b.direction,
+            TransactionField::Description => a.description != b.description,
+            TransactionField::Counterparty => a.counterparty != b.counterparty,
+            TransactionField::CounterpartyName => a.counterparty_name != b.counterparty_name,
+            TransactionField::CounterpartyAddress => {
+                a.counterparty_address != b.counterparty_address
+            }
+            TransactionField::Reference => a.reference != b.reference,
+            TransactionField::BankReference => a.bank_reference != b.bank_reference,
+            TransactionField::PurposeCode => a.purpose_code != b.purpose_code,
+            TransactionField::Fx => a.fx != b.fx,
+            TransactionField::OriginalCurrency => a.original_currency != b.original_currency,
+            TransactionField::Charges => a.charges != b.charges,
+            TransactionField::Reversal => a.is_reversal != b.is_reversal,
+            TransactionField::Pending => a.pending != b.pending,
+            TransactionField::OperationCode => a.operation_code != b.operation_code,
+            TransactionField::Category => a.category != b.category,
+        }
+    }
+}
+
+impl fmt::Display for TransactionField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Настройки сравнения для [`diff_statements_with`]
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Допуск на расхождение сумм (в "копейках")
+    ///
+    /// Суммы, отличающиеся не больше чем на это значение, считаются равными.
+    /// Полезно при сравнении выписок из разных форматов, где суммы могли
+    /// округлиться по-разному. По умолчанию `0` - точное сравнение.
+    pub amount_tolerance: u64,
+}
+
+/// Расхождение в паре транзакций на одной позиции
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionDiff {
+    /// позиция в обоих списках
+    pub index: usize,
+    /// различающиеся поля
+    pub fields: Vec<TransactionField>,
+}
+
+/// Результат [`diff_statements`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StatementDiff {
+    /// номера счетов, если они различаются
+    pub account_id: Option<(String, String)>,
+    /// валюты, если они различаются
+    pub currency: Option<(Currency, Currency)>,
+    /// число совпавших пар транзакций
+    pub matched: usize,
+    /// пары транзакций с расхождениями
+    pub mismatched: Vec<TransactionDiff>,
+    /// позиции транзакций, которые есть только в первой выписке
+    pub only_in_first: Vec<usize>,
+    /// позиции транзакций, которые есть только во второй выписке
+    pub only_in_second: Vec<usize>,
+}
+
+impl StatementDiff {
+    /// `true`, если выписки совпадают
+    pub fn is_empty(&self) -> bool {
+        self.account_id.is_none()
+            && self.currency.is_none()
+            && self.mismatched.is_empty()
+            && self.only_in_first.is_empty()
+            && self.only_in_second.is_empty()
+    }
+
+    /// Расхождения одной строкой JSON
+    ///
+    /// ```json
+    /// {"account_id":{"file1":"A","file2":"B"},"currency":null,"matched":1,
+    ///  "mismatched":[{"index":1,"fields":["amount"]}],"only_in_first":[2],"only_in_second":[]}
+    /// ```
+    ///
+    /// Поля транзакций названы по [`TransactionField::key`].
+    pub fn to_json(&self) -> String {
+        let pair = |pair: Option<(String, String)>| match pair {
+            Some((a, b)) => format!(
+                "{{\"file1\":{},\"file2\":{}}}",
+                json_string(&a),
+                json_string(&b)
+            ),
+            None => "null".to_string(),
+        };
+        let indexes = |indexes: &[usize]| {
+            let items: Vec<String> = indexes.iter().map(usize::to_string).collect();
+            format!("[{}]", items.join(","))
+        };
+        let mismatched: Vec<String> = self
+            .mismatched
+            .iter()
+            .map(|diff| {
+                let fields: Vec<String> = diff
+                    .fields
+                    .iter()
+                    .map(|field| json_string(field.key()))
+                    .collect();
+                format!(
+                    "{{\"index\":{},\"fields\":[{}]}}",
+                    diff.index,
+                    fields.join(",")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"account_id\":{},\"currency\":{},\"matched\":{},\"mismatched\":[{}],\"only_in_first\":{},\"only_in_second\":{}}}",
+            pair(self.account_id.clone()),
+            pair(
+                self.currency
+                    .as_ref()
+                    .map(|(a, b)| (a.to_string(), b.to_string()))
+            ),
+            self.matched,
+            mismatched.join(","),
+            indexes(&self.only_in_first),
+            indexes(&self.only_in_second),
+        )
+    }
+
+    /// Сводная статистика по расхождениям
+    pub fn summary(&self) -> DiffSummary {
+        let mut field_mismatches: BTreeMap<TransactionField, usize> = BTreeMap::new();
+        for diff in &self.mismatched {
+            for field in &diff.fields {
+                *field_mismatches.entry(*field).or_default() += 1;
+            }
+        }
+
+        DiffSummary {
+            matched: self.matched,
+            total: self.matched
+                + self.mismatched.len()
+                + self.only_in_first.len()
+                + self.only_in_second.len(),
+            field_mismatches,
+            only_in_first: self.only_in_first.len(),
+            only_in_second: self.only_in_second.len(),
+        }
+    }
+}
+
+/// Сводка по [`StatementDiff`]
+///
+/// `Display` выводит её одной строкой, например
+/// `matched 40/42, amount mismatches: 1, date mismatches: 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSummary {
+    /// число совпавших транзакций
+    pub matched: usize,
+    /// общее число сопоставленных позиций
+    pub total: usize,
+    /// сколько раз различалось каждое поле
+    pub field_mismatches: BTreeMap<TransactionField, usize>,
+    /// лишние транзакции в первой выписке
+    pub only_in_first: usize,
+    /// лишние транзакции во второй выписке
+    pub only_in_second: usize,
+}
+
+impl DiffSummary {
+    /// Поля с расхождениями, самые частые первыми
+    pub fn most_frequent(&self) -> Vec<(TransactionField, usize)> {
+        let mut fields: Vec<_> = self
+            .field_mismatches
+            .iter()
+            .map(|(field, count)| (*field, *count))
+            .collect();
+        // сортировка стабильная: при равенстве остаётся порядок объявления полей
+        fields.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        fields
+    }
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "matched {}/{}", self.matched, self.total)?;
+        for (field, count) in self.most_frequent() {
+            write!(f, ", {field} mismatches: {count}")?;
+        }
+        if self.only_in_first > 0 {
+            write!(f, ", only in file1: {}", self.only_in_first)?;
+        }
+        if self.only_in_second > 0 {
+            write!(f, ", only in file2: {}", self.only_in_second)?;
+        }
+        Ok(())
+    }
+}
+
+/// Строка JSON в кавычках с экранированием
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Сравнивает две выписки
+pub fn diff_statements(a: &Statement, b: &Statement) -> StatementDiff {
+    diff_statements_with(a, b, &DiffOptions::default())
+}
+
+/// То же, что [`diff_statements`], но с дополнительными настройками
+pub fn diff_statements_with(a: &Statement, b: &Statement, options: &DiffOptions) -> StatementDiff {
+    let mut diff = StatementDiff::default();
+
+    if a.account_id != b.account_id {
+        diff.account_id = Some((a.account_id.clone(), b.account_id.clone()));
+    }
+    if a.currency != b.currency {
+        diff.currency = Some((a.currency.clone(), b.currency.clone()));
+    }
+
+    for (index, (ta, tb)) in a.transactions.iter().zip(&b.transactions).enumerate() {
+        let fields: Vec<TransactionField> = TransactionField::ALL
+            .into_iter()
+            .filter(|field| field.differs(ta, tb, options))
+            .collect();
+
+        if fields.is_empty() {
+            diff.matched += 1;
+        } else {
+            diff.mismatched.push(TransactionDiff { index, fields });
+        }
+    }
+
+    let common = a.transactions.len().min(b.transactions.len());
+    diff.only_in_first = (common..a.transactions.len()).collect();
+    diff.only_in_second = (common..b.transactions.len()).collect();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::ymd;
+    use crate::model::Direction;
+
+    fn tx(day: u32, amount: u64, description: &str) -> Transaction {
+        Transaction::new(
+            ymd(2024, 1, day).unwrap(),
+            None,
+            amount,
+            Direction::Credit,
+            description.to_string(),
+            None,
+            None,
+        )
+    }
+
+    fn stmt(transactions: Vec<Transaction>) -> Statement {
+        Statement::new(
+            "ACC".to_string(),
+            None,
+            Currency::EUR,
+            None,
+            None,
+            transactions,
+            ymd(2024, 1, 1).unwrap(),
+            ymd(2024, 1, 31).unwrap(),
+        )
+    }
+
+    #[test]
+    fn every_transaction_field_is_compared() {
+        // литерал без `..`: новое поле Transaction не соберётся, пока его не добавят сюда и в TransactionField
+        let full = Transaction {
+            booking_date: ymd(2024, 2, 1).unwrap(),
+            value_date: Some(ymd(2024, 2, 2).unwrap()),
+            amount: 999,
+            direction: Direction::Debit,
+            description: "full".to_string(),
+            counterparty: Some("DE89370400440532013000".to_string()),
+            counterparty_name: Some("Alice".to_string()),
+            counterparty_address: Some(crate::model::PostalAddress {
+                town: Some("Berlin".to_string()),
+                ..Default::default()
+            }),
+            reference: Some("E2E".to_string()),
+            bank_reference: Some("BANK".to_string()),
+            purpose_code: Some("SALA".to_string()),
+            fx: Some(crate::model::FxDetails {
+                source_currency: Some(Currency::USD),
+                target_currency: Some(Currency::EUR),
+                rate: Some("1.1".to_string()),
+            }),
+            charges: Some(10),
+            operation_code: Some(crate::model::OperationType::PaymentOrder),
+            is_reversal: true,
+            pending: true,
+            original_currency: Some(Currency::USD),
+            category: Some("income".to_string()),
+        };
+
+        let diff = diff_statements(&stmt(vec![tx(1, 100, "")]), &stmt(vec![full]));
+
+        assert_eq!(diff.mismatched[0].fields, TransactionField::ALL.to_vec());
+    }
+
+    #[test]
+    fn diff_statements_equal_is_empty() {
+        let a = stmt(vec![tx(1, 100, "a"), tx(2, 200, "b")]);
+
+        let diff = diff_statements(&a, &a);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.matched, 2);
+        assert_eq!(diff.summary().to_string(), "matched 2/2");
+    }
+
+    #[test]
+    fn diff_statements_tallies_field_mismatches() {
+        let a = stmt(vec![
+            tx(1, 100, "a"),
+            tx(2, 200, "b"),
+            tx(3, 300, "c"),
+            tx(4, 400, "d"),
+        ]);
+        let b = stmt(vec![
+            tx(1, 100, "a"),
+            tx(2, 250, "b"),
+            tx(5, 300, "c"),
+            tx(6, 400, "changed"),
+        ]);
+
+        let diff = diff_statements(&a, &b);
+
+        assert_eq!(diff.matched, 1);
+        assert_eq!(
+            diff.mismatched,
+            vec![
+                TransactionDiff {
+                    index: 1,
+                    fields: vec![TransactionField::Amount],
+                },
+                TransactionDiff {
+                    index: 2,
+                    fields: vec![TransactionField::BookingDate],
+                },
+                TransactionDiff {
+                    index: 3,
+                    fields: vec![TransactionField::BookingDate, TransactionField::Description],
+                },
+            ]
+        );
+
+        let summary = diff.summary();
+        assert_eq!(summary.total, 4);
+        assert_eq!(
+            summary.most_frequent(),
+            vec![
+                (TransactionField::BookingDate, 2),
+                (TransactionField::Amount, 1),
+                (TransactionField::Description, 1),
+            ]
+        );
+        assert_eq!(
+            summary.to_string(),
+            "matched 1/4, date mismatches: 2, amount mismatches: 1, description mismatches: 1"
+        );
+    }
+
+    #[test]
+    fn diff_statements_with_amount_tolerance() {
+        let a = stmt(vec![tx(1, 100, "a"), tx(2, 200, "b")]);
+        let b = stmt(vec![tx(1, 101, "a"), tx(2, 202, "b")]);
+
+        let options = DiffOptions {
+            amount_tolerance: 1,
+        };
+        let diff = diff_statements_with(&a, &b, &options);
+
+        assert_eq!(diff.matched, 1);
+        assert_eq!(
+            diff.mismatched,
+            vec![TransactionDiff {
+                index: 1,
+                fields: vec![TransactionField::Amount],
+            }]
+        );
+
+        // без допуска расходятся обе суммы
+        assert_eq!(diff_statements(&a, &b).mismatched.len(), 2);
+    }
+
+    #[test]
+    fn diff_statements_reports_extra_transactions_and_header() {
+        let a = stmt(vec![tx(1, 100, "a"), tx(2, 200, "b"), tx(3, 300, "c")]);
+        let mut b = stmt(vec![tx(1, 100, "a")]);
+        b.account_id = "OTHER".to_string();
+
+        let diff = diff_statements(&a, &b);
+
+        assert_eq!(diff.account_id, Some(("ACC".into(), "OTHER".into())));
+        assert_eq!(diff.currency, None);
+        assert_eq!(diff.only_in_first, vec![1, 2]);
+        assert!(diff.only_in_second.is_empty());
+        assert_eq!(diff.summary().to_string(), "matched 1/3, only in file1: 2");
+    }
+
+    #[test]
+    fn statement_diff_to_json_shape() {
+        let a = stmt(vec![tx(1, 100, "a"), tx(2, 200, "b"), tx(3, 300, "c")]);
+        let mut b = stmt(vec![tx(1, 100, "a"), tx(4, 250, "b")]);
+        b.account_id = "OTHER \"2\"".to_string();
+        b.currency = Currency::RUB;
+
+        assert_eq!(
+            diff_statements(&a, &b).to_json(),
+            concat!(
+                r#"{"account_id":{"file1":"ACC","file2":"OTHER \"2\""},"#,
+                r#""currency":{"file1":"EUR","file2":"RUB"},"#,
+                r#""matched":1,"#,
+                r#""mismatched":[{"index":1,"fields":["booking_date","amount"]}],"#,
+                r#""only_in_first":[2],"only_in_second":[]}"#,
+            )
+        );
+
+        assert_eq!(
+            diff_statements(&a, &a).to_json(),
+            r#"{"account_id":null,"currency":null,"matched":3,"mismatched":[],"only_in_first":[],"only_in_second":[]}"#
+        );
+    }
+
+    #[test]
+    fn json_string_escapes_control_characters() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::date::Date;
+use crate::model::{Balance, Currency};
 use thiserror::Error;
 
 /// Ошибки при парсинге данных
@@ -59,4 +61,274 @@ pub enum ParseError {
     /// ошибка парсинга тега mt940
     #[error("bad mt940 tag: {0}")]
     Mt940Tag(String),
+
+    /// итоговые обороты из подвала не совпадают с суммой операций (например, файл обрезан)
+    #[error(
+        "turnover mismatch: footer debit {footer_debit} / credit {footer_credit}, rows debit {rows_debit} / credit {rows_credit}"
+    )]
+    TurnoverMismatch {
+        /// оборот по дебету из подвала
+        footer_debit: u64,
+        /// оборот по кредиту из подвала
+        footer_credit: u64,
+        /// сумма дебетовых операций
+        rows_debit: u64,
+        /// сумма кредитовых операций
+        rows_credit: u64,
+    },
+
+    /// остатки выписки не сходятся с оборотом, см. [`Statement::validate`](crate::Statement::validate)
+    #[error("unbalanced statement: opening {opening} + net {net} != closing {closing}")]
+    Unbalanced {
+        /// открывающий баланс
+        opening: Balance,
+        /// чистый оборот по транзакциям
+        net: i128,
+        /// закрывающий баланс
+        closing: Balance,
+    },
+
+    /// дата проводки транзакции вне периода выписки, см. [`Statement::validate`](crate::Statement::validate)
+    #[error(
+        "transaction #{index} booked on {date}, outside statement period {period_from} - {period_until}"
+    )]
+    OutOfPeriod {
+        /// индекс транзакции в выписке
+        index: usize,
+        /// дата проводки
+        date: Date,
+        /// начало периода выписки
+        period_from: Date,
+        /// конец периода выписки
+        period_until: Date,
+    },
+
+    /// во входных данных больше одной выписки, а политика
+    /// [`MultipleStatementsPolicy`](crate::MultipleStatementsPolicy) не разрешает взять первую
+    #[error("{count} statements in {format} input, expected one (use parse_all to read them all)")]
+    MultipleStatements {
+        /// формат входных данных
+        format: &'static str,
+        /// сколько выписок найдено
+        count: usize,
+    },
+
+    /// ошибка, привязанная к строке входных данных
+    ///
+    /// MT940 и CSV оборачивают в неё ошибки разбора отдельных строк.
+    #[error("line {line}: {source}")]
+    AtLine {
+        /// номер строки во входных данных, с единицы
+        line: usize,
+        /// отрывок входных данных вокруг строки, см. [`ParseError::excerpt`]
+        excerpt: String,
+        /// исходная ошибка
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Привязывает ошибку к строке входных данных
+    pub(crate) fn at_line(self, line: usize, excerpt: String) -> Self {
+        ParseError::AtLine {
+            line,
+            excerpt,
+            source: Box::new(self),
+        }
+    }
+
+    /// Номер строки входных данных, на которой произошла ошибка, если он известен
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::AtLine { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+
+    /// Отрывок входных данных вокруг ошибочной строки, если он известен
+    ///
+    /// Строки пронумерованы, ошибочная отмечена `>`:
+    ///
+    /// ```text
+    ///   12 | :60F:C230101EUR100,00
+    /// > 13 | :61:2301XXC50,00
+    ///   14 | :86:payment
+    /// ```
+    pub fn excerpt(&self) -> Option<&str> {
+        match self {
+            ParseError::AtLine { excerpt, .. } => Some(excerpt),
+            _ => None,
+        }
+    }
+
+    /// Исходная ошибка без привязки к строке
+    pub fn root(&self) -> &ParseError {
+        match self {
+            ParseError::AtLine { source, .. } => source.root(),
+            other => other,
+        }
+    }
+}
+
+impl From<ParseError> for std::io::Error {
+    /// Переводит ошибку в `io::Error` для кода, работающего с `io::Result`
+    ///
+    /// Ошибки ввода-вывода (в том числе внутри [`ParseError::AtLine`] и ошибок CSV)
+    /// возвращаются как есть, все остальные - с видом `ErrorKind::InvalidData`.
+    fn from(err: ParseError) -> Self {
+        match err {
+            ParseError::Io(err) => err,
+            ParseError::Csv(err) if err.is_io_error() => err.into(),
+            ParseError::AtLine { source, .. }
+                if matches!(source.root(), ParseError::Io(_) | ParseError::Csv(_)) =>
+            {
+                std::io::Error::from(*source)
+            }
+            other => std::io::Error::new(std::io::ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+/// Некритичные замечания, собранные при парсинге или записи выписки
+///
+/// Библиотека не пишет их в stderr: они возвращаются вызывающему коду
+/// (например, в поле `warnings` структур-обёрток), а тот сам решает, что с ними делать.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseWarning {
+    /// во входных данных больше одной выписки, прочитана только первая
+    #[error("more than one statement provided to {format} parser. only reading first")]
+    MultipleStatements {
+        /// формат входных данных
+        format: &'static str,
+    },
+
+    /// повторяющийся открывающий баланс `:60F:` или `:60M:`, использован первый
+    #[error("multiple :60: opening balances, keeping the first one")]
+    MultipleOpeningBalances,
+
+    /// неизвестный тег MT940/MT942 пропущен
+    #[error("skipped unknown tag {tag}: {value}")]
+    UnknownTag {
+        /// тег без двоеточий
+        tag: String,
+        /// значение тега
+        value: String,
+    },
+
+    /// в суммах выписки одновременно встречаются `.` и `,` как десятичный разделитель
+    #[error("mt940 statement {account} mixes '.' and ',' decimal separators in amounts")]
+    MixedDecimalSeparators {
+        /// счёт выписки
+        account: String,
+    },
+
+    /// валюта, указанная у суммы, не совпадает с валютой выписки
+    #[error(
+        "csv record #{record}: amount '{amount}' is in {currency}, statement currency is {statement_currency}"
+    )]
+    AmountCurrencyMismatch {
+        /// номер записи в таблице
+        record: usize,
+        /// сумма как есть
+        amount: String,
+        /// валюта суммы
+        currency: Currency,
+        /// валюта выписки
+        statement_currency: Currency,
+    },
+
+    /// валюта не поддерживается форматом, вместо кода записан плейсхолдер
+    #[error(
+        "found unknown currency {currency} while converting to {format}. using placeholder '{placeholder}'"
+    )]
+    UnknownCurrency {
+        /// валюта как есть
+        currency: String,
+        /// выходной формат
+        format: &'static str,
+        /// записанный плейсхолдер
+        placeholder: &'static str,
+    },
+
+    /// поля транзакции не представимы в MT940 и отброшены
+    #[error("transaction #{index} ({date}): dropped {} not representable in MT940", fields.join(", "))]
+    DroppedFields {
+        /// номер транзакции
+        index: usize,
+        /// дата проводки
+        date: Date,
+        /// отброшенные поля
+        fields: Vec<&'static str>,
+    },
+
+    /// текст `:86:` не поместился в ограничения SWIFT и был обрезан
+    #[error(
+        "{}: :86: text truncated to {max_lines} lines of {max_len} chars",
+        match (index, date) {
+            (Some(index), Some(date)) => format!("transaction #{index} ({date})"),
+            _ => "statement".to_string(),
+        }
+    )]
+    TruncatedInfo {
+        /// номер транзакции, `None` - `:86:` уровня выписки
+        index: Option<usize>,
+        /// дата проводки, `None` - `:86:` уровня выписки
+        date: Option<Date>,
+        /// максимум строк
+        max_lines: usize,
+        /// максимум символов в строке
+        max_len: usize,
+    },
+
+    /// валюты в выписке нет, она угадана по стране IBAN счёта
+    #[error("no currency in {format} statement {account}, guessed {currency} from IBAN country")]
+    CurrencyInferred {
+        /// входной формат
+        format: &'static str,
+        /// счёт выписки
+        account: String,
+        /// угаданная валюта
+        currency: Currency,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn logical_error_becomes_invalid_data() {
+        let err = io::Error::from(ParseError::BadInput("broken".into()));
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "bad input: broken");
+        assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref::<ParseError>()),
+            Some(ParseError::BadInput(_))
+        ));
+    }
+
+    #[test]
+    fn io_error_is_preserved() {
+        let inner = io::Error::new(io::ErrorKind::UnexpectedEof, "eof");
+        let err = io::Error::from(ParseError::Io(inner));
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let inner = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let at_line = ParseError::Io(inner).at_line(3, String::new());
+        assert_eq!(
+            io::Error::from(at_line).kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn line_bound_logical_error_keeps_line_in_message() {
+        let err = ParseError::MissingField("amount").at_line(7, String::new());
+        let err = io::Error::from(err);
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "line 7: missing field: amount");
+    }
 }
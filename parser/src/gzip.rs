@@ -0,0 +1,567 @@
+//! Прозрачное чтение сжатых gzip выписок (фича `gzip`)
+//!
+//! Выгрузки из банков часто приходят как `.gz`. [`open_maybe_gzip`] смотрит
+//! на первые байты входа и, если там сигнатура gzip (`1f 8b`), оборачивает
+//! reader в [`GzDecoder`]; иначе вход отдаётся как есть. Результат реализует
+//! `Read + Seek`, поэтому его можно передавать и в [`detect_format`](crate::detect_format),
+//! и в парсеры.
+//!
+//! Распаковка (DEFLATE, RFC 1951, и обёртка gzip, RFC 1952) реализована
+//! внутри крейта, без внешних зависимостей. Выписка распаковывается в память
+//! целиком при первом чтении; контрольная сумма CRC-32 и длина проверяются.
+//!
+//! ```rust
+//! use parser::{DetectedFormat, detect_format, open_maybe_gzip};
+//! use std::io::{Cursor, Read};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! // ":20:REF\n:25:ACC\n" без сжатия (stored-блок)
+//! let compressed = [
+//!     0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x01, 0x10, 0x00, 0xef,
+//!     0xff, b':', b'2', b'0', b':', b'R', b'E', b'F', b'\n', b':', b'2', b'5', b':', b'A',
+//!     b'C', b'C', b'\n', 0xce, 0xd3, 0x36, 0x94, 0x10, 0x00, 0x00, 0x00,
+//! ];
+//!
+//! let mut reader = open_maybe_gzip(Cursor::new(compressed))?;
+//! assert_eq!(detect_format(&mut reader), Some(DetectedFormat::Mt940));
+//!
+//! let mut text = String::new();
+//! reader.read_to_string(&mut text)?;
+//! assert_eq!(text, ":20:REF\n:25:ACC\n");
+//! #     Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// Сигнатура gzip в начале файла
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Базовые длины для кодов 257..=285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+/// Число дополнительных бит для кодов длины
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Базовые расстояния для кодов 0..=29
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// Число дополнительных бит для кодов расстояния
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Порядок длин кодов в заголовке динамического блока
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Максимальная длина кода Хаффмана в DEFLATE
+const MAX_BITS: usize = 15;
+
+/// Флаги заголовка gzip
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("gzip: {msg}"))
+}
+
+/// Таблица CRC-32 (полином 0xEDB88320)
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        CRC_TABLE[((crc ^ u32::from(b)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Канонический код Хаффмана: число кодов каждой длины и символы по порядку
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+
+        // кодов одной длины не может быть больше, чем свободных значений
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0u16; usize::from(offsets[MAX_BITS + 1])];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                let slot = &mut offsets[usize::from(len)];
+                symbols[usize::from(*slot)] = symbol as u16;
+                *slot += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+/// Распаковщик одного потока DEFLATE
+struct Inflater<'a> {
+    input: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+    out: Vec<u8>,
+}
+
+impl<'a> Inflater<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Inflater {
+            input,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+            out: Vec::new(),
+        }
+    }
+
+    /// Следующие `need` бит (`need <= 16`), младшие первыми
+    fn bits(&mut self, need: u32) -> io::Result<u32> {
+        while self.bit_count < need {
+            let byte = *self
+                .input
+                .get(self.pos)
+                .ok_or_else(|| invalid("unexpected end of compressed data"))?;
+            self.pos += 1;
+            self.bit_buf |= u32::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buf & ((1 << need) - 1);
+        self.bit_buf >>= need;
+        self.bit_count -= need;
+        Ok(value)
+    }
+
+    /// Отбрасывает биты до границы байта
+    fn align(&mut self) {
+        // в буфере остаются только биты уже прочитанного байта
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for &count in &huffman.counts[1..] {
+            code |= self.bits(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid("invalid Huffman code"))
+    }
+
+    /// Распаковывает блоки до последнего (`BFINAL`)
+    fn inflate(&mut self) -> io::Result<()> {
+        loop {
+            let last = self.bits(1)? == 1;
+            match self.bits(2)? {
+                0 => self.stored()?,
+                1 => {
+                    let (literals, distances) = fixed_codes()?;
+                    self.codes(&literals, &distances)?;
+                }
+                2 => {
+                    let (literals, distances) = self.dynamic_codes()?;
+                    self.codes(&literals, &distances)?;
+                }
+                _ => return Err(invalid("invalid block type")),
+            }
+
+            if last {
+                self.align();
+                return Ok(());
+            }
+        }
+    }
+
+    fn stored(&mut self) -> io::Result<()> {
+        self.align();
+
+        let header = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| invalid("unexpected end of compressed data"))?;
+        let len = u16::from_le_bytes([header[0], header[1]]);
+        let nlen = u16::from_le_bytes([header[2], header[3]]);
+        if len != !nlen {
+            return Err(invalid("stored block length mismatch"));
+        }
+        self.pos += 4;
+
+        let data = self
+            .input
+            .get(self.pos..self.pos + usize::from(len))
+            .ok_or_else(|| invalid("unexpected end of compressed data"))?;
+        self.out.extend_from_slice(data);
+        self.pos += usize::from(len);
+        Ok(())
+    }
+
+    fn dynamic_codes(&mut self) -> io::Result<(Huffman, Huffman)> {
+        let literal_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_length_count = self.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(invalid("too many length or distance codes"));
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[index] = self.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![0u8; literal_count + distance_count];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = self.decode(&code_length_code)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *index
+                        .checked_sub(1)
+                        .and_then(|i| lengths.get(i))
+                        .ok_or_else(|| invalid("repeat without previous code length"))?;
+                    (previous, 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize),
+            };
+
+            let slots = lengths
+                .get_mut(index..index + repeat)
+                .ok_or_else(|| invalid("too many code lengths"))?;
+            slots.fill(value);
+            index += repeat;
+        }
+
+        if lengths[256] == 0 {
+            return Err(invalid("missing end-of-block code"));
+        }
+
+        let (literal_lengths, distance_lengths) = lengths.split_at(literal_count);
+        Ok((
+            Huffman::new(literal_lengths)?,
+            Huffman::new(distance_lengths)?,
+        ))
+    }
+
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+        loop {
+            let symbol = self.decode(literals)?;
+            let symbol = match symbol {
+                0..=255 => {
+                    self.out.push(symbol as u8);
+                    continue;
+                }
+                256 => return Ok(()),
+                _ => usize::from(symbol - 257),
+            };
+
+            let (&base, &extra) = LENGTH_BASE
+                .get(symbol)
+                .zip(LENGTH_EXTRA.get(symbol))
+                .ok_or_else(|| invalid("invalid length code"))?;
+            let len = usize::from(base) + self.bits(u32::from(extra))? as usize;
+
+            let symbol = usize::from(self.decode(distances)?);
+            let (&base, &extra) = DIST_BASE
+                .get(symbol)
+                .zip(DIST_EXTRA.get(symbol))
+                .ok_or_else(|| invalid("invalid distance code"))?;
+            let distance = usize::from(base) + self.bits(u32::from(extra))? as usize;
+
+            let start = self
+                .out
+                .len()
+                .checked_sub(distance)
+                .ok_or_else(|| invalid("distance too far back"))?;
+            // отрезки могут перекрываться ("abab..."), поэтому по байту
+            for i in start..start + len {
+                let byte = self.out[i];
+                self.out.push(byte);
+            }
+        }
+    }
+}
+
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// Пропускает строку с нулевым окончанием (имя файла, комментарий)
+fn skip_zero_terminated(data: &[u8], pos: usize) -> io::Result<usize> {
+    data.get(pos..)
+        .and_then(|rest| rest.iter().position(|&b| b == 0))
+        .map(|end| pos + end + 1)
+        .ok_or_else(|| invalid("truncated header"))
+}
+
+/// Разбирает один член gzip, возвращает его длину в байтах
+fn decompress_member(data: &[u8], out: &mut Vec<u8>) -> io::Result<usize> {
+    let header = data.get(..10).ok_or_else(|| invalid("truncated header"))?;
+    if header[..2] != GZIP_MAGIC {
+        return Err(invalid("not a gzip stream"));
+    }
+    if header[2] != 8 {
+        return Err(invalid("unsupported compression method"));
+    }
+
+    let flags = header[3];
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = data
+            .get(pos..pos + 2)
+            .ok_or_else(|| invalid("truncated header"))?;
+        pos += 2 + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    }
+    if flags & FNAME != 0 {
+        pos = skip_zero_terminated(data, pos)?;
+    }
+    if flags & FCOMMENT != 0 {
+        pos = skip_zero_terminated(data, pos)?;
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+
+    let body = data.get(pos..).ok_or_else(|| invalid("truncated header"))?;
+    let mut inflater = Inflater::new(body);
+    inflater.inflate()?;
+    pos += inflater.pos;
+
+    let trailer = data
+        .get(pos..pos + 8)
+        .ok_or_else(|| invalid("truncated trailer"))?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc != crc32(&inflater.out) {
+        return Err(invalid("CRC-32 mismatch"));
+    }
+    // ISIZE хранит длину по модулю 2^32
+    if size != inflater.out.len() as u32 {
+        return Err(invalid("length mismatch"));
+    }
+
+    out.extend_from_slice(&inflater.out);
+    Ok(pos + 8)
+}
+
+/// Распаковывает gzip целиком; несколько склеенных членов читаются подряд
+fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    loop {
+        pos += decompress_member(&data[pos..], &mut out)?;
+        if !data[pos..].starts_with(&GZIP_MAGIC) {
+            break;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Reader, распаковывающий gzip из `R`
+///
+/// Вход читается и распаковывается целиком при первом обращении; ошибка
+/// распаковки (битые данные, несовпадение CRC-32) возвращается из этого
+/// обращения как `io::ErrorKind::InvalidData`. `Seek` работает по уже
+/// распакованным данным.
+#[derive(Debug)]
+pub struct GzDecoder<R> {
+    inner: Option<R>,
+    decoded: Cursor<Vec<u8>>,
+}
+
+impl<R: Read> GzDecoder<R> {
+    /// Оборачивает сжатый поток
+    pub fn new(inner: R) -> Self {
+        GzDecoder {
+            inner: Some(inner),
+            decoded: Cursor::new(Vec::new()),
+        }
+    }
+
+    fn decoded(&mut self) -> io::Result<&mut Cursor<Vec<u8>>> {
+        if let Some(mut inner) = self.inner.take() {
+            let mut raw = Vec::new();
+            inner.read_to_end(&mut raw)?;
+            self.decoded = Cursor::new(decompress(&raw)?);
+        }
+
+        Ok(&mut self.decoded)
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoded()?.read(buf)
+    }
+}
+
+impl<R: Read> Seek for GzDecoder<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.decoded()?.seek(pos)
+    }
+}
+
+/// Вход, который мог оказаться сжатым, см. [`open_maybe_gzip`]
+#[derive(Debug)]
+pub enum MaybeGzip<R> {
+    /// Несжатый вход, читается как есть
+    Plain(R),
+    /// Вход с сигнатурой gzip
+    Gzip(GzDecoder<R>),
+}
+
+impl<R: Read> Read for MaybeGzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            MaybeGzip::Plain(reader) => reader.read(buf),
+            MaybeGzip::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read + Seek> Seek for MaybeGzip<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            MaybeGzip::Plain(reader) => reader.seek(pos),
+            MaybeGzip::Gzip(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// Оборачивает `reader` в [`GzDecoder`], если вход начинается с `1f 8b`
+///
+/// Сигнатура проверяется с текущей позиции, после чего reader возвращается
+/// на неё же; несжатый вход отдаётся без изменений.
+pub fn open_maybe_gzip<R: Read + Seek>(mut reader: R) -> io::Result<MaybeGzip<R>> {
+    let start = reader.stream_position()?;
+
+    let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+    (&mut reader)
+        .take(GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    if magic == GZIP_MAGIC {
+        Ok(MaybeGzip::Gzip(GzDecoder::new(reader)))
+    } else {
+        Ok(MaybeGzip::Plain(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "hello hello hello\n", сжато `gzip -9` (фиксированные коды, повторы)
+    const HELLO_GZ: [u8; 29] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0x40, 0x90, 0x5c, 0x00, 0x3b, 0x7c, 0x8a, 0xdf, 0x12, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn crc32_matches_reference_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn decompress_handles_back_references() {
+        assert_eq!(decompress(&HELLO_GZ).unwrap(), b"hello hello hello\n");
+    }
+
+    #[test]
+    fn decompress_reads_concatenated_members() {
+        let data = [HELLO_GZ, HELLO_GZ].concat();
+        assert_eq!(decompress(&data).unwrap(), b"hello hello hello\n".repeat(2));
+    }
+
+    #[test]
+    fn decompress_rejects_crc_mismatch() {
+        let mut data = HELLO_GZ;
+        data[21] ^= 0xff;
+
+        let err = decompress(&data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("CRC-32"), "{err}");
+    }
+
+    #[test]
+    fn decompress_rejects_truncated_stream() {
+        assert!(decompress(&HELLO_GZ[..15]).is_err());
+    }
+
+    #[test]
+    fn open_maybe_gzip_passes_plain_input_through() {
+        let mut reader = open_maybe_gzip(Cursor::new(b":20:REF".to_vec())).unwrap();
+        assert!(matches!(reader, MaybeGzip::Plain(_)));
+
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, ":20:REF");
+    }
+}
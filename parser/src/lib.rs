@@ -7,7 +7,8 @@
 //! Поддерживаемые форматы:
 //! - **CSV** - табличные выгрузки;
 //! - **CAMT.053 XML** - стандарт ISO 20022;
-//! - **MT940** - формат SWIFT.
+//! - **MT940** - формат SWIFT;
+//! - **MT942** - промежуточная выписка SWIFT (только чтение).
 //!
 //! # Основные типы
 //!
@@ -19,23 +20,51 @@
 //!
 //! - [`CsvData`],
 //! - [`Camt053Data`],
-//! - [`Mt940Data`].
+//! - [`Mt940Data`],
+//! - [`Mt942Data`].
 //!
 //! Каждый из них имеет метод `parse(reader)` для любого `impl Read`.
 //! Для преобразования в [`Statement`] реализован трейт `TryFrom<...>`.
 //!
+//! Если формат входа заранее неизвестен, его можно определить по
+//! содержимому через [`detect_format`]. С фичей `gzip` сжатый вход
+//! распаковывается прозрачно: `open_maybe_gzip` оборачивает reader в
+//! `GzDecoder`, если он начинается с сигнатуры gzip.
+//!
+//! Основные типы можно импортировать разом: `use parser::prelude::*;`
+//! (см. [`prelude`]).
+//!
+//! Для конвертации одним вызовом есть [`convert`] (и [`convert_with`] с
+//! настройками записи), формат задаётся через [`Format`].
+//!
+//! Суммы хранятся в минорных единицах ("копейках"); перевести их в текст и
+//! обратно так же, как это делают парсеры, можно через модуль [`money`].
+//!
+//! С фичей `testkit` доступен модуль `testkit` с генераторами случайных
+//! выписок для проверки round trip в своих тестах.
+//!
+//! Две выписки можно сравнить через [`diff_statements`]: результат
+//! ([`StatementDiff`]) содержит поля, по которым разошлись транзакции.
+//!
 //! # Сериализация
 //!
 //! Модуль [`serialization`] предоставляет методы записи [`Statement`]
 //! обратно в поддерживаемые форматы (CSV / CAMT.053 / MT940):
 //!
 //! - `Statement::write_csv(writer)`  
+//! - `Statement::write_csv_with(writer, &CsvWriteOptions)` - свой разделитель и BOM  
 //! - `Statement::write_camt053(writer)`  
+//! - `Statement::write_camt053_with(writer, &Camt053WriteOptions)` - всегда писать OPBD/CLBD  
 //! - `Statement::write_mt940(writer)`  
+//! - `Statement::write_mt940_with(writer, &Mt940WriteOptions)` - с заголовками `{1:}`/`{2:}`  
+//! - `Statement::write_simple_csv(writer)` - плоский CSV, одна строка на операцию (только запись)  
 //!
 //! Все функции принимают любой `impl Write`, поэтому могут выводить
 //! как в файл, так и в память или сетевой поток.
 //!
+//! Для простых случаев есть `to_csv_string()` / `to_camt053_string()` /
+//! `to_mt940_string()`, возвращающие сразу `String`.
+//!
 //! # Пример
 //!
 //! ```no_run
@@ -64,6 +93,7 @@
 //!   Многие форматы позволяют содержать несколько выписок в одном файле.  
 //!   Внутренний парсер может вернуть несколько [`Statement`], но
 //!   текущие CLI-утилиты используют **только первый**, остальные игнорируются.
+//!   Все выписки CAMT.053 можно получить через `Camt053Data::parse_all`.
 //!
 //! - **Одна логическая выписка на файл**  
 //!   Ожидается, что входной файл содержит выписку по одному счёту.  
@@ -85,27 +115,91 @@
 //! - **Потеря данных**  
 //!   В ряде форматов (особенно MT940) допускается потеря менее значимых
 //!   полей при нормализации.
-//!   Поддерживается ограниченный набор валют.
+//!   Поддерживается ограниченный набор валют. Свои названия валют можно
+//!   добавить через [`register_currency_synonym`] или разобрать по своей
+//!   таблице через [`parse_currency_with`].
+//!
+//! # Предупреждения
+//!
+//! Библиотека ничего не печатает в stdout/stderr: некритичные замечания
+//! возвращаются как [`ParseWarning`] (поле `warnings` у структур данных форматов,
+//! [`parse_statement_with_warnings`], результат `write_*_with`).
+//! Это проверяется на этапе сборки линтами `clippy::print_stdout` / `clippy::print_stderr`.
+//!
+//! # Ошибки
+//!
+//! Ошибки разбора отдельных строк MT940 и записей CSV приходят обёрнутыми в
+//! [`ParseError::AtLine`]: [`ParseError::line`] и [`ParseError::excerpt`] указывают место
+//! во входных данных, а [`ParseError::root`] возвращает исходную ошибку.
 //!
 
 #![warn(missing_docs)]
+#![deny(clippy::print_stdout, clippy::print_stderr, clippy::dbg_macro)]
 
 mod camt053;
+mod convert;
 mod csv_parser;
+mod date;
+mod detect;
+mod diff;
 mod error;
+#[cfg(feature = "gzip")]
+mod gzip;
 mod model;
+pub mod money;
 mod mt940;
+pub mod prelude;
 mod serialization;
+mod simple_csv;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
 mod utils;
 
 // Публичные типы верхнего уровня
 
-pub use crate::error::ParseError;
+pub use crate::error::{ParseError, ParseWarning};
+
+pub use crate::date::{Date, Timestamp, ymd};
+
+pub use crate::model::{
+    Balance, Currency, Direction, FxDetails, OperationType, PostalAddress, SourceFormat, Statement,
+    Transaction, TransactionChecks, signed_amount,
+};
 
-pub use crate::model::{Balance, Currency, Direction, Statement, Transaction};
+pub use crate::utils::{
+    MultipleStatementsPolicy, RoundingMode, default_currency_table, parse_currency_with,
+    register_currency_synonym,
+};
 
 // Формат-специфические структуры-обёртки и их `parse()`
 
-pub use crate::camt053::Camt053Data;
-pub use crate::csv_parser::CsvData;
-pub use crate::mt940::Mt940Data;
+pub use crate::camt053::{Camt053Data, Camt053ParseOptions};
+pub use crate::csv_parser::{
+    AccountKind, AmountLayout, BothSidesPolicy, CsvData, CsvLayoutConfig, CsvParseOptions,
+    SubheaderRow,
+};
+pub use crate::detect::{DetectedFormat, detect_format};
+#[cfg(feature = "gzip")]
+pub use crate::gzip::{GzDecoder, MaybeGzip, open_maybe_gzip};
+pub use crate::mt940::{Mt940Data, Mt940Header, Mt940ParseOptions, Mt942Data};
+pub use crate::simple_csv::{SimpleCsvData, SimpleCsvParseOptions};
+
+// Сравнение выписок
+
+pub use crate::diff::{
+    DiffOptions, DiffSummary, StatementDiff, TransactionDiff, TransactionField, diff_statements,
+    diff_statements_with,
+};
+
+// Конвертация одним вызовом
+
+pub use crate::convert::{
+    ConvertOptions, Format, convert, convert_with, parse_statement, parse_statement_with_warnings,
+    write_statement,
+};
+
+// Настройки сериализации
+
+pub use crate::serialization::{
+    Camt053WriteOptions, CsvWriteOptions, Mt940WriteOptions, SimpleCsvWriteOptions,
+};
@@ -1,5 +1,12 @@
-use chrono::NaiveDate;
+use crate::date::{self, Date};
+use crate::error::ParseError;
+use crate::utils::{
+    iban_checksum_ok, normalize_and_check_iban, parse_currency, pseudonymous_account,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
 
 /// Тип для хранения баланса счёта в "копейках", signed
 pub type Balance = i128;
@@ -8,7 +15,7 @@ pub type Balance = i128;
 ///    
 /// Важно:
 /// При использовании [`Currency::Other`] не все операции парсинга/сериализации будут давать стабильный результат.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Currency {
     /// Российский рубль
     RUB,
@@ -63,16 +70,45 @@ pub struct Statement {
     pub opening_balance: Option<Balance>,
     /// закрывающий баланс
     pub closing_balance: Option<Balance>,
+    /// доступный баланс на конец периода (CAMT `CLAV`)
+    ///
+    /// Заполняется только парсером CAMT.053, [`Statement::new`] оставляет `None`.
+    pub closing_available_balance: Option<Balance>,
+    /// доступный баланс на будущие даты (CAMT `FWAV`)
+    ///
+    /// Заполняется только парсером CAMT.053, [`Statement::new`] оставляет `None`.
+    pub forward_available_balance: Option<Balance>,
     /// транзакции
     pub transactions: Vec<Transaction>,
     /// начало временного периода выписки
-    pub period_from: NaiveDate,
+    pub period_from: Date,
     /// конец временного периода выписки
-    pub period_until: NaiveDate,
+    pub period_until: Date,
+    /// формат, из которого выписка была прочитана
+    ///
+    /// Заполняется парсерами, [`Statement::new`] оставляет `None`.
+    /// При записи в любой формат не используется.
+    pub source_format: Option<SourceFormat>,
+}
+
+/// Исходный формат выписки, см. [`Statement::source_format`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceFormat {
+    /// Табличная CSV-выписка
+    Csv,
+    /// CAMT.053 XML
+    Camt053,
+    /// SWIFT MT940
+    Mt940,
+    /// SWIFT MT942
+    Mt942,
+    /// Плоский CSV, см. [`Statement::write_simple_csv`]
+    SimpleCsv,
 }
 
 impl Statement {
     /// Go to [`Statement`]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         account_id: String,
         account_name: Option<String>,
@@ -80,8 +116,8 @@ impl Statement {
         opening_balance: Option<Balance>,
         closing_balance: Option<Balance>,
         transactions: Vec<Transaction>,
-        period_from: NaiveDate,
-        period_until: NaiveDate,
+        period_from: Date,
+        period_until: Date,
     ) -> Self {
         Statement {
             account_id,
@@ -89,15 +125,452 @@ impl Statement {
             currency,
             opening_balance,
             closing_balance,
+            closing_available_balance: None,
+            forward_available_balance: None,
             transactions,
             period_from,
             period_until,
+            source_format: None,
+        }
+    }
+
+    /// Сумма транзакций (в "копейках") в заданном направлении
+    ///
+    /// Считается в `u128`, как и [`net_amount`](Self::net_amount): сумма
+    /// любого реального числа операций с `u64`-суммами не переполняется.
+    pub fn sum_amount(&self, direction: Direction) -> u128 {
+        self.transactions
+            .iter()
+            .filter(|tx| tx.direction == direction)
+            .map(|tx| u128::from(tx.amount))
+            .sum()
+    }
+
+    /// Чистый оборот по выписке: кредит минус дебет (в "копейках")
+    pub fn net_amount(&self) -> i128 {
+        self.transactions.iter().map(signed_amount).sum()
+    }
+
+    /// Длина периода выписки в днях, включая обе границы
+    ///
+    /// Выписка за один день даёт `1`. Если `period_until` раньше
+    /// `period_from`, результат не больше нуля.
+    pub fn period_days(&self) -> i64 {
+        date::days_between(self.period_from, self.period_until) + 1
+    }
+
+    /// Самая ранняя и самая поздняя дата проводки среди транзакций
+    ///
+    /// `None`, если транзакций нет. В отличие от `period_from`/`period_until`
+    /// считается по самим операциям, а не берётся из шапки выписки.
+    pub fn transaction_date_bounds(&self) -> Option<(Date, Date)> {
+        let mut dates = self.transactions.iter().map(|tx| tx.booking_date);
+        let first = dates.next()?;
+        Some(dates.fold((first, first), |(min, max), d| (min.min(d), max.max(d))))
+    }
+
+    /// Оставляет только транзакции `offset..offset + limit`, как `OFFSET`/`LIMIT` в SQL
+    ///
+    /// Без `limit` остаются все транзакции после `offset`. Балансы пересчитываются
+    /// под оставшееся окно: открывающий сдвигается на оборот отброшенных перед ним
+    /// транзакций, закрывающий - на оборот отброшенных после. Период не меняется.
+    pub fn slice_transactions(&mut self, offset: usize, limit: Option<usize>) {
+        let start = offset.min(self.transactions.len());
+        let end = match limit {
+            Some(limit) => start.saturating_add(limit).min(self.transactions.len()),
+            None => self.transactions.len(),
+        };
+
+        let net = |txs: &[Transaction]| txs.iter().map(signed_amount).sum::<i128>();
+        let before = net(&self.transactions[..start]);
+        let after = net(&self.transactions[end..]);
+
+        self.opening_balance = self.opening_balance.map(|b| b + before);
+        self.closing_balance = self.closing_balance.map(|b| b - after);
+
+        self.transactions.truncate(end);
+        self.transactions.drain(..start);
+    }
+
+    /// Заменяет транзакции и подгоняет период выписки под новые даты проводки
+    ///
+    /// Период пересчитывается по [`Statement::transaction_date_bounds`]; для пустого
+    /// списка остаётся прежним. Остатки не меняются - при необходимости их можно
+    /// досчитать через [`Statement::rebalance`].
+    pub fn with_transactions(mut self, transactions: Vec<Transaction>) -> Self {
+        self.transactions = transactions;
+        if let Some((from, until)) = self.transaction_date_bounds() {
+            self.period_from = from;
+            self.period_until = until;
+        }
+        self
+    }
+
+    /// Досчитывает закрывающий баланс: открывающий плюс [`Statement::net_amount`]
+    ///
+    /// Срабатывает только если открывающий баланс есть, а закрывающего нет
+    /// (например, MT940 без `:62F:`). Парсеры сами его не вызывают.
+    pub fn rebalance(&mut self) {
+        if let (Some(opening), None) = (self.opening_balance, self.closing_balance) {
+            self.closing_balance = Some(opening + self.net_amount());
+        }
+    }
+
+    /// Проверяет согласованность выписки
+    ///
+    /// Возвращает [`ParseError::Unbalanced`], если остатки не сходятся
+    /// (см. [`Statement::is_balanced`]), и [`ParseError::OutOfPeriod`] для первой
+    /// транзакции с датой проводки вне `period_from..=period_until`.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if let (Some(opening), Some(closing)) = (self.opening_balance, self.closing_balance)
+            && !self.is_balanced()
+        {
+            return Err(ParseError::Unbalanced {
+                opening,
+                net: self.net_amount(),
+                closing,
+            });
+        }
+
+        let period = self.period_from..=self.period_until;
+        if let Some((index, tx)) = self
+            .transactions
+            .iter()
+            .enumerate()
+            .find(|(_, tx)| !period.contains(&tx.booking_date))
+        {
+            return Err(ParseError::OutOfPeriod {
+                index,
+                date: tx.booking_date,
+                period_from: self.period_from,
+                period_until: self.period_until,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Сходятся ли остатки: открывающий плюс [`Statement::net_amount`] равен закрывающему
+    ///
+    /// Если открывающего или закрывающего баланса нет, проверять нечего и
+    /// возвращается `true`.
+    pub fn is_balanced(&self) -> bool {
+        match (self.opening_balance, self.closing_balance) {
+            (Some(opening), Some(closing)) => opening + self.net_amount() == closing,
+            _ => true,
+        }
+    }
+
+    /// Проводки для главной книги: дата проводки, сумма со знаком и описание
+    ///
+    /// Знак - как у [`signed_amount`]. Если нужна обратная конвенция
+    /// (кредит с минусом), достаточно поменять знак сумм.
+    pub fn to_ledger_entries(&self) -> Vec<(Date, i128, String)> {
+        self.transactions
+            .iter()
+            .map(|tx| (tx.booking_date, signed_amount(tx), tx.description.clone()))
+            .collect()
+    }
+
+    /// Заполняет отсутствующие имена контрагентов по их счетам
+    ///
+    /// Для каждой транзакции, где есть `counterparty`, но нет `counterparty_name`,
+    /// вызывает `resolver` со счётом контрагента. Если он вернул имя - оно записывается.
+    pub fn apply_counterparty_resolver(&mut self, resolver: impl Fn(&str) -> Option<String>) {
+        for tx in &mut self.transactions {
+            if tx.counterparty_name.is_some() {
+                continue;
+            }
+            if let Some(account) = tx.counterparty.as_deref() {
+                tx.counterparty_name = resolver(account);
+            }
+        }
+    }
+
+    /// Проставляет категории транзакций по правилам пользователя
+    ///
+    /// `rules` вызывается для каждой транзакции; если он вернул категорию, она
+    /// записывается в [`Transaction::category`], иначе прежнее значение остаётся.
+    pub fn categorize(&mut self, rules: impl Fn(&Transaction) -> Option<String>) {
+        for tx in &mut self.transactions {
+            if let Some(category) = rules(tx) {
+                tx.category = Some(category);
+            }
+        }
+    }
+
+    /// Индексы транзакций, повторяющих одну из предыдущих по [`Transaction::dedup_key`]
+    ///
+    /// Первое вхождение дубликатом не считается. Работает за O(n).
+    pub fn find_duplicates(&self) -> Vec<usize> {
+        let mut seen = HashSet::with_capacity(self.transactions.len());
+
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| !seen.insert(tx.dedup_key()))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Сортирует транзакции по дате проводки
+    ///
+    /// Сортировка стабильная: операции одного дня сохраняют исходный порядок.
+    pub fn sort_transactions_by_date(&mut self) {
+        self.transactions.sort_by_key(|tx| tx.booking_date);
+    }
+
+    /// Удаляет дубликаты (см. [`Statement::find_duplicates`]), оставляя первое вхождение
+    ///
+    /// Возвращает число удалённых транзакций.
+    pub fn dedup(&mut self) -> usize {
+        let before = self.transactions.len();
+        let mut seen = HashSet::with_capacity(before);
+        self.transactions.retain(|tx| seen.insert(tx.dedup_key()));
+
+        before - self.transactions.len()
+    }
+
+    /// Удаляет ещё не проведённые операции ([`Transaction::pending`])
+    ///
+    /// Возвращает число удалённых транзакций.
+    pub fn remove_pending(&mut self) -> usize {
+        let before = self.transactions.len();
+        self.transactions.retain(|tx| !tx.pending);
+
+        before - self.transactions.len()
+    }
+
+    /// Меняет направление у сторнирующих операций ([`Transaction::is_reversal`])
+    /// на направление отменяемой операции
+    ///
+    /// Парсеры всегда отдают фактическое направление проводки, по которому сходятся
+    /// остатки. Метод нужен, если сторно надо показать с направлением исходной
+    /// операции; после него [`Statement::is_balanced`] для выписки со сторно
+    /// вернёт `false`. Флаг `is_reversal` не сбрасывается.
+    /// Возвращает число изменённых транзакций.
+    pub fn invert_reversals(&mut self) -> usize {
+        let mut inverted = 0;
+        for tx in self.transactions.iter_mut().filter(|tx| tx.is_reversal) {
+            tx.direction = match tx.direction {
+                Direction::Credit => Direction::Debit,
+                Direction::Debit => Direction::Credit,
+            };
+            inverted += 1;
+        }
+
+        inverted
+    }
+
+    /// Хэш содержимого выписки для быстрой проверки на равенство
+    ///
+    /// Учитываются счёт, валюта, балансы, период и транзакции (дата проводки,
+    /// сумма, направление, счёт контрагента и описание без пробелов по краям).
+    /// Порядок транзакций не важен. Остальные поля, которые форматы передают
+    /// по-разному, в хэш не входят, поэтому равные хэши ещё не значат, что
+    /// [`diff_statements`](crate::diff_statements) не найдёт различий.
+    ///
+    /// Значение стабильно только в пределах одного запуска программы:
+    /// сохранять его между запусками не стоит.
+    pub fn content_hash(&self) -> u64 {
+        let mut transactions: Vec<_> = self
+            .transactions
+            .iter()
+            .map(|tx| {
+                (
+                    tx.booking_date,
+                    tx.amount,
+                    tx.direction,
+                    tx.counterparty.as_deref(),
+                    tx.description.trim(),
+                )
+            })
+            .collect();
+        transactions.sort();
+
+        let mut hasher = DefaultHasher::new();
+        self.account_id.hash(&mut hasher);
+        self.currency.hash(&mut hasher);
+        self.opening_balance.hash(&mut hasher);
+        self.closing_balance.hash(&mut hasher);
+        self.period_from.hash(&mut hasher);
+        self.period_until.hash(&mut hasher);
+        transactions.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Номер счёта как IBAN, если [`Statement::account_id`] на него похож
+    ///
+    /// Пробелы внутри убираются, буквы приводятся к верхнему регистру.
+    /// Возвращает `None`, если строка не имеет формы IBAN или не сходятся
+    /// контрольные цифры (mod-97).
+    pub fn account_iban(&self) -> Option<String> {
+        let compact: String = self
+            .account_id
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        normalize_and_check_iban(&compact).filter(|iban| iban_checksum_ok(iban))
+    }
+
+    /// Заменяет счета и имена подставными значениями, чтобы выпиской можно было поделиться
+    ///
+    /// Замена детерминирована: одинаковые исходные значения получают одинаковые
+    /// подстановки, нумерация идёт по порядку появления (сначала счёт выписки,
+    /// затем транзакции). Счета сохраняют форму (IBAN остаётся корректным IBAN,
+    /// см. [`Statement::account_iban`]), имена становятся `Party {n}`.
+    /// У адресов контрагентов заменяются улица и строки адреса, индекс, город и
+    /// страна остаются. Суммы, даты, описания и ссылки не меняются - описания
+    /// стоит просмотреть вручную.
+    pub fn anonymize(&mut self) {
+        let mut accounts: HashMap<String, String> = HashMap::new();
+        let mut names: HashMap<String, String> = HashMap::new();
+
+        let mut account = |raw: &str| {
+            let next = accounts.len() + 1;
+            accounts
+                .entry(raw.to_string())
+                .or_insert_with(|| pseudonymous_account(raw, next))
+                .clone()
+        };
+        let mut name = |raw: &str| {
+            let next = names.len() + 1;
+            names
+                .entry(raw.to_string())
+                .or_insert_with(|| format!("Party {next}"))
+                .clone()
+        };
+
+        self.account_id = account(&self.account_id);
+        self.account_name = self.account_name.as_deref().map(&mut name);
+
+        for tx in &mut self.transactions {
+            tx.counterparty = tx.counterparty.as_deref().map(&mut account);
+            tx.counterparty_name = tx.counterparty_name.as_deref().map(&mut name);
+            if let Some(address) = &mut tx.counterparty_address {
+                if address.street.is_some() {
+                    address.street = Some("Street".to_string());
+                }
+                for (i, line) in address.lines.iter_mut().enumerate() {
+                    *line = format!("Address line {}", i + 1);
+                }
+            }
+        }
+    }
+
+    /// Разбивает выписку на несколько, по одной на каждую валюту операций
+    ///
+    /// Валюта операции берётся из [`Transaction::original_currency`], а если
+    /// она не задана, то из [`Statement::currency`]. Первой идёт выписка в
+    /// валюте исходной, остальные упорядочены по коду валюты.
+    ///
+    /// Остатки относятся к валюте счёта, поэтому сохраняются только у первой
+    /// выписки; у остальных они `None`. В результирующих выписках
+    /// `original_currency` транзакций сбрасывается в `None`.
+    pub fn split_by_currency(self) -> Vec<Statement> {
+        let mut groups: BTreeMap<Currency, Vec<Transaction>> = BTreeMap::new();
+        for mut tx in self.transactions {
+            let currency = tx
+                .original_currency
+                .take()
+                .unwrap_or_else(|| self.currency.clone());
+            groups.entry(currency).or_default().push(tx);
+        }
+
+        let primary = Statement {
+            transactions: groups.remove(&self.currency).unwrap_or_default(),
+            account_id: self.account_id,
+            account_name: self.account_name,
+            currency: self.currency,
+            opening_balance: self.opening_balance,
+            closing_balance: self.closing_balance,
+            closing_available_balance: self.closing_available_balance,
+            forward_available_balance: self.forward_available_balance,
+            period_from: self.period_from,
+            period_until: self.period_until,
+            source_format: self.source_format,
+        };
+
+        let mut result = Vec::with_capacity(groups.len() + 1);
+        for (currency, transactions) in groups {
+            result.push(Statement {
+                account_id: primary.account_id.clone(),
+                account_name: primary.account_name.clone(),
+                currency,
+                opening_balance: None,
+                closing_balance: None,
+                closing_available_balance: None,
+                forward_available_balance: None,
+                transactions,
+                period_from: primary.period_from,
+                period_until: primary.period_until,
+                source_format: primary.source_format,
+            });
+        }
+        result.insert(0, primary);
+
+        result
+    }
+}
+
+/// Сумма транзакции со знаком (в "копейках"): кредит - плюс, дебет - минус
+///
+/// Та же конвенция, что у балансов [`Balance`] в [`Statement`].
+pub fn signed_amount(tx: &Transaction) -> i128 {
+    match tx.direction {
+        Direction::Credit => tx.amount as i128,
+        Direction::Debit => -(tx.amount as i128),
+    }
+}
+
+/// Вид операции (колонка "ВО" в выписках российских банков)
+///
+/// Известные коды по классификации Банка России; остальные значения
+/// сохраняются как есть в [`OperationType::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OperationType {
+    /// 01 - платёжное поручение
+    PaymentOrder,
+    /// 02 - платёжное требование
+    PaymentRequest,
+    /// 03 - денежный чек
+    CashCheck,
+    /// 04 - объявление на взнос наличными
+    CashDeposit,
+    /// 06 - инкассовое поручение
+    CollectionOrder,
+    /// 09 - мемориальный ордер
+    MemorialOrder,
+    /// 16 - платёжный ордер (частичное исполнение)
+    PartialPaymentOrder,
+    /// 17 - банковский ордер
+    BankOrder,
+
+    /// Неизвестный код, хранится как есть
+    Other(String),
+}
+
+impl OperationType {
+    /// Двузначный код операции
+    pub fn code(&self) -> &str {
+        match self {
+            OperationType::PaymentOrder => "01",
+            OperationType::PaymentRequest => "02",
+            OperationType::CashCheck => "03",
+            OperationType::CashDeposit => "04",
+            OperationType::CollectionOrder => "06",
+            OperationType::MemorialOrder => "09",
+            OperationType::PartialPaymentOrder => "16",
+            OperationType::BankOrder => "17",
+            OperationType::Other(code) => code,
         }
     }
 }
 
 /// Направление транзакции (Дебет/Кредит)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Direction {
     /// Дебет
     Debit,
@@ -114,9 +587,9 @@ pub enum Direction {
 #[derive(Debug, PartialEq, Eq)]
 pub struct Transaction {
     /// дата проводки
-    pub booking_date: NaiveDate,
+    pub booking_date: Date,
     /// дата валютирования
-    pub value_date: Option<NaiveDate>,
+    pub value_date: Option<Date>,
     /// денежная сумма (в "копейках")
     pub amount: u64,
     /// направление транзакции
@@ -127,13 +600,111 @@ pub struct Transaction {
     pub counterparty: Option<String>,
     /// имя контрагента
     pub counterparty_name: Option<String>,
+    /// почтовый адрес контрагента (CAMT `PstlAdr`)
+    pub counterparty_address: Option<PostalAddress>,
+    /// сквозной идентификатор платежа (например, EndToEndId в CAMT.053)
+    pub reference: Option<String>,
+    /// референс операции на стороне банка
+    ///
+    /// MT940: часть `:61:` после `//`, CAMT.053: `AcctSvcrRef` (или `NtryRef`, если его нет).
+    pub bank_reference: Option<String>,
+    /// код назначения платежа (ISO 20022 `Purp/Cd`)
+    pub purpose_code: Option<String>,
+    /// сведения о конвертации валют
+    pub fx: Option<FxDetails>,
+    /// сумма комиссий по операции (в "копейках")
+    pub charges: Option<u64>,
+    /// вид операции (ВО) из российских выписок
+    pub operation_code: Option<OperationType>,
+    /// сторнирующая операция (MT940 `RC`/`RD` или funds code `R`, CAMT `RvslInd`)
+    ///
+    /// [`Transaction::direction`] при этом - фактическое направление проводки:
+    /// MT940 `RC` (сторно кредита) даёт дебет, `RD` - кредит, `D`/`C` с funds
+    /// code `R` и CAMT `CdtDbtInd` берутся как есть. Направление отменяемой
+    /// операции можно получить через [`Statement::invert_reversals`].
+    pub is_reversal: bool,
+    /// операция ещё не проведена (CAMT `<Sts>PDNG</Sts>`)
+    ///
+    /// Такие операции не должны влиять на остатки, см. [`Statement::remove_pending`].
+    pub pending: bool,
+    /// валюта суммы, если она отличается от валюты выписки (CAMT `Ntry/Amt@Ccy`)
+    ///
+    /// `None` означает, что сумма в валюте [`Statement::currency`],
+    /// см. [`Statement::split_by_currency`].
+    pub original_currency: Option<Currency>,
+    /// категория операции, см. [`Statement::categorize`]
+    ///
+    /// Парсеры её не заполняют, и ни в один формат она не записывается.
+    pub category: Option<String>,
+}
+
+/// Почтовый адрес контрагента
+///
+/// Поля соответствуют элементам CAMT `PstlAdr`; адрес в свободной форме
+/// лежит в `lines` (`AdrLine`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostalAddress {
+    /// улица (`StrtNm`)
+    pub street: Option<String>,
+    /// почтовый индекс (`PstCd`)
+    pub postcode: Option<String>,
+    /// город (`TwnNm`)
+    pub town: Option<String>,
+    /// код страны ISO 3166 (`Ctry`)
+    pub country: Option<String>,
+    /// строки адреса в свободной форме (`AdrLine`)
+    pub lines: Vec<String>,
+}
+
+impl PostalAddress {
+    /// Адрес без единого заполненного поля
+    pub fn is_empty(&self) -> bool {
+        [&self.street, &self.postcode, &self.town, &self.country]
+            .into_iter()
+            .all(Option::is_none)
+            && self.lines.is_empty()
+    }
+}
+
+/// Проверки для [`Transaction::try_new`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionChecks {
+    /// Разрешить нулевую сумму (по умолчанию нет)
+    pub allow_zero_amount: bool,
+    /// На сколько дней дата валютирования может быть раньше даты проводки
+    ///
+    /// По умолчанию 30; более ранняя дата валютирования считается опечаткой.
+    /// Дата валютирования позже проводки не ограничивается.
+    pub max_value_date_lag_days: u32,
+}
+
+impl Default for TransactionChecks {
+    fn default() -> Self {
+        TransactionChecks {
+            allow_zero_amount: false,
+            max_value_date_lag_days: 30,
+        }
+    }
+}
+
+/// Сведения о конвертации валют по операции
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FxDetails {
+    /// исходная валюта
+    pub source_currency: Option<Currency>,
+    /// целевая валюта
+    pub target_currency: Option<Currency>,
+    /// курс как есть, строкой (например, "7.4738000")
+    pub rate: Option<String>,
 }
 
 impl Transaction {
     /// Go to [`Transaction`]
+    ///
+    /// Дополнительные поля (`reference`, `purpose_code`, `fx`, `charges`) остаются пустыми.
     pub fn new(
-        booking_date: NaiveDate,
-        value_date: Option<NaiveDate>,
+        booking_date: Date,
+        value_date: Option<Date>,
         amount: u64,
         direction: Direction,
         description: String,
@@ -148,8 +719,180 @@ impl Transaction {
             description,
             counterparty,
             counterparty_name,
+            counterparty_address: None,
+            reference: None,
+            bank_reference: None,
+            purpose_code: None,
+            fx: None,
+            charges: None,
+            operation_code: None,
+            is_reversal: false,
+            pending: false,
+            original_currency: None,
+            category: None,
         }
     }
+
+    /// То же, что [`Transaction::new`], но с проверкой правдоподобия
+    ///
+    /// Возвращает [`ParseError::InvalidAmount`] для нулевой суммы (если её не
+    /// разрешают `checks`) и [`ParseError::BadInput`], если дата валютирования
+    /// раньше даты проводки больше чем на `checks.max_value_date_lag_days` дней.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        booking_date: Date,
+        value_date: Option<Date>,
+        amount: u64,
+        direction: Direction,
+        description: String,
+        counterparty: Option<String>,
+        counterparty_name: Option<String>,
+        checks: &TransactionChecks,
+    ) -> Result<Self, ParseError> {
+        if amount == 0 && !checks.allow_zero_amount {
+            return Err(ParseError::InvalidAmount(format!(
+                "zero amount in transaction on {booking_date}"
+            )));
+        }
+
+        if let Some(value_date) = value_date
+            && date::days_between(value_date, booking_date)
+                > i64::from(checks.max_value_date_lag_days)
+        {
+            return Err(ParseError::BadInput(format!(
+                "value date {value_date} is more than {} days before booking date {booking_date}",
+                checks.max_value_date_lag_days
+            )));
+        }
+
+        Ok(Self::new(
+            booking_date,
+            value_date,
+            amount,
+            direction,
+            description,
+            counterparty,
+            counterparty_name,
+        ))
+    }
+
+    /// Заменяет описание, возвращает изменённую транзакцию
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Заменяет счёт и имя контрагента, возвращает изменённую транзакцию
+    pub fn with_counterparty(
+        mut self,
+        counterparty: Option<String>,
+        counterparty_name: Option<String>,
+    ) -> Self {
+        self.counterparty = counterparty;
+        self.counterparty_name = counterparty_name;
+        self
+    }
+
+    /// Заменяет сумму (в "копейках"), возвращает изменённую транзакцию
+    pub fn with_amount(mut self, amount: u64) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Ключ для поиска дубликатов: дата проводки, сумма, направление и счёт контрагента
+    ///
+    /// Описание в ключ не входит - разные форматы часто пишут его по-разному.
+    pub fn dedup_key(&self) -> (Date, u64, Direction, Option<String>) {
+        (
+            self.booking_date,
+            self.amount,
+            self.direction,
+            self.counterparty.clone(),
+        )
+    }
+}
+
+impl Currency {
+    /// ISO-код валюты, для [`Currency::Other`] - строка как есть
+    pub fn code(&self) -> &str {
+        match self {
+            Currency::RUB => "RUB",
+            Currency::EUR => "EUR",
+            Currency::USD => "USD",
+            Currency::CNY => "CNY",
+            Currency::Other(s) => s,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    /// Выводит ISO-код валюты, для [`Currency::Other`] - строку как есть
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Ord for Currency {
+    /// Порядок по коду ([`Currency::code`]); при одинаковом коде известная
+    /// валюта идёт раньше [`Currency::Other`]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let is_other = |c: &Currency| matches!(c, Currency::Other(_));
+        self.code()
+            .cmp(other.code())
+            .then_with(|| is_other(self).cmp(&is_other(other)))
+    }
+}
+
+impl PartialOrd for Currency {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for Currency {
+    type Err = ParseError;
+
+    /// Распознаёт ISO-код или русское/английское название валюты.
+    ///
+    /// Неизвестные значения попадают в [`Currency::Other`], ошибка не возвращается.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse_currency(s))
+    }
+}
+
+impl fmt::Display for OperationType {
+    /// Выводит код операции, для [`OperationType::Other`] - строку как есть
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for OperationType {
+    type Err = ParseError;
+
+    /// Распознаёт код операции; однозначные коды дополняются нулём (`"1"` -> `01`).
+    ///
+    /// Неизвестные значения попадают в [`OperationType::Other`], ошибка не возвращается.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s.trim();
+        let code = if raw.len() == 1 && raw.chars().all(|c| c.is_ascii_digit()) {
+            format!("0{raw}")
+        } else {
+            raw.to_string()
+        };
+
+        Ok(match code.as_str() {
+            "01" => OperationType::PaymentOrder,
+            "02" => OperationType::PaymentRequest,
+            "03" => OperationType::CashCheck,
+            "04" => OperationType::CashDeposit,
+            "06" => OperationType::CollectionOrder,
+            "09" => OperationType::MemorialOrder,
+            "16" => OperationType::PartialPaymentOrder,
+            "17" => OperationType::BankOrder,
+            _ => OperationType::Other(raw.to_string()),
+        })
+    }
 }
 
 impl fmt::Display for Direction {
@@ -161,6 +904,50 @@ impl fmt::Display for Direction {
     }
 }
 
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    /// Распознаёт `debit`/`credit`, коды `D`/`C` и `DBIT`/`CRDT` без учёта регистра
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "debit" | "d" | "dbit" => Ok(Direction::Debit),
+            "credit" | "c" | "crdt" => Ok(Direction::Credit),
+            _ => Err(ParseError::InvalidDirection(s.to_string())),
+        }
+    }
+}
+
+/// Человекочитаемая сводка: шапка выписки и таблица транзакций
+///
+/// Суммы и балансы выводятся в "копейках", как хранятся в структуре.
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let balance_str =
+            |balance: Option<Balance>| balance.map(|b| b.to_string()).unwrap_or("-".into());
+
+        write!(f, "Account:         {}", self.account_id)?;
+        if let Some(name) = &self.account_name {
+            write!(f, " ({name})")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Currency:        {}", self.currency)?;
+        writeln!(
+            f,
+            "Period:          {} - {}",
+            self.period_from, self.period_until
+        )?;
+        writeln!(f, "Opening balance: {}", balance_str(self.opening_balance))?;
+        writeln!(f, "Closing balance: {}", balance_str(self.closing_balance))?;
+        writeln!(f, "Transactions:    {}", self.transactions.len())?;
+
+        for tx in &self.transactions {
+            writeln!(f, "  {tx}")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl fmt::Display for Transaction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let value_date_str = self.value_date.map(|d| d.to_string()).unwrap_or_default();
@@ -182,3 +969,762 @@ impl fmt::Display for Transaction {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> Date {
+        crate::date::ymd(y, m, day).unwrap()
+    }
+
+    fn tx(amount: u64, direction: Direction) -> Transaction {
+        Transaction::new(
+            d(2023, 1, 10),
+            None,
+            amount,
+            direction,
+            String::new(),
+            None,
+            None,
+        )
+    }
+
+    fn statement_with(transactions: Vec<Transaction>) -> Statement {
+        Statement::new(
+            "ACC".to_string(),
+            None,
+            Currency::RUB,
+            None,
+            None,
+            transactions,
+            d(2023, 1, 1),
+            d(2023, 1, 31),
+        )
+    }
+
+    // Currency: Display / FromStr
+
+    #[test]
+    fn currency_display_prints_iso_code() {
+        assert_eq!(Currency::RUB.to_string(), "RUB");
+        assert_eq!(Currency::EUR.to_string(), "EUR");
+        assert_eq!(Currency::USD.to_string(), "USD");
+        assert_eq!(Currency::CNY.to_string(), "CNY");
+        assert_eq!(Currency::Other("GBP".to_string()).to_string(), "GBP");
+    }
+
+    #[test]
+    fn currency_from_str_roundtrips_known_currencies() {
+        for cur in [Currency::RUB, Currency::EUR, Currency::USD, Currency::CNY] {
+            let parsed: Currency = cur.to_string().parse().unwrap();
+            assert_eq!(parsed, cur);
+        }
+    }
+
+    #[test]
+    fn currency_from_str_roundtrips_other() {
+        let cur = Currency::Other("GBP".to_string());
+        let parsed: Currency = cur.to_string().parse().unwrap();
+        assert_eq!(parsed, cur);
+    }
+
+    #[test]
+    fn currency_from_str_accepts_russian_names() {
+        assert_eq!(
+            "Российский рубль".parse::<Currency>().unwrap(),
+            Currency::RUB
+        );
+        assert_eq!("евро".parse::<Currency>().unwrap(), Currency::EUR);
+    }
+
+    #[test]
+    fn operation_type_from_str_maps_known_codes() {
+        assert_eq!(
+            "01".parse::<OperationType>().unwrap(),
+            OperationType::PaymentOrder
+        );
+        assert_eq!(
+            " 17 ".parse::<OperationType>().unwrap(),
+            OperationType::BankOrder
+        );
+        assert_eq!(
+            "6".parse::<OperationType>().unwrap(),
+            OperationType::CollectionOrder
+        );
+        assert_eq!(OperationType::BankOrder.to_string(), "17");
+    }
+
+    #[test]
+    fn operation_type_from_str_keeps_unknown_code() {
+        let parsed: OperationType = "42".parse().unwrap();
+        assert_eq!(parsed, OperationType::Other("42".to_string()));
+        assert_eq!(parsed.to_string(), "42");
+    }
+
+    // Direction: FromStr
+
+    #[test]
+    fn direction_from_str_accepts_words_and_codes() {
+        assert_eq!("debit".parse::<Direction>().unwrap(), Direction::Debit);
+        assert_eq!(" CRDT ".parse::<Direction>().unwrap(), Direction::Credit);
+        assert_eq!("c".parse::<Direction>().unwrap(), Direction::Credit);
+        assert!(matches!(
+            "both".parse::<Direction>(),
+            Err(ParseError::InvalidDirection(_))
+        ));
+    }
+
+    // Statement: sum_amount / net_amount
+
+    #[test]
+    fn sum_amount_sums_only_given_direction() {
+        let stmt = statement_with(vec![
+            tx(10_000, Direction::Credit),
+            tx(2_500, Direction::Debit),
+            tx(5_000, Direction::Credit),
+            tx(1_000, Direction::Debit),
+        ]);
+
+        assert_eq!(stmt.sum_amount(Direction::Credit), 15_000);
+        assert_eq!(stmt.sum_amount(Direction::Debit), 3_500);
+    }
+
+    #[test]
+    fn sum_amount_does_not_overflow_u64() {
+        let stmt = statement_with(vec![
+            tx(u64::MAX, Direction::Credit),
+            tx(1, Direction::Credit),
+        ]);
+
+        assert_eq!(stmt.sum_amount(Direction::Credit), u128::from(u64::MAX) + 1);
+    }
+
+    #[test]
+    fn net_amount_is_credit_minus_debit() {
+        let stmt = statement_with(vec![
+            tx(10_000, Direction::Credit),
+            tx(2_500, Direction::Debit),
+            tx(5_000, Direction::Credit),
+            tx(20_000, Direction::Debit),
+        ]);
+
+        assert_eq!(stmt.net_amount(), -7_500);
+        assert_eq!(statement_with(Vec::new()).net_amount(), 0);
+    }
+
+    #[test]
+    fn net_amount_does_not_overflow_on_large_amounts() {
+        let stmt = statement_with(vec![
+            tx(u64::MAX, Direction::Credit),
+            tx(u64::MAX, Direction::Credit),
+        ]);
+
+        assert_eq!(stmt.net_amount(), 2 * u64::MAX as i128);
+    }
+
+    #[test]
+    fn slice_transactions_keeps_window_and_shifts_balances() {
+        let mut stmt = statement_with(vec![
+            tx(100, Direction::Credit),
+            tx(20, Direction::Debit),
+            tx(300, Direction::Credit),
+            tx(4, Direction::Debit),
+        ]);
+        stmt.opening_balance = Some(1_000);
+        stmt.closing_balance = Some(1_376);
+
+        stmt.slice_transactions(1, Some(2));
+
+        let amounts: Vec<u64> = stmt.transactions.iter().map(|tx| tx.amount).collect();
+        assert_eq!(amounts, vec![20, 300]);
+        assert_eq!(stmt.opening_balance, Some(1_100));
+        assert_eq!(stmt.closing_balance, Some(1_380));
+    }
+
+    #[test]
+    fn slice_transactions_clamps_out_of_range_window() {
+        let mut stmt = statement_with(vec![tx(100, Direction::Credit)]);
+        stmt.opening_balance = Some(0);
+
+        stmt.slice_transactions(5, Some(usize::MAX));
+
+        assert!(stmt.transactions.is_empty());
+        assert_eq!(stmt.opening_balance, Some(100));
+        assert_eq!(stmt.closing_balance, None);
+    }
+
+    #[test]
+    fn rebalance_derives_closing_from_opening_and_transactions() {
+        let mut stmt = statement_with(vec![
+            tx(5_000, Direction::Credit),
+            tx(1_500, Direction::Debit),
+        ]);
+        stmt.opening_balance = Some(10_000);
+
+        stmt.rebalance();
+
+        assert_eq!(stmt.closing_balance, Some(13_500));
+    }
+
+    #[test]
+    fn rebalance_keeps_existing_closing_and_needs_opening() {
+        let mut stmt = statement_with(vec![tx(5_000, Direction::Credit)]);
+        stmt.rebalance();
+        assert_eq!(stmt.closing_balance, None);
+
+        stmt.opening_balance = Some(0);
+        stmt.closing_balance = Some(42);
+        stmt.rebalance();
+        assert_eq!(stmt.closing_balance, Some(42));
+    }
+
+    #[test]
+    fn apply_counterparty_resolver_fills_only_missing_names() {
+        let mut with_name = tx(100, Direction::Credit);
+        with_name.counterparty = Some("DE02".to_string());
+        with_name.counterparty_name = Some("Bank Name".to_string());
+
+        let mut without_name = tx(200, Direction::Debit);
+        without_name.counterparty = Some("DE01".to_string());
+
+        let mut unknown = tx(300, Direction::Debit);
+        unknown.counterparty = Some("DE03".to_string());
+
+        let no_account = tx(400, Direction::Credit);
+
+        let mut stmt = statement_with(vec![with_name, without_name, unknown, no_account]);
+
+        stmt.apply_counterparty_resolver(|iban| match iban {
+            "DE01" | "DE02" => Some("Resolved GmbH".to_string()),
+            _ => None,
+        });
+
+        let names: Vec<Option<&str>> = stmt
+            .transactions
+            .iter()
+            .map(|t| t.counterparty_name.as_deref())
+            .collect();
+        assert_eq!(
+            names,
+            vec![Some("Bank Name"), Some("Resolved GmbH"), None, None]
+        );
+    }
+
+    // period_days / transaction_date_bounds
+
+    #[test]
+    fn period_days_counts_both_ends() {
+        let mut stmt = statement_with(vec![]);
+        assert_eq!(stmt.period_days(), 31);
+
+        stmt.period_until = stmt.period_from;
+        assert_eq!(stmt.period_days(), 1);
+    }
+
+    #[test]
+    fn transaction_date_bounds_uses_booking_dates() {
+        assert_eq!(statement_with(vec![]).transaction_date_bounds(), None);
+
+        let mut late = tx(100, Direction::Credit);
+        late.booking_date = d(2023, 1, 20);
+        let mut early = tx(200, Direction::Debit);
+        early.booking_date = d(2023, 1, 3);
+        let stmt = statement_with(vec![tx(300, Direction::Credit), late, early]);
+
+        assert_eq!(
+            stmt.transaction_date_bounds(),
+            Some((d(2023, 1, 3), d(2023, 1, 20)))
+        );
+    }
+
+    #[test]
+    fn with_transactions_recomputes_period() {
+        let mut first = tx(100, Direction::Credit);
+        first.booking_date = d(2023, 2, 3);
+        let mut last = tx(200, Direction::Debit);
+        last.booking_date = d(2023, 2, 17);
+
+        let stmt =
+            statement_with(vec![tx(1, Direction::Credit)]).with_transactions(vec![last, first]);
+
+        assert_eq!(stmt.transactions.len(), 2);
+        assert_eq!(stmt.period_from, d(2023, 2, 3));
+        assert_eq!(stmt.period_until, d(2023, 2, 17));
+
+        // пустой список период не трогает
+        let stmt = stmt.with_transactions(Vec::new());
+        assert!(stmt.transactions.is_empty());
+        assert_eq!(stmt.period_from, d(2023, 2, 3));
+        assert_eq!(stmt.period_until, d(2023, 2, 17));
+    }
+
+    // Transaction::try_new
+
+    fn try_tx(
+        amount: u64,
+        value_date: Option<Date>,
+        checks: &TransactionChecks,
+    ) -> Result<Transaction, ParseError> {
+        Transaction::try_new(
+            d(2023, 1, 10),
+            value_date,
+            amount,
+            Direction::Debit,
+            "Rent".to_string(),
+            None,
+            None,
+            checks,
+        )
+    }
+
+    #[test]
+    fn try_new_accepts_plausible_transaction() {
+        let checks = TransactionChecks::default();
+
+        let tx = try_tx(100, Some(d(2023, 1, 8)), &checks).unwrap();
+        assert_eq!(tx.amount, 100);
+        assert_eq!(tx.value_date, Some(d(2023, 1, 8)));
+
+        // валютирование позже проводки и ровно на границе допуска
+        assert!(try_tx(100, Some(d(2023, 3, 1)), &checks).is_ok());
+        assert!(try_tx(100, Some(d(2022, 12, 11)), &checks).is_ok());
+    }
+
+    #[test]
+    fn try_new_rejects_zero_amount_unless_allowed() {
+        let err = try_tx(0, None, &TransactionChecks::default()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidAmount(_)), "{err:?}");
+
+        let checks = TransactionChecks {
+            allow_zero_amount: true,
+            ..Default::default()
+        };
+        assert_eq!(try_tx(0, None, &checks).unwrap().amount, 0);
+    }
+
+    #[test]
+    fn try_new_rejects_value_date_long_before_booking() {
+        let err = try_tx(100, Some(d(2022, 12, 10)), &TransactionChecks::default()).unwrap_err();
+        assert!(matches!(err, ParseError::BadInput(_)), "{err:?}");
+
+        let checks = TransactionChecks {
+            max_value_date_lag_days: 0,
+            ..Default::default()
+        };
+        assert!(try_tx(100, Some(d(2023, 1, 9)), &checks).is_err());
+        assert!(try_tx(100, Some(d(2023, 1, 10)), &checks).is_ok());
+    }
+
+    // find_duplicates / dedup_key
+
+    #[test]
+    fn dedup_key_ignores_description() {
+        let a = tx(100, Direction::Credit);
+        let mut b = tx(100, Direction::Credit);
+        b.description = "другое описание".to_string();
+        assert_eq!(a.dedup_key(), b.dedup_key());
+
+        b.counterparty = Some("ACC2".to_string());
+        assert_ne!(a.dedup_key(), b.dedup_key());
+    }
+
+    #[test]
+    fn find_duplicates_returns_repeated_indices_only() {
+        let stmt = statement_with(vec![
+            tx(100, Direction::Credit),
+            tx(100, Direction::Debit),
+            tx(100, Direction::Credit),
+            tx(200, Direction::Credit),
+            tx(100, Direction::Credit),
+        ]);
+        assert_eq!(stmt.find_duplicates(), vec![2, 4]);
+    }
+
+    #[test]
+    fn find_duplicates_handles_large_input() {
+        // 100 000 операций, каждая десятая повторяет предыдущую:
+        // попарное сравнение здесь заметно тормозило бы
+        let n = 100_000u64;
+        let mut transactions = Vec::with_capacity(n as usize);
+        let mut expected = Vec::new();
+        for i in 0..n {
+            let amount = if i % 10 == 9 { i - 1 } else { i };
+            if i % 10 == 9 {
+                expected.push(i as usize);
+            }
+            transactions.push(tx(amount, Direction::Credit));
+        }
+
+        let stmt = statement_with(transactions);
+        assert_eq!(stmt.find_duplicates(), expected);
+    }
+
+    // sort_transactions_by_date / dedup
+
+    #[test]
+    fn sort_transactions_by_date_is_stable() {
+        let mut late = tx(1, Direction::Credit);
+        late.booking_date = d(2023, 1, 20);
+        let mut stmt = statement_with(vec![
+            late,
+            tx(2, Direction::Credit),
+            tx(3, Direction::Debit),
+        ]);
+
+        stmt.sort_transactions_by_date();
+
+        let amounts: Vec<u64> = stmt.transactions.iter().map(|t| t.amount).collect();
+        assert_eq!(amounts, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn dedup_keeps_first_occurrence() {
+        let mut stmt = statement_with(vec![
+            tx(100, Direction::Credit),
+            tx(100, Direction::Debit),
+            tx(100, Direction::Credit),
+        ]);
+
+        assert_eq!(stmt.dedup(), 1);
+        assert_eq!(stmt.transactions.len(), 2);
+        assert!(stmt.find_duplicates().is_empty());
+    }
+
+    #[test]
+    fn invert_reversals_flips_only_reversal_entries() {
+        let mut reversal = tx(100, Direction::Debit);
+        reversal.is_reversal = true;
+        let mut stmt = statement_with(vec![tx(200, Direction::Debit), reversal]);
+
+        assert_eq!(stmt.invert_reversals(), 1);
+        assert_eq!(stmt.transactions[0].direction, Direction::Debit);
+        assert_eq!(stmt.transactions[1].direction, Direction::Credit);
+        assert!(stmt.transactions[1].is_reversal);
+    }
+
+    #[test]
+    fn statement_display_prints_header_and_transactions() {
+        let mut stmt = statement_with(vec![
+            tx(100, Direction::Debit).with_description("Аренда"),
+            tx(200, Direction::Credit),
+        ]);
+        stmt.opening_balance = Some(-500);
+
+        let text = stmt.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].contains("ACC"), "{text}");
+        assert!(text.contains("Currency:        RUB"), "{text}");
+        assert!(
+            text.contains("Period:          2023-01-01 - 2023-01-31"),
+            "{text}"
+        );
+        assert!(text.contains("Opening balance: -500"), "{text}");
+        assert!(text.contains("Closing balance: -"), "{text}");
+        assert!(text.contains("Transactions:    2"), "{text}");
+        assert_eq!(lines.len(), 6 + 2);
+        assert!(lines[6].ends_with("Аренда"), "{text}");
+    }
+
+    #[test]
+    fn transaction_with_setters_chain() {
+        let tx = tx(100, Direction::Debit)
+            .with_amount(2_500)
+            .with_description("Аренда")
+            .with_counterparty(
+                Some("40702810".to_string()),
+                Some("ООО Ромашка".to_string()),
+            );
+
+        assert_eq!(tx.amount, 2_500);
+        assert_eq!(tx.description, "Аренда");
+        assert_eq!(tx.counterparty.as_deref(), Some("40702810"));
+        assert_eq!(tx.counterparty_name.as_deref(), Some("ООО Ромашка"));
+        assert_eq!(tx.direction, Direction::Debit);
+        assert_eq!(tx.booking_date, d(2023, 1, 10));
+    }
+
+    #[test]
+    fn validate_reports_unbalanced_and_out_of_period() {
+        let mut st = statement_with(vec![tx(300, Direction::Credit)]);
+        assert!(st.validate().is_ok());
+
+        st.opening_balance = Some(1_000);
+        st.closing_balance = Some(1_000);
+        assert!(matches!(
+            st.validate(),
+            Err(ParseError::Unbalanced {
+                opening: 1_000,
+                net: 300,
+                closing: 1_000
+            })
+        ));
+
+        st.closing_balance = Some(1_300);
+        st.transactions[0].booking_date = d(2023, 2, 1);
+        assert!(matches!(
+            st.validate(),
+            Err(ParseError::OutOfPeriod { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn categorize_applies_keyword_rule() {
+        let mut st = statement_with(vec![
+            tx(100, Direction::Debit).with_description("Coffee at Starbucks"),
+            tx(200, Direction::Debit).with_description("Monthly rent"),
+            tx(300, Direction::Credit).with_description("Salary"),
+        ]);
+        st.transactions[2].category = Some("income".to_string());
+
+        st.categorize(|tx| {
+            let description = tx.description.to_lowercase();
+            if description.contains("coffee") {
+                Some("food".to_string())
+            } else if description.contains("rent") {
+                Some("housing".to_string())
+            } else {
+                None
+            }
+        });
+
+        let categories: Vec<_> = st
+            .transactions
+            .iter()
+            .map(|tx| tx.category.as_deref())
+            .collect();
+        assert_eq!(
+            categories,
+            vec![Some("food"), Some("housing"), Some("income")]
+        );
+    }
+
+    #[test]
+    fn is_balanced_checks_opening_plus_net() {
+        let mut st = statement_with(vec![tx(300, Direction::Credit), tx(100, Direction::Debit)]);
+        st.opening_balance = Some(1_000);
+        st.closing_balance = Some(1_200);
+        assert!(st.is_balanced());
+
+        st.closing_balance = Some(1_201);
+        assert!(!st.is_balanced());
+    }
+
+    #[test]
+    fn is_balanced_without_balances_is_true() {
+        let mut st = statement_with(vec![tx(300, Direction::Credit)]);
+        assert!(st.is_balanced());
+
+        st.opening_balance = Some(1_000);
+        assert!(st.is_balanced());
+
+        st.opening_balance = None;
+        st.closing_balance = Some(0);
+        assert!(st.is_balanced());
+    }
+
+    #[test]
+    fn split_by_currency_partitions_transactions() {
+        let mut usd = tx(300, Direction::Debit);
+        usd.original_currency = Some(Currency::USD);
+        let mut st = statement_with(vec![
+            tx(100, Direction::Credit),
+            usd,
+            tx(200, Direction::Debit),
+        ]);
+        st.opening_balance = Some(1_000);
+        st.closing_balance = Some(900);
+
+        let parts = st.split_by_currency();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].currency, Currency::RUB);
+        assert_eq!(parts[0].transactions.len(), 2);
+        assert_eq!(parts[0].opening_balance, Some(1_000));
+        assert_eq!(parts[1].currency, Currency::USD);
+        assert_eq!(parts[1].transactions.len(), 1);
+        assert_eq!(parts[1].transactions[0].amount, 300);
+        assert_eq!(parts[1].transactions[0].original_currency, None);
+        assert_eq!(parts[1].opening_balance, None);
+        assert_eq!(parts[1].account_id, "ACC");
+    }
+
+    #[test]
+    fn split_by_currency_keeps_single_currency_statement() {
+        let st = statement_with(vec![tx(100, Direction::Credit)]);
+        let parts = st.split_by_currency();
+        assert_eq!(
+            parts,
+            vec![statement_with(vec![tx(100, Direction::Credit)])]
+        );
+    }
+
+    #[test]
+    fn content_hash_ignores_order_and_description_padding() {
+        let mut credit = tx(200, Direction::Credit);
+        credit.description = "Оплата".to_string();
+        let mut padded = tx(200, Direction::Credit);
+        padded.description = "  Оплата\n".to_string();
+        // поля вне хэша
+        padded.reference = Some("E2E".to_string());
+
+        let a = statement_with(vec![tx(100, Direction::Debit), credit]);
+        let b = statement_with(vec![padded, tx(100, Direction::Debit)]);
+
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn content_hash_changes_with_amount() {
+        let a = statement_with(vec![tx(100, Direction::Debit)]);
+        let b = statement_with(vec![tx(101, Direction::Debit)]);
+
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn content_hash_distinguishes_known_currency_from_other() {
+        let a = statement_with(vec![tx(100, Direction::Debit)]);
+        let mut b = statement_with(vec![tx(100, Direction::Debit)]);
+        b.currency = Currency::Other("RUB".to_string());
+
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn account_iban_normalizes_valid_iban() {
+        let mut stmt = statement_with(vec![]);
+        stmt.account_id = "de89 3704 0044 0532 0130 00".to_string();
+
+        assert_eq!(
+            stmt.account_iban().as_deref(),
+            Some("DE89370400440532013000")
+        );
+    }
+
+    #[test]
+    fn account_iban_is_none_for_non_iban_or_bad_checksum() {
+        let mut stmt = statement_with(vec![]);
+        stmt.account_id = "40702810000000000001".to_string();
+        assert_eq!(stmt.account_iban(), None);
+
+        stmt.account_id = "DE88370400440532013000".to_string();
+        assert_eq!(stmt.account_iban(), None);
+    }
+
+    #[test]
+    fn currencies_sort_by_code() {
+        let mut currencies = vec![
+            Currency::USD,
+            Currency::Other("GBP".into()),
+            Currency::RUB,
+            Currency::Other("EUR".into()),
+            Currency::EUR,
+            Currency::CNY,
+        ];
+        currencies.sort();
+
+        assert_eq!(
+            currencies,
+            vec![
+                Currency::CNY,
+                Currency::EUR,
+                Currency::Other("EUR".into()),
+                Currency::Other("GBP".into()),
+                Currency::RUB,
+                Currency::USD,
+            ]
+        );
+
+        let grouped: std::collections::BTreeMap<Currency, usize> =
+            [(Currency::USD, 1), (Currency::EUR, 2)]
+                .into_iter()
+                .collect();
+        assert_eq!(grouped.keys().next(), Some(&Currency::EUR));
+    }
+
+    // anonymize
+
+    #[test]
+    fn anonymize_replaces_accounts_and_names_but_keeps_amounts_and_dates() {
+        let sample = || {
+            let mut first = tx(100, Direction::Credit).with_counterparty(
+                Some("DE89370400440532013000".to_string()),
+                Some("Иван Петров".to_string()),
+            );
+            first.counterparty_address = Some(PostalAddress {
+                street: Some("ул. Ленина, 1".to_string()),
+                town: Some("Москва".to_string()),
+                ..Default::default()
+            });
+            let second = tx(250, Direction::Debit).with_counterparty(
+                Some("DE89370400440532013000".to_string()),
+                Some("Иван Петров".to_string()),
+            );
+            let mut stmt = statement_with(vec![first, second]);
+            stmt.account_id = "GB82WEST12345698765432".to_string();
+            stmt.account_name = Some("ООО Ромашка".to_string());
+            stmt
+        };
+        let original = sample();
+        let mut stmt = sample();
+
+        stmt.anonymize();
+
+        assert_ne!(stmt.account_id, original.account_id);
+        assert!(stmt.account_iban().is_some(), "{}", stmt.account_id);
+        assert_eq!(stmt.account_name.as_deref(), Some("Party 1"));
+
+        let (a, b) = (&stmt.transactions[0], &stmt.transactions[1]);
+        assert_ne!(a.counterparty, original.transactions[0].counterparty);
+        assert!(a.counterparty.as_deref().unwrap().starts_with("DE"));
+        assert_eq!(a.counterparty, b.counterparty);
+        assert_eq!(a.counterparty_name.as_deref(), Some("Party 2"));
+        assert_eq!(b.counterparty_name.as_deref(), Some("Party 2"));
+        let address = a.counterparty_address.as_ref().unwrap();
+        assert_eq!(address.street.as_deref(), Some("Street"));
+        assert_eq!(address.town.as_deref(), Some("Москва"));
+
+        for (anon, orig) in stmt.transactions.iter().zip(&original.transactions) {
+            assert_eq!(anon.amount, orig.amount);
+            assert_eq!(anon.direction, orig.direction);
+            assert_eq!(anon.booking_date, orig.booking_date);
+        }
+        assert_eq!(stmt.period_from, original.period_from);
+        assert_eq!(stmt.period_until, original.period_until);
+
+        // повторный запуск на той же выписке даёт тот же результат
+        let mut again = sample();
+        again.anonymize();
+        assert_eq!(again.account_id, stmt.account_id);
+        assert_eq!(again.transactions[0].counterparty, a.counterparty);
+    }
+
+    // signed_amount / to_ledger_entries
+
+    #[test]
+    fn signed_amount_follows_balance_convention() {
+        assert_eq!(signed_amount(&tx(150, Direction::Credit)), 150);
+        assert_eq!(signed_amount(&tx(150, Direction::Debit)), -150);
+    }
+
+    #[test]
+    fn to_ledger_entries_makes_debits_negative() {
+        let mut debit = tx(2_500, Direction::Debit);
+        debit.description = "Аренда".to_string();
+        let stmt = statement_with(vec![tx(10_000, Direction::Credit), debit]);
+
+        let entries = stmt.to_ledger_entries();
+
+        assert_eq!(
+            entries,
+            vec![
+                (d(2023, 1, 10), 10_000, String::new()),
+                (d(2023, 1, 10), -2_500, "Аренда".to_string()),
+            ]
+        );
+        let total: i128 = entries.iter().map(|(_, amount, _)| amount).sum();
+        assert_eq!(total, stmt.net_amount());
+    }
+}
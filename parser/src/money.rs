@@ -0,0 +1,214 @@
+//! Форматирование и разбор денежных сумм
+//!
+//! Суммы в крейте хранятся целым числом минорных единиц ("копеек").
+//! Число знаков после запятой передаётся явно; суммы в [`Statement`](crate::Statement)
+//! всегда хранятся в сотых долях, то есть с двумя знаками.
+//!
+//! ```rust
+//! use parser::RoundingMode;
+//! use parser::money::{format_minor_units, parse_amount};
+//!
+//! # fn main() -> Result<(), parser::ParseError> {
+//! let text = format_minor_units(12345_u64, 2, '.');
+//! assert_eq!(text, "123.45");
+//!
+//! assert_eq!(parse_amount(&text, 2, RoundingMode::Error)?, 12345);
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error::ParseError;
+use crate::utils::RoundingMode;
+
+/// Форматирует сумму в минорных единицах как десятичное число
+///
+/// Знак не выводится: во всех форматах направление записывается отдельно
+/// (D/C, `CdtDbtInd`, колонки дебета и кредита).
+///
+/// `decimals` может быть любым: если `10^decimals` не помещается в `u128`
+/// (больше 38 знаков), целая часть заведомо нулевая и всё значение уходит
+/// в дробную часть.
+///
+/// ```rust
+/// use parser::money::format_minor_units;
+///
+/// assert_eq!(format_minor_units(12345_u64, 2, '.'), "123.45");
+/// assert_eq!(format_minor_units(-5_i64, 2, ','), "0,05");
+/// assert_eq!(format_minor_units(42_u32, 0, '.'), "42");
+/// ```
+pub fn format_minor_units<T>(value: T, decimals: u32, decimal_separator: char) -> String
+where
+    T: Into<i128>,
+{
+    let v = value.into().unsigned_abs();
+    if decimals == 0 {
+        return v.to_string();
+    }
+
+    // |v| <= 2^127 < 10^39, поэтому без масштаба всё значение - дробная часть
+    let (units, frac) = match 10u128.checked_pow(decimals) {
+        Some(scale) => (v / scale, v % scale),
+        None => (0, v),
+    };
+    let width = decimals as usize;
+
+    format!("{units}{decimal_separator}{frac:0width$}")
+}
+
+/// Разбирает неотрицательную сумму в минорные единицы
+///
+/// Принимает `.` или `,` как десятичный разделитель и пробелы как разделитель
+/// тысяч; если в строке есть и `,` и `.`, запятая считается разделителем тысяч.
+/// Лишние знаки после запятой обрабатываются согласно `rounding`.
+///
+/// ```rust
+/// use parser::RoundingMode;
+/// use parser::money::parse_amount;
+///
+/// assert_eq!(parse_amount("123.45", 2, RoundingMode::Error).unwrap(), 12345);
+/// assert_eq!(parse_amount("1 234,5", 2, RoundingMode::Error).unwrap(), 123450);
+/// assert_eq!(parse_amount("0.125", 2, RoundingMode::HalfEven).unwrap(), 12);
+/// assert!(parse_amount("-1", 2, RoundingMode::Error).is_err());
+/// ```
+pub fn parse_amount(raw: &str, decimals: u32, rounding: RoundingMode) -> Result<u64, ParseError> {
+    let mut cleaned = raw.trim().replace(' ', "");
+
+    if raw.contains(',') {
+        if raw.contains('.') {
+            cleaned = cleaned.replace(',', "");
+        } else {
+            cleaned = cleaned.replace(',', ".");
+        }
+    }
+
+    if cleaned.is_empty() {
+        return Err(ParseError::InvalidAmount("empty amount".into()));
+    }
+    if cleaned.starts_with('-') {
+        return Err(ParseError::InvalidAmount(format!(
+            "negative amount: {cleaned}"
+        )));
+    }
+
+    let mut split = cleaned.split('.');
+    // cleaned точно не пусто, так что ошибки здесь быть не может
+    let int_part = split
+        .next()
+        .expect("cleaned is verified to be non-empty so panic! must be impossible to happen");
+    let dec_part = split.next().unwrap_or("");
+    if split.next().is_some() {
+        // больше одной точки - странный формат
+        return Err(ParseError::InvalidAmount(format!(
+            "too many dots in amount: {cleaned}"
+        )));
+    }
+
+    // ",50" / ".5" - целая часть опущена, считаем её нулём
+    let int_part: u64 = if int_part.is_empty() && !dec_part.is_empty() {
+        0
+    } else {
+        int_part.parse()?
+    };
+
+    if !dec_part.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ParseError::InvalidAmount(format!(
+            "invalid fractional part: {cleaned}"
+        )));
+    }
+
+    let too_large = || ParseError::InvalidAmount(format!("amount is too large: {cleaned}"));
+    let scale = 10u64.checked_pow(decimals).ok_or_else(too_large)?;
+
+    let width = decimals as usize;
+    let (kept, rest) = if dec_part.len() > width {
+        if rounding == RoundingMode::Error {
+            return Err(ParseError::InvalidAmount(format!(
+                "too many fractional digits in amount: {cleaned}"
+            )));
+        }
+        dec_part.split_at(width)
+    } else {
+        (dec_part, "")
+    };
+
+    // "5" при двух знаках - это 50 копеек
+    let frac = kept
+        .bytes()
+        .chain(std::iter::repeat_n(b'0', width - kept.len()))
+        .fold(0u64, |acc, b| acc * 10 + u64::from(b - b'0'));
+
+    let total = int_part
+        .checked_mul(scale)
+        .and_then(|v| v.checked_add(frac))
+        .ok_or_else(too_large)?;
+
+    let round_up = match rounding {
+        RoundingMode::HalfEven if !rest.is_empty() => {
+            let mut rest_digits = rest.bytes().map(|b| b - b'0');
+            let first = rest_digits.next().unwrap_or(0);
+            let tail_is_zero = rest_digits.all(|d| d == 0);
+            first > 5 || (first == 5 && (!tail_is_zero || total % 2 == 1))
+        }
+        _ => false,
+    };
+
+    // перенос в целую часть (0.995 -> 1.00) получается сам собой
+    total.checked_add(round_up as u64).ok_or_else(too_large)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_respects_decimals() {
+        assert_eq!(format_minor_units(12345_i32, 0, '.'), "12345");
+        assert_eq!(format_minor_units(12345_i32, 3, '.'), "12.345");
+        assert_eq!(format_minor_units(5_i32, 3, ','), "0,005");
+    }
+
+    #[test]
+    fn format_does_not_overflow_on_large_decimals() {
+        assert_eq!(
+            format_minor_units(1_u64, 39, '.'),
+            format!("0.{}1", "0".repeat(38))
+        );
+        assert_eq!(
+            format_minor_units(i128::MIN, 38, '.'),
+            "1.70141183460469231731687303715884105728"
+        );
+    }
+
+    #[test]
+    fn parse_respects_decimals() {
+        assert_eq!(parse_amount("12", 0, RoundingMode::Error).unwrap(), 12);
+        assert_eq!(parse_amount("1.5", 3, RoundingMode::Error).unwrap(), 1_500);
+        assert_eq!(
+            parse_amount("1.2345", 3, RoundingMode::Truncate).unwrap(),
+            1_234
+        );
+        assert!(parse_amount("1.5", 0, RoundingMode::Error).is_err());
+    }
+
+    #[test]
+    fn parse_half_even_without_fraction_rounds_to_even_unit() {
+        assert_eq!(parse_amount("2.5", 0, RoundingMode::HalfEven).unwrap(), 2);
+        assert_eq!(parse_amount("3.5", 0, RoundingMode::HalfEven).unwrap(), 4);
+    }
+
+    #[test]
+    fn parse_overflow_is_error() {
+        assert!(matches!(
+            parse_amount("18446744073709551615", 2, RoundingMode::Error),
+            Err(ParseError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn format_and_parse_roundtrip() {
+        for v in [0_u64, 1, 99, 100, 12345, 1_000_000] {
+            let text = format_minor_units(v, 2, '.');
+            assert_eq!(parse_amount(&text, 2, RoundingMode::Error).unwrap(), v);
+        }
+    }
+}
@@ -1,33 +1,51 @@
+mod mt942;
 mod utils;
-use crate::error::ParseError;
-use crate::model::{Balance, Currency, Direction, Statement, Transaction};
-use crate::utils::{parse_amount, parse_currency};
-use chrono::NaiveDate;
+use crate::date::Date;
+use crate::error::{ParseError, ParseWarning};
+use crate::model::{Balance, Currency, Direction, SourceFormat, Statement, Transaction};
+use crate::utils::{
+    MultipleStatementsPolicy, RowErrors, collect_transactions, currency_for_iban, excerpt_around,
+    parse_currency,
+};
 use std::io::{BufReader, Read};
 use utils::*;
 
+pub use mt942::Mt942Data;
+
 #[derive(Debug, Clone)]
 pub struct Mt940Message {
     /// :20: Transaction Reference Number (может быть пустым у некоторых банков)
     pub transaction_reference: Option<String>,
 
+    /// :21: Related Reference, опционально
+    pub related_reference: Option<String>,
+
     /// :25: Account Identification (номер счёта/IBAN как есть)
     pub account_id: String,
 
     /// :28C: Statement Number/Sequence, сырой текст, например "49/2" или "00001/001"
     pub statement_number: Option<String>,
 
-    /// :60F: / :60M: Opening Balance
+    /// Opening Balance: `:60F:`, а при его отсутствии - `:60M:`
     pub opening_balance: Mt940Balance,
 
+    /// :60M: промежуточный Opening Balance страницы-продолжения многостраничной выписки
+    pub intermediate_opening_balance: Option<Mt940Balance>,
+
     /// Список всех проводок (:61: + связанные текстовые блоки, включая :86: и голые строки)
     pub entries: Vec<Mt940Entry>,
 
-    /// :62F: Closing Balance (может отсутствовать в кривых файлах)
+    /// :62F: финальный Closing Balance (может отсутствовать в кривых файлах)
     pub closing_balance: Option<Mt940Balance>,
 
+    /// :62M: промежуточный Closing Balance многостраничной выписки (последний встреченный)
+    pub intermediate_closing_balance: Option<Mt940Balance>,
+
     /// :64: Closing Available Balance (доступный баланс), опционально
     pub closing_available_balance: Option<Mt940Balance>,
+
+    /// Строки :86: до первой :61: - сведения ко всей выписке, а не к проводке
+    pub statement_info: Vec<String>,
 }
 
 fn parse_balance(value: &str) -> Result<Mt940Balance, ParseError> {
@@ -57,41 +75,83 @@ fn parse_balance(value: &str) -> Result<Mt940Balance, ParseError> {
     }
 
     let date = &rest[0..6];
-    let currency = &rest[6..9];
-    let amount = &rest[9..];
+    // в урезанных выписках валюты может не быть: сразу за датой идёт сумма
+    let (currency, amount) = match rest.get(6..9) {
+        Some(currency) if currency.chars().all(|c| c.is_ascii_alphabetic()) => {
+            (currency, &rest[9..])
+        }
+        _ => ("", &rest[6..]),
+    };
 
     Ok(Mt940Balance {
         dc_mark,
         date: date.to_string(),
         currency: currency.to_string(),
-        amount: amount.trim().to_string(),
+        amount: normalize_mt940_amount(amount)?,
     })
 }
 
 impl Mt940Message {
-    pub(crate) fn from_string_lines(lines: &[String]) -> Result<Self, ParseError> {
+    /// То же, что [`Mt940Message::from_numbered_lines`], строки нумеруются с единицы
+    #[cfg(test)]
+    pub(crate) fn from_string_lines(
+        lines: &[String],
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Self, ParseError> {
+        let line_numbers: Vec<usize> = (1..=lines.len()).collect();
+        Self::from_numbered_lines(lines, &line_numbers, warnings)
+    }
+
+    /// Собирает сообщение из строк блока 4
+    ///
+    /// `line_numbers` - номера `lines` во входных данных: ошибки разбора
+    /// отдельной строки оборачиваются в [`ParseError::AtLine`].
+    pub(crate) fn from_numbered_lines(
+        lines: &[String],
+        line_numbers: &[usize],
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Self, ParseError> {
         let mut tx_ref: Option<String> = None; // :20:
+        let mut related_ref: Option<String> = None; // :21:
         let mut account_id: Option<String> = None; // :25:
         let mut statement_number: Option<String> = None; // :28C:
 
-        let mut opening_balance: Option<Mt940Balance> = None; // :60F: / :60M:
+        let mut opening_balance: Option<Mt940Balance> = None; // :60F:
+        let mut intermediate_opening_balance: Option<Mt940Balance> = None; // :60M:
         let mut closing_balance: Option<Mt940Balance> = None; // :62F:
+        let mut intermediate_closing_balance: Option<Mt940Balance> = None; // :62M:
         let mut closing_available_balance: Option<Mt940Balance> = None; // :64:
 
         let mut entries: Vec<Mt940Entry> = Vec::new();
         let mut current_entry: Option<Mt940Entry> = None;
 
-        for raw_line in lines {
+        // :86: до первой :61: и её строки-продолжения
+        let mut statement_info: Vec<String> = Vec::new();
+        let mut in_statement_info = false;
+
+        for (idx, raw_line) in lines.iter().enumerate() {
             let line = raw_line.trim_end_matches('\r');
             let line_trimmed = line.trim_start();
+            let at_line = |err: ParseError| {
+                let number = line_numbers.get(idx).copied().unwrap_or(idx + 1);
+                let numbered = line_numbers
+                    .iter()
+                    .copied()
+                    .zip(lines.iter().map(String::as_str));
+                err.at_line(number, excerpt_around(numbered, number..=number))
+            };
 
             if line_trimmed.starts_with(':') {
-                let (tag, value) = split_tag_line(line_trimmed)?;
+                let (tag, value) = split_tag_line(line_trimmed).map_err(at_line)?;
+                in_statement_info = false;
 
                 match tag {
                     "20" => {
                         tx_ref = Some(value.to_string());
                     }
+                    "21" => {
+                        related_ref = Some(value.to_string());
+                    }
                     "25" => {
                         account_id = Some(value.to_string());
                     }
@@ -99,20 +159,29 @@ impl Mt940Message {
                         statement_number = Some(value.to_string());
                     }
                     "60F" | "60M" => {
-                        let bal = parse_balance(value)?;
-                        // первый 60* считаем opening_balance
-                        if opening_balance.is_none() {
-                            opening_balance = Some(bal);
+                        let bal = parse_balance(value).map_err(at_line)?;
+                        let slot = if tag == "60F" {
+                            &mut opening_balance
                         } else {
-                            eprintln!("multiple :60: opening balances, keeping the first one");
+                            &mut intermediate_opening_balance
+                        };
+                        // из повторов одного тега берём первый
+                        if slot.is_none() {
+                            *slot = Some(bal);
+                        } else {
+                            warnings.push(ParseWarning::MultipleOpeningBalances);
                         }
                     }
-                    "62F" | "62M" => {
-                        let bal = parse_balance(value)?;
+                    "62F" => {
+                        let bal = parse_balance(value).map_err(at_line)?;
                         closing_balance = Some(bal);
                     }
+                    "62M" => {
+                        let bal = parse_balance(value).map_err(at_line)?;
+                        intermediate_closing_balance = Some(bal);
+                    }
                     "64" => {
-                        let bal = parse_balance(value)?;
+                        let bal = parse_balance(value).map_err(at_line)?;
                         closing_available_balance = Some(bal);
                     }
                     "61" => {
@@ -120,22 +189,32 @@ impl Mt940Message {
                         if let Some(entry) = current_entry.take() {
                             entries.push(entry);
                         }
-                        current_entry =
-                            Some(Mt940Entry::from_61_line(value, line_trimmed.to_string())?);
+                        current_entry = Some(
+                            Mt940Entry::from_61_line(value, line_trimmed.to_string())
+                                .map_err(at_line)?,
+                        );
                     }
                     "86" => {
                         if let Some(entry) = current_entry.as_mut() {
                             entry.push_info_line(value);
+                        } else {
+                            statement_info.push(value.to_string());
+                            in_statement_info = true;
                         }
                     }
                     other => {
-                        eprintln!("skipped unknown tag {other}: {value}");
+                        warnings.push(ParseWarning::UnknownTag {
+                            tag: other.to_string(),
+                            value: value.to_string(),
+                        });
                     }
                 }
             } else {
                 // строка без ':', продолжение описания
                 if let Some(entry) = current_entry.as_mut() {
                     entry.push_info_line(line_trimmed);
+                } else if in_statement_info {
+                    statement_info.push(line_trimmed.to_string());
                 }
             }
         }
@@ -148,99 +227,168 @@ impl Mt940Message {
         // проверяем обязательные поля
         let account_id = account_id
             .ok_or_else(|| ParseError::BadInput("MT940: missing :25: account id".into()))?;
-        let opening_balance = opening_balance.ok_or_else(|| {
-            ParseError::BadInput("MT940: missing opening balance :60F:/:60M:".into())
-        })?;
+        // :60F: открывает выписку, :60M: - лишь очередную её страницу
+        let opening_balance = opening_balance
+            .or_else(|| intermediate_opening_balance.clone())
+            .ok_or_else(|| {
+                ParseError::BadInput("MT940: missing opening balance :60F:/:60M:".into())
+            })?;
 
         Ok(Mt940Message {
             transaction_reference: tx_ref,
+            related_reference: related_ref,
             account_id,
             statement_number,
             opening_balance,
+            intermediate_opening_balance,
             entries,
             closing_balance,
+            intermediate_closing_balance,
             closing_available_balance,
+            statement_info,
         })
     }
 }
 
+impl Mt940Message {
+    /// Итоговый закрывающий баланс: `:62F:`, а при его отсутствии - последний `:62M:`
+    pub fn effective_closing_balance(&self) -> Option<&Mt940Balance> {
+        self.closing_balance
+            .as_ref()
+            .or(self.intermediate_closing_balance.as_ref())
+    }
+
+    /// Проверяет, встречаются ли в суммах выписки одновременно `.` и `,` как
+    /// десятичный разделитель
+    pub(crate) fn has_mixed_decimal_separators(&self) -> bool {
+        let balances = [
+            Some(&self.opening_balance),
+            self.intermediate_opening_balance.as_ref(),
+            self.closing_balance.as_ref(),
+            self.intermediate_closing_balance.as_ref(),
+            self.closing_available_balance.as_ref(),
+        ];
+
+        let amounts = balances
+            .into_iter()
+            .flatten()
+            .map(|b| b.amount.as_str())
+            .chain(self.entries.iter().map(|e| e.amount.as_str()));
+
+        let mut seen: Option<char> = None;
+        for sep in amounts.filter_map(decimal_separator) {
+            match seen {
+                Some(prev) if prev != sep => return true,
+                _ => seen = Some(sep),
+            }
+        }
+
+        false
+    }
+}
+
 impl TryFrom<Mt940Message> for Statement {
     type Error = ParseError;
 
     fn try_from(message: Mt940Message) -> Result<Self, Self::Error> {
-        let Mt940Message {
-            transaction_reference: _,
-            account_id,
-            statement_number: _,
-            opening_balance: opening_mt,
-            entries,
-            closing_balance: closing_mt,
-            closing_available_balance: _,
-        } = message;
-
-        // в MT940 обычно нет имени счёта
-        let account_name: Option<String> = None;
+        statement_from_message(message, false).map(|(statement, _)| statement)
+    }
+}
 
-        let currency: Currency = parse_currency(&opening_mt.currency);
+fn statement_from_message(
+    message: Mt940Message,
+    lenient: bool,
+) -> Result<(Statement, RowErrors), ParseError> {
+    let Mt940Message {
+        transaction_reference: _,
+        related_reference: _,
+        account_id,
+        statement_number: _,
+        opening_balance: opening_mt,
+        intermediate_opening_balance: _,
+        entries,
+        closing_balance,
+        intermediate_closing_balance,
+        closing_available_balance: _,
+        statement_info,
+    } = message;
+
+    // финальный :62F: важнее промежуточного :62M:
+    let closing_mt = closing_balance.or(intermediate_closing_balance);
+
+    // в MT940 обычно нет имени счёта, но некоторые банки дописывают владельца
+    // в :25: после номера или указывают его в :86: выписки или первой проводки
+    let (account_id, owner) = split_account_owner(&account_id);
+    let account_name: Option<String> = owner
+        .map(str::to_string)
+        .or_else(|| find_wrapped_owner_name_in_lines(&statement_info))
+        .or_else(|| {
+            entries
+                .first()
+                .and_then(|entry| find_owner_name_in_lines(&entry.info.lines))
+        });
+    let account_id = account_id.to_string();
+
+    let currency: Currency = parse_currency(&opening_mt.currency);
+
+    // открывающий баланс: строка суммы + знак C/D
+    let opening_raw = parse_mt940_amount(&opening_mt.amount)? as i128;
+    let opening_balance: Option<Balance> = Some(match opening_mt.dc_mark {
+        'C' => opening_raw,
+        'D' => -opening_raw,
+        other => {
+            return Err(ParseError::InvalidAmount(format!(
+                "unknown opening balance direction: {other}"
+            )));
+        }
+    });
 
-        // открывающий баланс: строка суммы + знак C/D
-        let opening_raw = parse_amount(&opening_mt.amount)? as i128;
-        let opening_balance: Option<Balance> = Some(match opening_mt.dc_mark {
-            'C' => opening_raw,
-            'D' => -opening_raw,
+    let closing_balance: Option<Balance> = if let Some(cb) = &closing_mt {
+        let raw = parse_mt940_amount(&cb.amount)? as i128;
+        let signed = match cb.dc_mark {
+            'C' => raw,
+            'D' => -raw,
             other => {
                 return Err(ParseError::InvalidAmount(format!(
-                    "unknown opening balance direction: {other}"
+                    "unknown closing balance direction: {other}"
                 )));
             }
-        });
-
-        let closing_balance: Option<Balance> = if let Some(cb) = &closing_mt {
-            let raw = parse_amount(&cb.amount)? as i128;
-            let signed = match cb.dc_mark {
-                'C' => raw,
-                'D' => -raw,
-                other => {
-                    return Err(ParseError::InvalidAmount(format!(
-                        "unknown closing balance direction: {other}"
-                    )));
-                }
-            };
-            Some(signed)
-        } else {
-            None
         };
+        Some(signed)
+    } else {
+        None
+    };
 
-        let period_from: NaiveDate = parse_mt940_yy_mm_dd(&opening_mt.date)?;
-
-        // конвертируем все Mt940Entry -> Transaction
-        let mut transactions: Vec<Transaction> = Vec::with_capacity(entries.len());
-        for entry in &entries {
-            let tx = Transaction::try_from(entry)?;
-            transactions.push(tx);
-        }
+    let period_from: Date = parse_mt940_yy_mm_dd(&opening_mt.date)?;
 
-        let period_until: NaiveDate = if let Some(cb) = &closing_mt {
-            parse_mt940_yy_mm_dd(&cb.date)?
-        } else {
-            transactions
-                .iter()
-                .map(|tx| tx.booking_date)
-                .max()
-                .unwrap_or(period_from)
-        };
+    // конвертируем все Mt940Entry -> Transaction
+    let (transactions, errors) =
+        collect_transactions(entries.iter().map(Transaction::try_from), lenient)?;
 
-        Ok(Statement::new(
-            account_id,
-            account_name,
-            currency,
-            opening_balance,
-            closing_balance,
-            transactions,
-            period_from,
-            period_until,
-        ))
-    }
+    let period_until: Date = if let Some(cb) = &closing_mt {
+        parse_mt940_yy_mm_dd(&cb.date)?
+    } else {
+        transactions
+            .iter()
+            .map(|tx| tx.booking_date)
+            .max()
+            .unwrap_or(period_from)
+    };
+
+    let mut statement = Statement::new(
+        account_id,
+        account_name,
+        currency,
+        opening_balance,
+        closing_balance,
+        transactions,
+        period_from,
+        period_until,
+    );
+
+    statement.source_format = Some(SourceFormat::Mt940);
+
+    Ok((statement, errors))
 }
 
 #[derive(Debug, Clone)]
@@ -254,7 +402,7 @@ pub struct Mt940Balance {
     /// Код валюты, как есть: "EUR", "USD", "CHF", ...
     pub currency: String,
 
-    /// Сумма, как в файле: "2732398848,02", "1000, 00"
+    /// Сумма без пробелов вокруг запятой: "2732398848,02", "1000, 00" -> "1000,00"
     pub amount: String,
 }
 
@@ -279,6 +427,11 @@ pub struct Mt940Entry {
     /// 'C' или 'D' - признак кредит/дебет в :61:
     pub dc_mark: char,
 
+    /// Признак сторно: в :61: стоял двухбуквенный `RD`/`RC`
+    ///
+    /// Направление при этом уже лежит в `dc_mark`: `RC` дает 'D', `RD` - 'C'.
+    pub reversal: bool,
+
     /// Дополнительный символ-флаг после C/D (напр. 'R' в "DR"), если есть
     pub funds_code: Option<char>,
 
@@ -370,7 +523,7 @@ impl TryFrom<&Mt940Entry> for Transaction {
             }
         };
 
-        let amount = parse_amount(&entry.amount)?;
+        let amount = parse_mt940_amount(&entry.amount)?;
 
         let value_date = parse_mt940_yy_mm_dd(&entry.value_date)?;
         let booking_date = derive_booking_date(value_date, entry.entry_date.as_deref())?;
@@ -378,15 +531,21 @@ impl TryFrom<&Mt940Entry> for Transaction {
         let description = build_description(entry);
         let (counterparty, counterparty_name) = extract_counterparty_from_mt940(entry);
 
-        Ok(Transaction {
+        let mut tx = Transaction::new(
             booking_date,
-            value_date: Some(value_date),
+            Some(value_date),
             amount,
             direction,
             description,
             counterparty,
             counterparty_name,
-        })
+        );
+        tx.bank_reference = entry.bank_reference.clone();
+        // "DR"/"CR" с funds code R часть банков тоже пишет как сторно;
+        // D/C в них - уже фактическое направление проводки, как и после RC/RD
+        tx.is_reversal = entry.reversal || entry.funds_code == Some('R');
+
+        Ok(tx)
     }
 }
 
@@ -417,7 +576,7 @@ impl Mt940Entry {
             idx += 4;
         }
 
-        let (dc_mark, funds_code, amount, rest_after_amount) =
+        let (dc_mark, reversal, funds_code, amount, rest_after_amount) =
             parse_dc_and_amount(&value[idx..], value)?;
 
         let mut rest = rest_after_amount;
@@ -427,16 +586,20 @@ impl Mt940Entry {
         let mut bank_reference = None;
         let mut extra_details = None;
 
-        // transaction_type: 4 буквы подряд
-        if rest.len() >= 4 && rest[..4].chars().all(|c| c.is_ascii_alphabetic()) {
-            transaction_type = Some(rest[..4].to_string());
+        // transaction_type: 4 символа, см. is_transaction_type
+        if let Some(code) = rest.get(..4).filter(|code| is_transaction_type(code)) {
+            transaction_type = Some(code.to_string());
             rest = rest[4..].trim_start();
         }
 
         if let Some(pos) = rest.find("//") {
             // есть customer_ref и bank_ref
             let (cust, after_cust) = rest.split_at(pos);
-            customer_reference = Some(cust.trim().to_string());
+            // customer_ref может быть пустым: так пишет сериализатор, если знает только bank_ref
+            let cust = cust.trim();
+            if !cust.is_empty() {
+                customer_reference = Some(cust.to_string());
+            }
 
             let after = &after_cust[2..]; // без //
             if let Some(space_pos) = after.find(' ') {
@@ -462,6 +625,7 @@ impl Mt940Entry {
             value_date: value_date.to_string(),
             entry_date,
             dc_mark,
+            reversal,
             funds_code,
             amount,
             transaction_type,
@@ -492,135 +656,392 @@ impl Mt940Entry {
 pub struct Mt940Data {
     /// Пока один Statement
     pub message: Mt940Message,
+
+    /// Заголовочные блоки `{1:}`/`{2:}`, если они были во входных данных
+    pub header: Mt940Header,
+
+    /// Некритичные замечания, собранные при парсинге
+    pub warnings: Vec<ParseWarning>,
+
+    /// Следующие сообщения входа, если разбирали с [`MultipleStatementsPolicy::KeepAll`];
+    /// в остальных случаях пусто
+    pub rest: Vec<Mt940Data>,
 }
 
-impl Mt940Data {
-    /// Парсит при помощи переданного reader данные  в [`Mt940Data`]
-    ///
-    /// При ошибке возвращает [`ParseError`]
-    pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
-        use std::io::BufRead;
+/// Данные из заголовочных блоков SWIFT-сообщения (`{1:}` basic header и `{2:}` application header)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mt940Header {
+    /// BIC отправителя сообщения
+    pub sender_bic: Option<String>,
+
+    /// BIC получателя сообщения
+    pub receiver_bic: Option<String>,
+}
 
-        let buf_reader = BufReader::new(reader);
-        let mut messages: Vec<Mt940Message> = Vec::new();
-        let mut message_lines: Vec<String> = Vec::new();
+/// Сырое сообщение: текст заголовочных блоков перед `{4:` и строки самого блока 4
+#[derive(Default)]
+struct RawMessage {
+    headers: String,
+    lines: Vec<String>,
+    /// номера строк `lines` во входных данных, с единицы
+    line_numbers: Vec<usize>,
+}
+
+/// Настройки парсинга MT940
+///
+/// По умолчанию все дополнительные проверки выключены.
+#[derive(Debug, Clone, Default)]
+pub struct Mt940ParseOptions {
+    /// Проверять, что во всех суммах выписки используется один и тот же
+    /// десятичный разделитель (`,` или `.`). При смешении добавляется предупреждение.
+    pub check_decimal_consistency: bool,
+    /// Что делать, если во входных данных больше одного сообщения
+    pub on_multiple: MultipleStatementsPolicy,
+    /// Если в `:60:` нет валюты, угадать её по стране IBAN счёта
+    ///
+    /// Это догадка, поэтому добавляется [`ParseWarning::CurrencyInferred`].
+    /// Без опции валюта остаётся пустой ([`Currency::Other`]).
+    pub infer_currency_from_iban: bool,
+}
 
-        #[derive(Copy, Clone, Debug)]
-        enum BlockKind {
-            Curly, // {4: ... -}
-            Paren, // (4: ... -)
+/// Отделяет содержимое строки от закрывающего маркера (`-}` / `-)`) в её конце
+///
+/// После маркера допускаются только завершающие блоки вроде `{5:...}`.
+/// Возвращает `None`, если маркера в конце строки нет.
+fn split_trailing_close_marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let line = line.trim_end();
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find(marker) {
+        let pos = search_from + offset;
+        let rest = &line[pos + marker.len()..];
+        if rest.is_empty() || rest.starts_with('{') || rest.starts_with('(') {
+            return Some(&line[..pos]);
         }
+        search_from = pos + marker.len();
+    }
+    None
+}
+
+/// Разбивает входной поток на текстовые блоки `{4: ... -}` / `(4: ... -)`
+///
+/// Возвращает строки каждого блока без открывающего и закрывающего маркеров,
+/// вместе с текстом стоящих перед ним заголовочных блоков.
+/// Если маркеров блока 4 во входе нет совсем, сообщениями считаются группы
+/// строк с тегами, разделённые пустой строкой (или строкой `-`).
+/// Используется и для MT940, и для близкого к нему MT942.
+fn split_text_blocks<R: Read>(reader: R) -> Result<Vec<RawMessage>, ParseError> {
+    let buf_reader = BufReader::new(reader);
+    let mut blocks: Vec<RawMessage> = Vec::new();
+    let mut message_lines: Vec<String> = Vec::new();
+    let mut line_numbers: Vec<usize> = Vec::new();
+    let mut headers = String::new();
+
+    #[derive(Copy, Clone, Debug)]
+    enum BlockKind {
+        Curly, // {4: ... -}
+        Paren, // (4: ... -)
+    }
 
-        let mut block_kind: Option<BlockKind> = None;
-        let mut in_text_block = false;
+    let mut block_kind: Option<BlockKind> = None;
+    let mut in_text_block = false;
 
-        for line_result in buf_reader.lines() {
-            let line = line_result?;
-            let trimmed = line.trim();
+    // строки до первого маркера блока: нужны, если маркеров не окажется вовсе
+    let mut bare_lines: Vec<(usize, String)> = Vec::new();
 
-            if trimmed.is_empty() {
-                continue;
-            }
+    for (idx, line_result) in normalized_lines(buf_reader).enumerate() {
+        let line = line_result?;
+        let line_number = idx + 1;
+        let trimmed = line.trim();
 
-            // ещё не внутри блока {4:/ (4:
-            if !in_text_block {
-                match block_kind {
-                    Some(BlockKind::Curly) => {
-                        if let Some(pos) = line.find("{4:") {
-                            in_text_block = true;
-                            let after = &line[pos + 3..];
-                            if !after.trim().is_empty() {
-                                message_lines.push(after.to_string());
-                            }
-                        }
-                    }
-                    Some(BlockKind::Paren) => {
-                        if let Some(pos) = line.find("(4:") {
-                            in_text_block = true;
-                            let after = &line[pos + 3..];
-                            if !after.trim().is_empty() {
-                                message_lines.push(after.to_string());
-                            }
+        if block_kind.is_none() {
+            bare_lines.push((line_number, line.clone()));
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // ещё не внутри блока {4:/ (4:
+        if !in_text_block {
+            match block_kind {
+                Some(BlockKind::Curly) => {
+                    if let Some(pos) = line.find("{4:") {
+                        in_text_block = true;
+                        headers.push_str(line[..pos].trim());
+                        let after = &line[pos + 3..];
+                        if !after.trim().is_empty() {
+                            message_lines.push(after.to_string());
+                            line_numbers.push(line_number);
                         }
+                    } else {
+                        headers.push_str(trimmed);
                     }
-                    None => {
-                        // первый раз определяем тип блока: что встретится раньше
-                        let pos_curly = line.find("{4:");
-                        let pos_paren = line.find("(4:");
-
-                        let (kind, pos) = match (pos_curly, pos_paren) {
-                            (Some(pc), Some(pp)) => {
-                                if pc <= pp {
-                                    (BlockKind::Curly, pc)
-                                } else {
-                                    (BlockKind::Paren, pp)
-                                }
-                            }
-                            (Some(pc), None) => (BlockKind::Curly, pc),
-                            (None, Some(pp)) => (BlockKind::Paren, pp),
-                            (None, None) => {
-                                // в этой строке начала блока нет
-                                continue;
-                            }
-                        };
-
-                        block_kind = Some(kind);
+                }
+                Some(BlockKind::Paren) => {
+                    if let Some(pos) = line.find("(4:") {
                         in_text_block = true;
-
+                        headers.push_str(line[..pos].trim());
                         let after = &line[pos + 3..];
                         if !after.trim().is_empty() {
                             message_lines.push(after.to_string());
+                            line_numbers.push(line_number);
                         }
+                    } else {
+                        headers.push_str(trimmed);
                     }
                 }
+                None => {
+                    // первый раз определяем тип блока: что встретится раньше
+                    let pos_curly = line.find("{4:");
+                    let pos_paren = line.find("(4:");
+
+                    let (kind, pos) = match (pos_curly, pos_paren) {
+                        (Some(pc), Some(pp)) => {
+                            if pc <= pp {
+                                (BlockKind::Curly, pc)
+                            } else {
+                                (BlockKind::Paren, pp)
+                            }
+                        }
+                        (Some(pc), None) => (BlockKind::Curly, pc),
+                        (None, Some(pp)) => (BlockKind::Paren, pp),
+                        (None, None) => {
+                            // в этой строке начала блока нет, но могут быть заголовки
+                            headers.push_str(trimmed);
+                            continue;
+                        }
+                    };
 
-                continue;
+                    block_kind = Some(kind);
+                    bare_lines.clear();
+                    in_text_block = true;
+                    headers.push_str(line[..pos].trim());
+
+                    let after = &line[pos + 3..];
+                    if !after.trim().is_empty() {
+                        message_lines.push(after.to_string());
+                        line_numbers.push(line_number);
+                    }
+                }
             }
 
-            // внутри блока
+            continue;
+        }
 
-            let kind = block_kind.expect("in_text_block set but block_kind is None");
+        // внутри блока
 
-            // закрывающие маркеры зависят от типа блока
-            let close_markers: &[&str] = match kind {
-                BlockKind::Curly => &["-}", "}"],
-                BlockKind::Paren => &["-)", ")"],
-            };
+        let kind = block_kind.expect("in_text_block set but block_kind is None");
 
-            if close_markers.iter().any(|p| trimmed.starts_with(p)) {
-                // закончили один message
-                let msg = Mt940Message::from_string_lines(&message_lines)?;
-                messages.push(msg);
+        // закрывающие маркеры зависят от типа блока
+        let close_markers: &[&str] = match kind {
+            BlockKind::Curly => &["-}", "}"],
+            BlockKind::Paren => &["-)", ")"],
+        };
+
+        // закрывающий маркер может стоять и в конце строки с содержимым: `:62F:...-}`
+        let trailing_close = if close_markers.iter().any(|p| trimmed.starts_with(p)) {
+            Some("")
+        } else {
+            split_trailing_close_marker(&line, close_markers[0])
+        };
 
-                message_lines.clear();
-                in_text_block = false;
-                continue;
+        if let Some(content) = trailing_close {
+            if !content.trim().is_empty() {
+                message_lines.push(content.to_string());
+                line_numbers.push(line_number);
             }
 
-            // обычная строка тела message
-            message_lines.push(line);
+            // закончили один message
+            blocks.push(RawMessage {
+                headers: std::mem::take(&mut headers),
+                lines: std::mem::take(&mut message_lines),
+                line_numbers: std::mem::take(&mut line_numbers),
+            });
+            in_text_block = false;
+            continue;
         }
 
-        // файл закончился, но блок не закрыт
-        if in_text_block && !message_lines.is_empty() {
-            let msg = Mt940Message::from_string_lines(&message_lines)?;
-            messages.push(msg);
+        // обычная строка тела message
+        message_lines.push(line);
+        line_numbers.push(line_number);
+    }
+
+    // файл закончился, но блок не закрыт
+    if in_text_block && !message_lines.is_empty() {
+        blocks.push(RawMessage {
+            headers,
+            lines: message_lines,
+            line_numbers,
+        });
+    }
+
+    if block_kind.is_none() {
+        return Ok(split_bare_messages(bare_lines));
+    }
+
+    Ok(blocks)
+}
+
+/// Делит поток тегов без конверта `{4:`/`-}` на сообщения по пустым строкам
+/// и строкам `-`. Группы, которые не начинаются с тега, пропускаются.
+fn split_bare_messages(lines: Vec<(usize, String)>) -> Vec<RawMessage> {
+    let mut blocks = Vec::new();
+    let mut current = RawMessage::default();
+
+    let mut flush = |current: &mut RawMessage| {
+        let message = std::mem::take(current);
+        let starts_with_tag = message
+            .lines
+            .first()
+            .is_some_and(|line| line.trim_start().starts_with(':'));
+        if starts_with_tag {
+            blocks.push(message);
         }
+    };
 
-        if messages.is_empty() {
-            return Err(ParseError::BadInput("0 mt940 messages detected".into()));
+    for (number, line) in lines {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed == "-" {
+            flush(&mut current);
+            continue;
         }
+        current.lines.push(line);
+        current.line_numbers.push(number);
+    }
+    flush(&mut current);
 
-        let mut messages_iter = messages.into_iter();
-        let final_msg = messages_iter
+    blocks
+}
+
+impl Mt940Data {
+    /// Мягкое преобразование в [`Statement`]: проводки `:61:` с ошибками пропускаются
+    ///
+    /// Возвращает выписку и список `(индекс проводки, ошибка)` для пропущенных.
+    /// Ошибки балансов по-прежнему фатальны
+    pub fn try_into_statement_lenient(
+        self,
+    ) -> Result<(Statement, Vec<(usize, ParseError)>), ParseError> {
+        statement_from_message(self.message, true)
+    }
+
+    /// Парсит при помощи переданного reader данные  в [`Mt940Data`]
+    ///
+    /// При ошибке возвращает [`ParseError`]
+    pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
+        Self::parse_with_options(reader, &Mt940ParseOptions::default())
+    }
+
+    /// То же, что [`Mt940Data::parse`], но с дополнительными настройками
+    pub fn parse_with_options<R: Read>(
+        reader: R,
+        options: &Mt940ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let mut messages = Self::parse_all_with_options(reader, options)?.into_iter();
+
+        let mut data = messages
             .next()
             .ok_or_else(|| ParseError::BadInput("0 mt940 messages detected".into()))?;
+        let rest: Vec<Self> = messages.collect();
 
-        if messages_iter.next().is_some() {
-            eprintln!("more than one statement provided to mt940 parser. only reading first");
+        if !rest.is_empty() {
+            match options.on_multiple {
+                MultipleStatementsPolicy::KeepFirst => {
+                    data.warnings
+                        .push(ParseWarning::MultipleStatements { format: "mt940" });
+                }
+                MultipleStatementsPolicy::ErrorOut => {
+                    return Err(ParseError::MultipleStatements {
+                        format: "mt940",
+                        count: rest.len() + 1,
+                    });
+                }
+                MultipleStatementsPolicy::KeepAll => data.rest = rest,
+            }
         }
 
-        Ok(Mt940Data { message: final_msg })
+        Ok(data)
+    }
+
+    /// Все сообщения по порядку: это и [`Mt940Data::rest`]
+    pub fn into_all(mut self) -> Vec<Self> {
+        let rest = std::mem::take(&mut self.rest);
+        let mut all = Vec::with_capacity(rest.len() + 1);
+        all.push(self);
+        all.extend(rest);
+        all
+    }
+
+    /// Парсит все сообщения входных данных (например, выписки за несколько дней)
+    ///
+    /// В отличие от [`Mt940Data::parse`], ни одно сообщение не отбрасывается.
+    /// Вход без сообщений даёт пустой список.
+    pub fn parse_all<R: Read>(reader: R) -> Result<Vec<Self>, ParseError> {
+        Self::parse_all_with_options(reader, &Mt940ParseOptions::default())
+    }
+
+    /// То же, что [`Mt940Data::parse_all`], но с дополнительными настройками
+    ///
+    /// [`Mt940ParseOptions::on_multiple`] здесь не учитывается.
+    pub fn parse_all_with_options<R: Read>(
+        reader: R,
+        options: &Mt940ParseOptions,
+    ) -> Result<Vec<Self>, ParseError> {
+        let mut messages = Vec::new();
+        for block in split_text_blocks(reader)? {
+            let mut warnings = Vec::new();
+            let mut message = Mt940Message::from_numbered_lines(
+                &block.lines,
+                &block.line_numbers,
+                &mut warnings,
+            )?;
+
+            if options.infer_currency_from_iban
+                && message.opening_balance.currency.trim().is_empty()
+            {
+                let (account, _) = split_account_owner(&message.account_id);
+                if let Some(currency) = currency_for_iban(account) {
+                    message.opening_balance.currency = currency.code().to_string();
+                    warnings.push(ParseWarning::CurrencyInferred {
+                        format: "mt940",
+                        account: account.to_string(),
+                        currency,
+                    });
+                }
+            }
+
+            if options.check_decimal_consistency && message.has_mixed_decimal_separators() {
+                warnings.push(ParseWarning::MixedDecimalSeparators {
+                    account: message.account_id.clone(),
+                });
+            }
+
+            messages.push(Mt940Data {
+                message,
+                header: parse_swift_headers(&block.headers),
+                warnings,
+                rest: Vec::new(),
+            });
+        }
+
+        Ok(messages)
+    }
+}
+
+impl TryFrom<&str> for Mt940Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Mt940Data::parse`]
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input.as_bytes())
+    }
+}
+
+impl TryFrom<String> for Mt940Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Mt940Data::parse`]
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::try_from(input.as_str())
     }
 }
 
@@ -661,6 +1082,15 @@ mod tests {
         assert_eq!(bal.amount, "1000,00");
     }
 
+    #[test]
+    fn parse_balance_normalizes_space_after_decimal_comma() {
+        let bal = parse_balance("C230101EUR1000, 00").unwrap();
+        assert_eq!(bal.amount, "1000,00");
+
+        // 1000.00 -> 100000 "копеек"
+        assert_eq!(parse_mt940_amount(&bal.amount).unwrap(), 100_000);
+    }
+
     #[test]
     fn parse_balance_errors_on_too_short_value() {
         let err = parse_balance("C2301").unwrap_err();
@@ -672,6 +1102,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_balance_without_currency_keeps_amount() {
+        let bal = parse_balance("C230101100,00").unwrap();
+        assert_eq!(bal.currency, "");
+        assert_eq!(bal.amount, "100,00");
+    }
+
     // Mt940Entry::from_61_line
 
     #[test]
@@ -711,6 +1148,131 @@ mod tests {
         assert_eq!(entry.extra_details.as_deref(), Some("some extra text"));
     }
 
+    #[test]
+    fn from_61_line_parses_numeric_swift_type_codes() {
+        let value = "2301010102D250,00N020REF123//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+        assert_eq!(entry.amount, "250,00");
+        assert_eq!(entry.transaction_type.as_deref(), Some("N020"));
+        assert_eq!(entry.customer_reference.as_deref(), Some("REF123"));
+        assert_eq!(entry.bank_reference.as_deref(), Some("BANKREF"));
+
+        // ссылка приклеена к типу без `//`
+        let value = "230101C1000,00S103PAYMENT42";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+        assert_eq!(entry.transaction_type.as_deref(), Some("S103"));
+        assert_eq!(entry.customer_reference.as_deref(), Some("PAYMENT42"));
+        assert!(entry.bank_reference.is_none());
+    }
+
+    #[test]
+    fn mt940_entry_with_funds_code_r_is_reversal() {
+        let value = "2301010102DR250,00NTRFREF123//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        assert_eq!(entry.funds_code, Some('R'));
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert!(tx.is_reversal);
+        assert_eq!(tx.direction, Direction::Debit);
+        assert_eq!(tx.amount, 25_000);
+
+        let value = "2301010102D250,00NTRFREF123//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+        assert!(!Transaction::try_from(&entry).unwrap().is_reversal);
+    }
+
+    #[test]
+    fn mt940_entry_with_swift_reversal_mark_rc() {
+        let value = "2301010102RC99,00NTRFNONREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        // RC - сторно кредита, то есть дебетовая запись
+        assert_eq!(entry.dc_mark, 'D');
+        assert!(entry.reversal);
+        assert_eq!(entry.funds_code, None);
+        assert_eq!(entry.amount, "99,00");
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert!(tx.is_reversal);
+        assert_eq!(tx.direction, Direction::Debit);
+    }
+
+    #[test]
+    fn mt940_entry_with_swift_reversal_mark_rd() {
+        let value = "2301010102RD12,50NTRFNONREF//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        // RD - сторно дебета, то есть кредитовая запись
+        assert_eq!(entry.dc_mark, 'C');
+        assert!(entry.reversal);
+        assert_eq!(entry.amount, "12,50");
+        assert_eq!(entry.transaction_type.as_deref(), Some("NTRF"));
+        assert_eq!(entry.bank_reference.as_deref(), Some("BANKREF"));
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert!(tx.is_reversal);
+        assert_eq!(tx.direction, Direction::Credit);
+        assert_eq!(tx.amount, 1_250);
+    }
+
+    #[test]
+    fn bank_reference_is_carried_into_transaction() {
+        let value = "2301010102C100,00NTRFREF123//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert_eq!(tx.bank_reference.as_deref(), Some("BANKREF"));
+    }
+
+    #[test]
+    fn from_61_line_treats_empty_customer_reference_as_missing() {
+        let value = "2301010102C100,00//BANKREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        assert!(entry.customer_reference.is_none());
+        assert_eq!(entry.bank_reference.as_deref(), Some("BANKREF"));
+    }
+
+    #[test]
+    fn mt940_entry_with_reversal_mark_and_funds_code() {
+        let value = "2301010102RCE99,00NTRFNONREF";
+        let entry = Mt940Entry::from_61_line(value, format!(":61:{value}")).unwrap();
+
+        assert_eq!(entry.dc_mark, 'D');
+        assert!(entry.reversal);
+        assert_eq!(entry.funds_code, Some('E'));
+
+        let tx = Transaction::try_from(&entry).unwrap();
+        assert!(tx.is_reversal);
+        assert_eq!(tx.direction, Direction::Debit);
+        assert_eq!(tx.amount, 9_900);
+    }
+
+    #[test]
+    fn swift_reversal_marks_keep_statement_balanced_until_inverted() {
+        let lines = vec![
+            ":25:DE11112222333344445555".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":61:2301020102RC10,00NTRFNONREF".to_string(),
+            ":61:2301030103RD2,50NTRFNONREF".to_string(),
+            ":62F:C230103EUR92,50".to_string(),
+        ];
+
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        let mut stmt = Statement::try_from(msg).unwrap();
+
+        assert_eq!(stmt.transactions[0].direction, Direction::Debit);
+        assert_eq!(stmt.transactions[1].direction, Direction::Credit);
+        assert!(stmt.is_balanced());
+
+        // направление отменяемых операций: RC - кредит, RD - дебет
+        assert_eq!(stmt.invert_reversals(), 2);
+        assert_eq!(stmt.transactions[0].direction, Direction::Credit);
+        assert_eq!(stmt.transactions[1].direction, Direction::Debit);
+        assert!(!stmt.is_balanced());
+    }
+
     #[test]
     fn from_61_line_errors_when_no_amount() {
         // value_date=230101, dc_mark=C, дальше только буквы
@@ -738,6 +1300,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: Some("0102".to_string()),
             dc_mark: 'C',
+            reversal: false,
             funds_code: None,
             amount: "100,00".to_string(),
             transaction_type: Some("NTRF".to_string()),
@@ -773,6 +1336,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: None,
             dc_mark: 'C',
+            reversal: false,
             funds_code: None,
             amount: "10,00".to_string(),
             transaction_type: None,
@@ -800,6 +1364,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: None,
             dc_mark: 'C',
+            reversal: false,
             funds_code: None,
             amount: "10,00".to_string(),
             transaction_type: None,
@@ -822,6 +1387,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: None,
             dc_mark: 'C',
+            reversal: false,
             funds_code: None,
             amount: "10,00".to_string(),
             transaction_type: None,
@@ -848,6 +1414,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: Some("0102".to_string()),
             dc_mark: 'C',
+            reversal: false,
             funds_code: None,
             amount: "100,00".to_string(),
             transaction_type: Some("NTRF".to_string()),
@@ -878,6 +1445,18 @@ mod tests {
         assert!(!tx.description.is_empty());
     }
 
+    #[test]
+    fn mt940_61_amounts_use_comma_as_mandatory_decimal_separator() {
+        for (amount, expected) in [("123,", 12_300), ("0,05", 5), ("1234567,89", 123_456_789)] {
+            let value = format!("2301010102C{amount}NTRFREF//BANK");
+            let entry = Mt940Entry::from_61_line(&value, format!(":61:{value}")).unwrap();
+
+            let tx = Transaction::try_from(&entry).unwrap();
+
+            assert_eq!(tx.amount, expected, "amount {amount}");
+        }
+    }
+
     #[test]
     fn mt940_entry_to_transaction_debit() {
         let entry = Mt940Entry {
@@ -885,6 +1464,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: None,
             dc_mark: 'D',
+            reversal: false,
             funds_code: None,
             amount: "50,00".to_string(),
             transaction_type: None,
@@ -907,6 +1487,7 @@ mod tests {
             value_date: "230101".to_string(),
             entry_date: None,
             dc_mark: 'X',
+            reversal: false,
             funds_code: None,
             amount: "100,00".to_string(),
             transaction_type: None,
@@ -927,6 +1508,48 @@ mod tests {
 
     // Mt940Message::from_string_lines
 
+    #[test]
+    fn mt940_message_prefers_60f_over_60m() {
+        let lines = vec![
+            ":20:REF123".to_string(),
+            ":25:DE11112222333344445555".to_string(),
+            ":60M:C230102EUR150,00".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":62F:C230103EUR150,00".to_string(),
+        ];
+
+        let mut warnings = Vec::new();
+        let msg = Mt940Message::from_string_lines(&lines, &mut warnings).unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(msg.opening_balance.amount, "100,00");
+        assert_eq!(
+            msg.intermediate_opening_balance
+                .as_ref()
+                .map(|b| b.amount.as_str()),
+            Some("150,00")
+        );
+
+        let stmt = Statement::try_from(msg).unwrap();
+        assert_eq!(stmt.opening_balance, Some(10_000));
+    }
+
+    #[test]
+    fn mt940_message_with_only_60m_uses_it_as_opening() {
+        let lines = vec![
+            ":20:REF123".to_string(),
+            ":25:DE11112222333344445555".to_string(),
+            ":60M:C230102EUR150,00".to_string(),
+            ":60M:C230102EUR999,00".to_string(),
+        ];
+
+        let mut warnings = Vec::new();
+        let msg = Mt940Message::from_string_lines(&lines, &mut warnings).unwrap();
+
+        assert_eq!(msg.opening_balance.amount, "150,00");
+        assert_eq!(warnings, vec![ParseWarning::MultipleOpeningBalances]);
+    }
+
     #[test]
     fn mt940_message_from_string_lines_parses_basic_message() {
         let lines = vec![
@@ -939,7 +1562,7 @@ mod tests {
             ":62F:C230103EUR150,00".to_string(),
         ];
 
-        let msg = Mt940Message::from_string_lines(&lines).unwrap();
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
 
         assert_eq!(msg.transaction_reference.as_deref(), Some("REF123"));
         assert_eq!(msg.account_id, "DE11112222333344445555");
@@ -954,11 +1577,39 @@ mod tests {
         assert!(msg.closing_balance.is_some());
     }
 
+    #[test]
+    fn mt940_message_captures_related_reference_and_statement_info() {
+        let lines = vec![
+            ":20:REF123".to_string(),
+            ":21:RELATED-42".to_string(),
+            ":25:DE11112222333344445555".to_string(),
+            ":28C:1/1".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":86:Statement narrative".to_string(),
+            "second line".to_string(),
+            ":61:2301020102C50,00NTRFREF//BANK".to_string(),
+            ":86:Payment text".to_string(),
+            ":62F:C230103EUR150,00".to_string(),
+        ];
+
+        let mut warnings = Vec::new();
+        let msg = Mt940Message::from_string_lines(&lines, &mut warnings).unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(msg.related_reference.as_deref(), Some("RELATED-42"));
+        assert_eq!(
+            msg.statement_info,
+            vec!["Statement narrative", "second line"]
+        );
+        assert_eq!(msg.entries.len(), 1);
+        assert_eq!(msg.entries[0].info.lines, vec!["Payment text"]);
+    }
+
     #[test]
     fn mt940_message_from_string_lines_requires_account_and_opening_balance() {
         let lines_missing_25 = vec![":20:REF".to_string(), ":60F:C230101EUR100,00".to_string()];
 
-        let err = Mt940Message::from_string_lines(&lines_missing_25).unwrap_err();
+        let err = Mt940Message::from_string_lines(&lines_missing_25, &mut Vec::new()).unwrap_err();
         match err {
             ParseError::BadInput(msg) => {
                 assert!(msg.contains("missing :25"), "unexpected msg: {msg}");
@@ -968,7 +1619,7 @@ mod tests {
 
         let lines_missing_60 = vec![":20:REF".to_string(), ":25:ACC".to_string()];
 
-        let err = Mt940Message::from_string_lines(&lines_missing_60).unwrap_err();
+        let err = Mt940Message::from_string_lines(&lines_missing_60, &mut Vec::new()).unwrap_err();
         match err {
             ParseError::BadInput(msg) => {
                 assert!(
@@ -980,8 +1631,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_string_lines_tracks_62m_and_62f_separately() {
+        let lines = vec![
+            ":25:ACC".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":62M:C230102EUR120,00".to_string(),
+            ":62M:C230102EUR130,00".to_string(),
+            ":62F:C230103EUR150,00".to_string(),
+        ];
+
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        assert_eq!(msg.closing_balance.as_ref().unwrap().amount, "150,00");
+        assert_eq!(
+            msg.intermediate_closing_balance.as_ref().unwrap().amount,
+            "130,00"
+        );
+
+        let stmt = Statement::try_from(msg).unwrap();
+        assert_eq!(stmt.closing_balance, Some(15_000));
+        assert_eq!(
+            stmt.period_until,
+            NaiveDate::from_ymd_opt(2023, 1, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn statement_falls_back_to_last_62m_without_62f() {
+        let lines = vec![
+            ":25:ACC".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":62M:C230102EUR120,00".to_string(),
+            ":62M:D230102EUR30,00".to_string(),
+        ];
+
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        assert!(msg.closing_balance.is_none());
+
+        let stmt = Statement::try_from(msg).unwrap();
+        assert_eq!(stmt.closing_balance, Some(-3_000));
+    }
+
     // TryFrom<Mt940Message> for Statement
 
+    #[test]
+    fn mt940_message_to_statement_takes_account_name_from_25() {
+        let lines = vec![
+            ":25:DE11112222333344445555 ACME TRADING GMBH".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":62F:C230103EUR100,00".to_string(),
+        ];
+
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        let stmt = Statement::try_from(msg).unwrap();
+
+        assert_eq!(stmt.account_id, "DE11112222333344445555");
+        assert_eq!(stmt.account_name, Some("ACME TRADING GMBH".to_string()));
+    }
+
+    #[test]
+    fn mt940_message_to_statement_takes_account_name_from_first_86() {
+        let lines = vec![
+            ":25:DE11112222333344445555".to_string(),
+            ":60F:C230101EUR100,00".to_string(),
+            ":61:2301020102C50,00NTRFREF//BANK".to_string(),
+            ":86:Account owner: John Doe".to_string(),
+        ];
+
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        let stmt = Statement::try_from(msg).unwrap();
+
+        assert_eq!(stmt.account_name, Some("John Doe".to_string()));
+    }
+
     #[test]
     fn mt940_message_to_statement_maps_basic_fields() {
         let lines = vec![
@@ -992,7 +1714,7 @@ mod tests {
             ":62F:D230103EUR80,00".to_string(),
         ];
 
-        let msg = Mt940Message::from_string_lines(&lines).unwrap();
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
         let stmt = Statement::try_from(msg).unwrap();
 
         assert_eq!(stmt.account_id, "DE11112222333344445555");
@@ -1023,7 +1745,7 @@ mod tests {
     fn mt940_message_to_statement_errors_on_unknown_dc_mark_in_balances() {
         let lines = vec![":25:ACC".to_string(), ":60F:X230101EUR100,00".to_string()];
 
-        let msg = Mt940Message::from_string_lines(&lines).unwrap();
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
         let err = Statement::try_from(msg).unwrap_err();
 
         match err {
@@ -1046,7 +1768,7 @@ mod tests {
             ":60F:D230101EUR100,00".to_string(),
         ];
 
-        let msg = Mt940Message::from_string_lines(&lines).unwrap();
+        let msg = Mt940Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
         let stmt = Statement::try_from(msg).unwrap();
 
         assert_eq!(stmt.opening_balance, Some(-10_000));
@@ -1077,6 +1799,282 @@ mod tests {
         assert_eq!(stmt.transactions.len(), 1);
     }
 
+    #[test]
+    fn mt940_data_parse_error_carries_line_and_excerpt() {
+        let input = "{1:F01BANKDEFFXXXX0000000000}\n{4:\n:20:REF123\n:25:DE11112222333344445555\n:60F:C230101EUR100,00\n:61:2301020102C50,555NTRFREF//BANK\n:62F:C230103EUR150,00\n-}";
+
+        let err = Mt940Data::parse(input.as_bytes()).unwrap_err();
+
+        assert_eq!(err.line(), Some(6));
+        assert!(matches!(err.root(), ParseError::InvalidAmount(_)), "{err}");
+        assert_eq!(
+            err.excerpt(),
+            Some(
+                "  5 | :60F:C230101EUR100,00\n> 6 | :61:2301020102C50,555NTRFREF//BANK\n  7 | :62F:C230103EUR150,00"
+            )
+        );
+    }
+
+    #[test]
+    fn mt940_data_parse_accepts_envelope_less_statements() {
+        let input = "\
+:20:REF1
+:25:DE11112222333344445555
+:60F:C230101EUR100,00
+:61:2301020102C50,00NTRFREF//BANK
+:86:first
+:62F:C230102EUR150,00
+
+:20:REF2
+:25:DE11112222333344445555
+:60F:C230102EUR150,00
+:62F:C230103EUR150,00
+";
+
+        let data = Mt940Data::parse(input.as_bytes()).unwrap();
+
+        assert_eq!(data.message.transaction_reference.as_deref(), Some("REF1"));
+        assert_eq!(data.message.entries.len(), 1);
+        assert_eq!(data.message.entries[0].info.lines, vec!["first"]);
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::MultipleStatements { format: "mt940" }]
+        );
+    }
+
+    const TWO_MESSAGES: &str = "{4:\n:20:REF1\n:25:ACC1\n:60F:C230101EUR100,00\n:62F:C230101EUR100,00\n-}\n\
+                                {4:\n:20:REF2\n:25:ACC2\n:60F:C230102EUR5,00\n:62F:C230102EUR5,00\n-}\n";
+
+    #[test]
+    fn mt940_on_multiple_keep_first_warns() {
+        let data = Mt940Data::parse(TWO_MESSAGES.as_bytes()).unwrap();
+
+        assert_eq!(data.message.account_id, "ACC1");
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::MultipleStatements { format: "mt940" }]
+        );
+    }
+
+    #[test]
+    fn mt940_on_multiple_error_out_fails_single_parse() {
+        let options = Mt940ParseOptions {
+            on_multiple: MultipleStatementsPolicy::ErrorOut,
+            ..Default::default()
+        };
+        let err = Mt940Data::parse_with_options(TWO_MESSAGES.as_bytes(), &options).unwrap_err();
+
+        assert!(
+            matches!(
+                err,
+                ParseError::MultipleStatements {
+                    format: "mt940",
+                    count: 2
+                }
+            ),
+            "{err:?}"
+        );
+
+        // одно сообщение - политика не важна
+        let single = TWO_MESSAGES.split_inclusive("-}\n").next().unwrap();
+        let options = Mt940ParseOptions {
+            on_multiple: MultipleStatementsPolicy::ErrorOut,
+            ..Default::default()
+        };
+        let data = Mt940Data::parse_with_options(single.as_bytes(), &options).unwrap();
+        assert!(data.warnings.is_empty());
+    }
+
+    #[test]
+    fn mt940_on_multiple_keep_all_keeps_every_message() {
+        let options = Mt940ParseOptions {
+            on_multiple: MultipleStatementsPolicy::KeepAll,
+            ..Default::default()
+        };
+        let data = Mt940Data::parse_with_options(TWO_MESSAGES.as_bytes(), &options).unwrap();
+
+        assert_eq!(data.message.account_id, "ACC1");
+        assert!(data.warnings.is_empty());
+        assert_eq!(data.rest.len(), 1);
+
+        let all = data.into_all();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].message.account_id, "ACC2");
+        assert!(all.iter().all(|data| data.rest.is_empty()));
+    }
+
+    #[test]
+    fn mt940_missing_currency_is_inferred_from_iban_only_on_request() {
+        let input = "{4:\n:20:REF1\n:25:DE89370400440532013000\n:60F:C230101100,00\n\
+                     :62F:C230101100,00\n-}\n";
+
+        let data = Mt940Data::parse(input.as_bytes()).unwrap();
+        assert!(data.warnings.is_empty());
+        let stmt = Statement::try_from(data).unwrap();
+        assert_eq!(stmt.currency, Currency::Other(String::new()));
+        assert_eq!(stmt.opening_balance, Some(10_000));
+
+        let options = Mt940ParseOptions {
+            infer_currency_from_iban: true,
+            ..Default::default()
+        };
+        let data = Mt940Data::parse_with_options(input.as_bytes(), &options).unwrap();
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::CurrencyInferred {
+                format: "mt940",
+                account: "DE89370400440532013000".to_string(),
+                currency: Currency::EUR,
+            }]
+        );
+        assert_eq!(Statement::try_from(data).unwrap().currency, Currency::EUR);
+
+        // явная валюта важнее догадки
+        let data = Mt940Data::parse_with_options(TWO_MESSAGES.as_bytes(), &options).unwrap();
+        assert!(
+            !data
+                .warnings
+                .iter()
+                .any(|w| matches!(w, ParseWarning::CurrencyInferred { .. }))
+        );
+    }
+
+    #[test]
+    fn mt940_parse_all_keeps_every_message() {
+        let all = Mt940Data::parse_all(TWO_MESSAGES.as_bytes()).unwrap();
+
+        let accounts: Vec<&str> = all.iter().map(|d| d.message.account_id.as_str()).collect();
+        assert_eq!(accounts, ["ACC1", "ACC2"]);
+        assert!(all.iter().all(|d| d.warnings.is_empty()));
+
+        assert!(Mt940Data::parse_all(&b""[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn mt940_data_try_from_str_and_string() {
+        let input = "{4:\n:20:REF\n:25:ACC\n:60F:C230101EUR100,00\n:62F:C230103EUR100,00\n-}";
+
+        let from_str = Mt940Data::try_from(input).unwrap();
+        assert_eq!(from_str.message.account_id, "ACC");
+
+        let from_string = Mt940Data::try_from(input.to_string()).unwrap();
+        assert_eq!(
+            from_string.message.transaction_reference.as_deref(),
+            Some("REF")
+        );
+
+        assert!(Mt940Data::try_from("").is_err());
+    }
+
+    #[test]
+    fn mt940_data_parse_warns_on_mixed_decimal_separators() {
+        let input = r#"{4:
+        :20:REF123
+        :25:DE11112222333344445555
+        :60F:C230101EUR100.00
+        :61:2301020102C50,50NTRFREF//BANK
+        :62F:C230103EUR150,50
+        -}
+        "#;
+
+        let options = Mt940ParseOptions {
+            check_decimal_consistency: true,
+            ..Default::default()
+        };
+        let data = Mt940Data::parse_with_options(input.as_bytes(), &options).unwrap();
+
+        assert_eq!(data.warnings.len(), 1);
+        assert_eq!(
+            data.warnings[0],
+            ParseWarning::MixedDecimalSeparators {
+                account: "DE11112222333344445555".to_string()
+            }
+        );
+
+        // без опции проверка не выполняется
+        let data = Mt940Data::parse(input.as_bytes()).unwrap();
+        assert!(data.warnings.is_empty());
+    }
+
+    #[test]
+    fn mt940_data_parse_accepts_close_marker_after_closing_balance() {
+        let input = "{4:\n:20:REF123\n:25:DE11112222333344445555\n:60F:C230101EUR100,00\n\
+                     :61:2301020102C50,00NTRFREF//BANK\n:62F:C230103EUR150,00-}{5:{CHK:123}}\n\
+                     {4:\n:20:SECOND\n:25:OTHER\n:60F:C230101EUR1,00\n:62F:C230103EUR1,00-}\n";
+
+        let data = Mt940Data::parse(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            data.message.transaction_reference.as_deref(),
+            Some("REF123")
+        );
+        assert_eq!(data.message.entries.len(), 1);
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::MultipleStatements { format: "mt940" }]
+        );
+
+        let stmt = Statement::try_from(data).unwrap();
+        assert_eq!(stmt.closing_balance, Some(15_000));
+    }
+
+    #[test]
+    fn split_trailing_close_marker_requires_marker_at_end() {
+        assert_eq!(
+            split_trailing_close_marker(":62F:C230103EUR150,00-}", "-}"),
+            Some(":62F:C230103EUR150,00")
+        );
+        assert_eq!(
+            split_trailing_close_marker(":62F:C230103EUR150,00-}{5:{CHK:1}}  ", "-}"),
+            Some(":62F:C230103EUR150,00")
+        );
+        assert_eq!(split_trailing_close_marker(":86:smile :-) ok", "-)"), None);
+        assert_eq!(split_trailing_close_marker(":86:a-}b", "-}"), None);
+    }
+
+    #[test]
+    fn mt940_data_parse_collects_unknown_tag_warning() {
+        let input = r#"{4:
+        :20:REF123
+        :25:DE11112222333344445555
+        :60F:C230101EUR100,00
+        :99X:SOMETHING
+        :61:2301020102C50,00NTRFREF//BANK
+        :62F:C230103EUR150,00
+        -}
+        "#;
+
+        let data = Mt940Data::parse(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            data.warnings,
+            vec![ParseWarning::UnknownTag {
+                tag: "99X".to_string(),
+                value: "SOMETHING".to_string(),
+            }]
+        );
+        assert_eq!(data.message.entries.len(), 1);
+    }
+
+    #[test]
+    fn mt940_data_parse_no_warning_on_consistent_separators() {
+        let input = r#"{4:
+        :25:DE11112222333344445555
+        :60F:C230101EUR100,00
+        :61:2301020102C50,50NTRFREF//BANK
+        :62F:C230103EUR150,50
+        -}
+        "#;
+
+        let options = Mt940ParseOptions {
+            check_decimal_consistency: true,
+            ..Default::default()
+        };
+        let data = Mt940Data::parse_with_options(input.as_bytes(), &options).unwrap();
+
+        assert!(data.warnings.is_empty());
+    }
+
     #[test]
     fn mt940_data_parse_errors_on_empty_input() {
         let err = Mt940Data::parse("".as_bytes()).unwrap_err();
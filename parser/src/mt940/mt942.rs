@@ -0,0 +1,410 @@
+use super::utils::*;
+use super::{Mt940Entry, split_text_blocks};
+use crate::date::Date;
+use crate::error::{ParseError, ParseWarning};
+use crate::model::{Currency, Direction, SourceFormat, Statement, Transaction};
+use crate::utils::parse_currency;
+use std::io::Read;
+
+/// Промежуточная выписка MT942 (interim transaction report)
+///
+/// По структуре близка к MT940, но не содержит балансов :60F:/:62F:.
+/// Вместо них есть :13D: (дата/время формирования) и :34F: (floor limit).
+#[derive(Debug, Clone)]
+pub struct Mt942Message {
+    /// :20: Transaction Reference Number
+    pub transaction_reference: Option<String>,
+
+    /// :25: Account Identification (номер счёта/IBAN как есть)
+    pub account_id: String,
+
+    /// :28C: Statement Number/Sequence, сырой текст
+    pub statement_number: Option<String>,
+
+    /// :13D: дата формирования отчёта (время и смещение отбрасываются)
+    pub creation_date: Option<Date>,
+
+    /// Код валюты из первого :34F:, как есть ("EUR", "USD", ...)
+    pub currency: Option<String>,
+
+    /// :34F: лимит по дебету: в отчёт попадают дебетовые проводки не меньше суммы (в "копейках")
+    ///
+    /// Единственный :34F: без отметки D/C действует на обе стороны.
+    pub debit_floor_limit: Option<(Direction, Currency, u64)>,
+
+    /// :34F: лимит по кредиту, см. [`Mt942Message::debit_floor_limit`]
+    pub credit_floor_limit: Option<(Direction, Currency, u64)>,
+
+    /// Список всех проводок (:61: + связанные текстовые блоки)
+    pub entries: Vec<Mt940Entry>,
+}
+
+impl Mt942Message {
+    pub(crate) fn from_string_lines(
+        lines: &[String],
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Self, ParseError> {
+        let mut tx_ref: Option<String> = None; // :20:
+        let mut account_id: Option<String> = None; // :25:
+        let mut statement_number: Option<String> = None; // :28C:
+        let mut creation_date: Option<Date> = None; // :13D:
+        let mut currency: Option<String> = None; // :34F:
+        let mut debit_floor_limit: Option<(Direction, Currency, u64)> = None; // :34F:D
+        let mut credit_floor_limit: Option<(Direction, Currency, u64)> = None; // :34F:C
+
+        let mut entries: Vec<Mt940Entry> = Vec::new();
+        let mut current_entry: Option<Mt940Entry> = None;
+
+        for raw_line in lines {
+            let line = raw_line.trim_end_matches('\r');
+            let line_trimmed = line.trim_start();
+
+            if line_trimmed.starts_with(':') {
+                let (tag, value) = split_tag_line(line_trimmed)?;
+
+                match tag {
+                    "20" => {
+                        tx_ref = Some(value.to_string());
+                    }
+                    "25" => {
+                        account_id = Some(value.to_string());
+                    }
+                    "28C" => {
+                        statement_number = Some(value.to_string());
+                    }
+                    "34F" => {
+                        let (ccy, mark, amount) = parse_floor_limit(value)?;
+                        let limit = |direction| Some((direction, parse_currency(ccy), amount));
+                        match mark {
+                            Some(Direction::Debit) => debit_floor_limit = limit(Direction::Debit),
+                            Some(Direction::Credit) => {
+                                credit_floor_limit = limit(Direction::Credit)
+                            }
+                            None => {
+                                debit_floor_limit = limit(Direction::Debit);
+                                credit_floor_limit = limit(Direction::Credit);
+                            }
+                        }
+                        if currency.is_none() {
+                            currency = Some(ccy.to_string());
+                        }
+                    }
+                    "13D" | "13" => {
+                        // YYMMDDHHMM[+-HHMM], нужна только дата
+                        let date = value.trim().get(..6).unwrap_or(value.trim());
+                        creation_date = Some(parse_mt940_yy_mm_dd(date)?);
+                    }
+                    "90D" | "90C" => {
+                        // итоги по оборотам - не используются
+                    }
+                    "61" => {
+                        if let Some(entry) = current_entry.take() {
+                            entries.push(entry);
+                        }
+                        current_entry =
+                            Some(Mt940Entry::from_61_line(value, line_trimmed.to_string())?);
+                    }
+                    "86" => {
+                        if let Some(entry) = current_entry.as_mut() {
+                            entry.push_info_line(value);
+                        }
+                    }
+                    other => {
+                        warnings.push(ParseWarning::UnknownTag {
+                            tag: other.to_string(),
+                            value: value.to_string(),
+                        });
+                    }
+                }
+            } else if let Some(entry) = current_entry.as_mut() {
+                // строка без ':', продолжение описания
+                entry.push_info_line(line_trimmed);
+            }
+        }
+
+        if let Some(entry) = current_entry.take() {
+            entries.push(entry);
+        }
+
+        let account_id = account_id
+            .ok_or_else(|| ParseError::BadInput("MT942: missing :25: account id".into()))?;
+
+        Ok(Mt942Message {
+            transaction_reference: tx_ref,
+            account_id,
+            statement_number,
+            creation_date,
+            currency,
+            debit_floor_limit,
+            credit_floor_limit,
+            entries,
+        })
+    }
+}
+
+/// Разбирает значение :34F: (`3!a[1!a]15d`): валюта, необязательная отметка D/C и сумма
+fn parse_floor_limit(value: &str) -> Result<(&str, Option<Direction>, u64), ParseError> {
+    let value = value.trim();
+    let invalid = || ParseError::BadInput(format!("MT942: invalid :34F: floor limit '{value}'"));
+
+    let ccy = value.get(0..3).ok_or_else(invalid)?;
+    if !ccy.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid());
+    }
+
+    let rest = &value[3..];
+    let (mark, amount) = match rest.chars().next() {
+        Some('D') => (Some(Direction::Debit), &rest[1..]),
+        Some('C') => (Some(Direction::Credit), &rest[1..]),
+        _ => (None, rest),
+    };
+
+    Ok((ccy, mark, parse_mt940_amount(amount)?))
+}
+
+impl TryFrom<Mt942Message> for Statement {
+    type Error = ParseError;
+
+    fn try_from(message: Mt942Message) -> Result<Self, Self::Error> {
+        let currency: Currency = match &message.currency {
+            Some(ccy) => parse_currency(ccy),
+            None => {
+                return Err(ParseError::InvalidCurrency(
+                    "MT942: no currency found (:34F:)".into(),
+                ));
+            }
+        };
+
+        let transactions = message
+            .entries
+            .iter()
+            .map(Transaction::try_from)
+            .collect::<Result<Vec<Transaction>, ParseError>>()?;
+
+        // балансов нет, поэтому период берём по датам проводок,
+        // а отчёт без проводок относим к дате формирования из :13D:
+        let dates = transactions.iter().map(|tx| tx.booking_date);
+        let period_from: Option<Date> = dates.clone().min().or(message.creation_date);
+        let period_until: Option<Date> = dates.max().or(message.creation_date);
+
+        let (Some(period_from), Some(period_until)) = (period_from, period_until) else {
+            return Err(ParseError::BadInput(
+                "MT942: cannot detect statement period without entries or :13D:".into(),
+            ));
+        };
+
+        let mut statement = Statement::new(
+            message.account_id,
+            None,
+            currency,
+            None,
+            None,
+            transactions,
+            period_from,
+            period_until,
+        );
+        statement.source_format = Some(SourceFormat::Mt942);
+
+        Ok(statement)
+    }
+}
+
+/// Структура с сырыми данными формата mt942.
+///
+/// Для парсинга используйте [`Mt942Data::parse`].
+#[derive(Debug, Clone)]
+pub struct Mt942Data {
+    /// Пока один Statement
+    pub message: Mt942Message,
+
+    /// Некритичные замечания, собранные при парсинге
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl Mt942Data {
+    /// Парсит при помощи переданного reader данные  в [`Mt942Data`]
+    ///
+    /// При ошибке возвращает [`ParseError`]
+    pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
+        let mut messages: Vec<(Mt942Message, Vec<ParseWarning>)> = Vec::new();
+        for block in split_text_blocks(reader)? {
+            let mut warnings = Vec::new();
+            let message = Mt942Message::from_string_lines(&block.lines, &mut warnings)?;
+            messages.push((message, warnings));
+        }
+
+        let mut messages_iter = messages.into_iter();
+        let (final_msg, mut warnings) = messages_iter
+            .next()
+            .ok_or_else(|| ParseError::BadInput("0 mt942 messages detected".into()))?;
+
+        if messages_iter.next().is_some() {
+            warnings.push(ParseWarning::MultipleStatements { format: "mt942" });
+        }
+
+        Ok(Mt942Data {
+            message: final_msg,
+            warnings,
+        })
+    }
+}
+
+impl TryFrom<&str> for Mt942Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Mt942Data::parse`]
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input.as_bytes())
+    }
+}
+
+impl TryFrom<String> for Mt942Data {
+    type Error = ParseError;
+
+    /// Разбирает строку в памяти, как [`Mt942Data::parse`]
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::try_from(input.as_str())
+    }
+}
+
+impl TryFrom<Mt942Data> for Statement {
+    type Error = ParseError;
+
+    fn try_from(data: Mt942Data) -> Result<Self, Self::Error> {
+        Statement::try_from(data.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Direction;
+
+    const MINIMAL_MT942: &str = r#"{1:F01FOOBARBAXXX0000000000}{2:O942...}{4:
+        :20:INTERIM1
+        :25:DE11112222333344445555
+        :28C:1/1
+        :34F:EURD0,
+        :13D:2301021200+0100
+        :61:2301020102C50,00NTRFREF//BANK
+        :86:Incoming payment
+        :61:2301030103D20,00NTRFREF2//BANK2
+        :90D:1EUR20,00
+        :90C:1EUR50,00
+        -}
+        "#;
+
+    #[test]
+    fn mt942_data_parse_parses_minimal_block() {
+        let data = Mt942Data::parse(MINIMAL_MT942.as_bytes()).unwrap();
+        let msg = &data.message;
+
+        assert_eq!(msg.transaction_reference.as_deref(), Some("INTERIM1"));
+        assert_eq!(msg.account_id, "DE11112222333344445555");
+        assert_eq!(msg.statement_number.as_deref(), Some("1/1"));
+        assert_eq!(msg.currency.as_deref(), Some("EUR"));
+        assert_eq!(msg.entries.len(), 2);
+        assert_eq!(msg.entries[0].info.lines, vec!["Incoming payment"]);
+        assert_eq!(msg.creation_date, crate::date::ymd(2023, 1, 2));
+    }
+
+    #[test]
+    fn mt942_without_entries_takes_period_from_13d() {
+        let input = ":20:INTERIM2\n:25:DE11112222333344445555\n:28C:2/1\n:34F:EUR0,00\n:13D:2301051200+0100\n";
+
+        let data = Mt942Data::parse(input.as_bytes()).unwrap();
+        let stmt = Statement::try_from(data).unwrap();
+
+        assert!(stmt.transactions.is_empty());
+        assert_eq!(Some(stmt.period_from), crate::date::ymd(2023, 1, 5));
+        assert_eq!(Some(stmt.period_until), crate::date::ymd(2023, 1, 5));
+    }
+
+    #[test]
+    fn mt942_data_try_from_str() {
+        let data = Mt942Data::try_from(MINIMAL_MT942).unwrap();
+        assert_eq!(data.message.account_id, "DE11112222333344445555");
+
+        let data = Mt942Data::try_from(MINIMAL_MT942.to_string()).unwrap();
+        assert_eq!(data.message.entries.len(), 2);
+    }
+
+    #[test]
+    fn mt942_to_statement_has_no_balances() {
+        let data = Mt942Data::parse(MINIMAL_MT942.as_bytes()).unwrap();
+        let stmt = Statement::try_from(data).unwrap();
+
+        assert_eq!(stmt.account_id, "DE11112222333344445555");
+        assert_eq!(stmt.currency, Currency::EUR);
+        assert_eq!(stmt.opening_balance, None);
+        assert_eq!(stmt.closing_balance, None);
+        assert_eq!(stmt.source_format, Some(SourceFormat::Mt942));
+
+        assert_eq!(stmt.transactions.len(), 2);
+        assert_eq!(stmt.transactions[0].direction, Direction::Credit);
+        assert_eq!(stmt.transactions[0].amount, 5_000);
+        assert_eq!(stmt.transactions[1].direction, Direction::Debit);
+
+        assert_eq!(stmt.period_from, crate::date::ymd(2023, 1, 2).unwrap());
+        assert_eq!(stmt.period_until, crate::date::ymd(2023, 1, 3).unwrap());
+    }
+
+    #[test]
+    fn mt942_message_parses_debit_and_credit_floor_limits() {
+        let lines = vec![
+            ":25:ACC".to_string(),
+            ":34F:EURD1000,".to_string(),
+            ":34F:EURC250,50".to_string(),
+        ];
+
+        let msg = Mt942Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            msg.debit_floor_limit,
+            Some((Direction::Debit, Currency::EUR, 100_000))
+        );
+        assert_eq!(
+            msg.credit_floor_limit,
+            Some((Direction::Credit, Currency::EUR, 25_050))
+        );
+        assert_eq!(msg.currency.as_deref(), Some("EUR"));
+    }
+
+    #[test]
+    fn mt942_single_floor_limit_applies_to_both_sides() {
+        let lines = vec![":25:ACC".to_string(), ":34F:USD0,".to_string()];
+
+        let msg = Mt942Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            msg.debit_floor_limit,
+            Some((Direction::Debit, Currency::USD, 0))
+        );
+        assert_eq!(
+            msg.credit_floor_limit,
+            Some((Direction::Credit, Currency::USD, 0))
+        );
+    }
+
+    #[test]
+    fn mt942_message_requires_account_id() {
+        let lines = vec![":20:REF".to_string(), ":34F:EUR0,".to_string()];
+
+        let err = Mt942Message::from_string_lines(&lines, &mut Vec::new()).unwrap_err();
+        match err {
+            ParseError::BadInput(msg) => {
+                assert!(msg.contains("missing :25"), "unexpected msg: {msg}");
+            }
+            other => panic!("expected BadInput, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn mt942_to_statement_errors_without_currency() {
+        let lines = vec![":25:ACC".to_string(), ":61:2301020102C50,00".to_string()];
+
+        let msg = Mt942Message::from_string_lines(&lines, &mut Vec::new()).unwrap();
+        let err = Statement::try_from(msg).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidCurrency(_)));
+    }
+}
@@ -1,15 +1,9 @@
+use super::Mt940Header;
 use crate::ParseError;
-use chrono::{Datelike, NaiveDate};
-use lazy_regex::lazy_regex;
-use once_cell::sync::Lazy;
-use regex::Regex;
-
-/// IBAN в формате:
-/// (?i) - case-insensitive
-/// ^[A-Z]{2} - 2 буквы страны
-/// \d{2} - 2 цифры
-/// [A-Z0-9]{11,30} - хвост
-static IBAN_RE: Lazy<Regex> = lazy_regex!(r"(?i)^[A-Z]{2}\d{2}[A-Z0-9]{11,30}$");
+use crate::date::{self, Date};
+use crate::utils::{normalize_and_check_iban, strip_utf8_bom};
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
 
 /// Разделяет строку с тегом на сам тег и строку после него
 pub(super) fn split_tag_line(line: &str) -> Result<(&str, &str), ParseError> {
@@ -30,7 +24,7 @@ pub(super) fn split_tag_line(line: &str) -> Result<(&str, &str), ParseError> {
     Ok((tag, value))
 }
 
-pub(super) fn parse_mt940_yy_mm_dd(s: &str) -> Result<NaiveDate, ParseError> {
+pub(super) fn parse_mt940_yy_mm_dd(s: &str) -> Result<Date, ParseError> {
     if s.len() != 6 {
         return Err(ParseError::BadInput(format!("invalid YYMMDD date: '{s}'")));
     }
@@ -48,14 +42,14 @@ pub(super) fn parse_mt940_yy_mm_dd(s: &str) -> Result<NaiveDate, ParseError> {
     // простое допущение: 00-79 -> 2000-2079, 80-99 -> 1900-1999
     let year = if yy >= 80 { 1900 + yy } else { 2000 + yy };
 
-    NaiveDate::from_ymd_opt(year, mm, dd)
+    date::ymd(year, mm, dd)
         .ok_or_else(|| ParseError::BadInput(format!("invalid YYMMDD date components: '{s}'")))
 }
 
 pub(super) fn derive_booking_date(
-    value_date: NaiveDate,
+    value_date: Date,
     entry_date: Option<&str>,
-) -> Result<NaiveDate, ParseError> {
+) -> Result<Date, ParseError> {
     let Some(ed) = entry_date else {
         // считаем, что дата проводки = value_date
         return Ok(value_date);
@@ -71,21 +65,21 @@ pub(super) fn derive_booking_date(
                 .parse()
                 .map_err(|_| ParseError::BadInput(format!("invalid MMDD in entry date: '{ed}'")))?;
 
-            let year = value_date.year();
+            let year = date::year(value_date);
 
-            NaiveDate::from_ymd_opt(year, mm, dd)
+            date::ymd(year, mm, dd)
                 .ok_or_else(|| ParseError::BadInput(format!("invalid MMDD entry date: '{ed}'")))
         }
         2 => {
             // DD, месяц берём из value_date
-            let mm = value_date.month();
+            let mm = date::month(value_date);
             let dd: u32 = ed
                 .parse()
                 .map_err(|_| ParseError::BadInput(format!("invalid DD in entry date: '{ed}'")))?;
 
-            let year = value_date.year();
+            let year = date::year(value_date);
 
-            NaiveDate::from_ymd_opt(year, mm, dd)
+            date::ymd(year, mm, dd)
                 .ok_or_else(|| ParseError::BadInput(format!("invalid DD entry date: '{ed}'")))
         }
         _ => Err(ParseError::BadInput(format!(
@@ -94,6 +88,59 @@ pub(super) fn derive_booking_date(
     }
 }
 
+/// Подписи, после которых в :86: банки указывают владельца счёта
+const OWNER_LABELS: [&str; 4] = ["ACCOUNT OWNER", "OWNER", "ВЛАДЕЛЕЦ СЧЕТА", "ВЛАДЕЛЕЦ"];
+
+/// Разделяет значение :25: на счёт и имя владельца, если банк дописал его через пробел
+///
+/// Имя берётся только если хвост похож на имя (буквы без цифр), иначе
+/// значение возвращается целиком как счёт.
+pub(super) fn split_account_owner(raw: &str) -> (&str, Option<&str>) {
+    let raw = raw.trim();
+    if let Some((account, rest)) = raw.split_once(char::is_whitespace) {
+        let rest = rest.trim();
+        if looks_like_party_name(rest) {
+            return (account, Some(rest));
+        }
+    }
+
+    (raw, None)
+}
+
+/// Ищет имя владельца счёта в строках :86: по подписи вроде `ACCOUNT OWNER: ...`
+pub(super) fn find_owner_name_in_lines(lines: &[String]) -> Option<String> {
+    lines.iter().find_map(|line| {
+        let (label, value) = line.split_once(':')?;
+        let label = label.trim().to_uppercase().replace('Ё', "Е");
+        if !OWNER_LABELS.contains(&label.as_str()) {
+            return None;
+        }
+
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// То же, что [`find_owner_name_in_lines`], но длинное имя может продолжаться
+/// на следующих строках без подписи - так его переносит сериализатор MT940.
+/// Подходит для `:86:` уровня выписки, где после имени нет текста проводки.
+pub(super) fn find_wrapped_owner_name_in_lines(lines: &[String]) -> Option<String> {
+    let pos = lines
+        .iter()
+        .position(|line| find_owner_name_in_lines(std::slice::from_ref(line)).is_some())?;
+    let mut name = find_owner_name_in_lines(&lines[pos..=pos])?;
+
+    for line in lines[pos + 1..]
+        .iter()
+        .take_while(|line| !line.contains(':'))
+    {
+        name.push(' ');
+        name.push_str(line.trim());
+    }
+
+    Some(name)
+}
+
 /// Ищет IBAN + имя в наборе строк
 pub(super) fn find_iban_and_name_in_lines(lines: &[String]) -> Option<(String, Option<String>)> {
     // Сначала пытаемся найти строку, где в одной строке есть и IBAN, и часть имени.
@@ -120,25 +167,42 @@ pub(super) fn find_iban_and_name_in_lines(lines: &[String]) -> Option<(String, O
 
     let iban = iban_value?;
 
-    // ищем имя в следующей непустой строке без IBAN
-    let mut name: Option<String> = None;
-    if let Some(idx) = iban_idx {
-        for line in lines.iter().skip(idx + 1) {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            if find_iban_in_line(trimmed).is_some() {
-                continue;
-            }
-            name = Some(trimmed.to_string());
-            break;
-        }
-    }
+    // кандидаты в имя - непустые строки без IBAN после строки с IBAN
+    let candidates: Vec<&str> = match iban_idx {
+        Some(idx) => lines
+            .iter()
+            .skip(idx + 1)
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && find_iban_in_line(line).is_none())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    // сначала строка, похожая на имя (адреса и суммы пропускаем),
+    // иначе - как раньше, первая непустая строка
+    let name = candidates
+        .iter()
+        .find(|line| looks_like_party_name(line))
+        .or(candidates.first())
+        .map(|line| line.to_string());
 
     Some((iban, name))
 }
 
+/// Эвристика: строка похожа на имя контрагента, а не на адрес или сумму.
+///
+/// Без цифр, длиннее 3 символов и в основном из букв.
+fn looks_like_party_name(line: &str) -> bool {
+    if line.chars().count() <= 3 || line.chars().any(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let non_space = line.chars().filter(|c| !c.is_whitespace()).count();
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+
+    letters * 10 >= non_space * 8
+}
+
 /// В одной строке ищем токен, похожий на IBAN.
 /// все, что после считается именем контрагента.
 pub(super) fn find_iban_and_name_in_line(line: &str) -> Option<(String, Option<String>)> {
@@ -172,22 +236,6 @@ pub(super) fn find_iban_in_line(line: &str) -> Option<String> {
         .next()
 }
 
-pub(super) fn normalize_and_check_iban(token: &str) -> Option<String> {
-    let cleaned = token
-        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
-        .to_uppercase();
-
-    if cleaned.is_empty() {
-        return None;
-    }
-
-    if IBAN_RE.is_match(&cleaned) {
-        Some(cleaned)
-    } else {
-        None
-    }
-}
-
 /// Забирает первый символ из rest и сдвигает rest на него.
 /// Возвращает Some(ch), если символ есть, иначе None.
 pub(super) fn take_char(rest: &mut &str) -> Option<char> {
@@ -224,18 +272,41 @@ pub(super) fn take_while(rest: &mut &str, mut pred: impl FnMut(char) -> bool) ->
     out
 }
 
-// возвращает: (dc_mark, funds_code, amount, оставшийся хвост)
+/// Определяет десятичный разделитель в сумме: последний из `,`/`.`
+///
+/// Если разделителя нет (целая сумма) - возвращает None
+pub(super) fn decimal_separator(amount: &str) -> Option<char> {
+    amount.chars().rev().find(|&c| c == ',' || c == '.')
+}
+
+// возвращает: (dc_mark, признак сторно, funds_code, amount, оставшийся хвост)
+//
+// Сторно по SWIFT пишется двумя буквами `RD`/`RC`: `RC` - сторно кредита,
+// то есть дебетовая запись, `RD` - сторно дебета, то есть кредитовая. В dc_mark
+// попадает итоговое направление, признак сторно выставляется, а следующая
+// буква (если есть) - это funds code.
 pub(super) fn parse_dc_and_amount<'a>(
     rest: &'a str,
     full_value: &str,
-) -> Result<(char, Option<char>, String, &'a str), ParseError> {
+) -> Result<(char, bool, Option<char>, String, &'a str), ParseError> {
     let mut rest = rest;
 
     // 1) D/C mark
-    let dc_mark = take_char(&mut rest).ok_or_else(|| {
+    let mut dc_mark = take_char(&mut rest).ok_or_else(|| {
         ParseError::BadInput(format!("no debit/credit mark in :61: '{full_value}'"))
     })?;
 
+    // 1a) RD/RC - сторно с направлением
+    let mut reversal = false;
+    if dc_mark == 'R'
+        && let Some(next_ch) = rest.chars().next()
+        && (next_ch == 'C' || next_ch == 'D')
+    {
+        let _ = take_char(&mut rest);
+        dc_mark = if next_ch == 'C' { 'D' } else { 'C' };
+        reversal = true;
+    }
+
     // 2) optional funds code (например R в "DR")
     let mut funds_code = None;
     if let Some(next_ch) = rest.chars().next()
@@ -248,10 +319,8 @@ pub(super) fn parse_dc_and_amount<'a>(
         funds_code = Some(next_ch);
     }
 
-    // 3) сумма: подряд идущие цифры/','/'.'
-    let amount = take_while(&mut rest, |ch| {
-        ch.is_ascii_digit() || ch == ',' || ch == '.'
-    });
+    // 3) сумма: цифры, десятичный разделитель и дробная часть
+    let amount = take_mt940_amount(&mut rest);
 
     if amount.is_empty() {
         return Err(ParseError::BadInput(format!(
@@ -259,7 +328,208 @@ pub(super) fn parse_dc_and_amount<'a>(
         )));
     }
 
-    Ok((dc_mark, funds_code, amount, rest))
+    Ok((
+        dc_mark,
+        reversal,
+        funds_code,
+        normalize_mt940_amount(&amount)?,
+        rest,
+    ))
+}
+
+/// Похоже ли на код типа операции из `:61:`
+///
+/// По SWIFT это `S` + три цифры (тип SWIFT-сообщения, напр. `S103`),
+/// `N` или `F` + три буквы/цифры (`NTRF`, `N020`, `FCHK`). Многие банки
+/// пишут и произвольные четыре буквы, их тоже принимаем.
+pub(super) fn is_transaction_type(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    if bytes.len() != 4 {
+        return false;
+    }
+    let tail = &bytes[1..];
+
+    match bytes[0] {
+        b'S' if tail.iter().all(u8::is_ascii_digit) => true,
+        b'N' | b'F' if tail.iter().all(u8::is_ascii_alphanumeric) => true,
+        _ => bytes.iter().all(u8::is_ascii_alphabetic),
+    }
+}
+
+/// Забирает из начала строки сумму MT940.
+///
+/// Пробелы допускаются только вокруг десятичного разделителя ("1000, 00"),
+/// в остальных местах пробел считается концом суммы.
+fn take_mt940_amount(rest: &mut &str) -> String {
+    let mut amount = take_while(rest, |ch| ch.is_ascii_digit());
+
+    let after_int = rest.trim_start_matches(' ');
+    if let Some(sep) = after_int.chars().next().filter(|c| matches!(c, ',' | '.')) {
+        amount.push(sep);
+        *rest = &after_int[1..];
+
+        let after_sep = rest.trim_start_matches(' ');
+        if after_sep.starts_with(|ch: char| ch.is_ascii_digit()) {
+            *rest = after_sep;
+        }
+        amount.push_str(&take_while(rest, |ch| ch.is_ascii_digit()));
+    }
+
+    amount
+}
+
+/// Нормализует сумму MT940: убирает пробелы вокруг десятичной запятой
+/// ("1000, 00" -> "1000,00") и проверяет, что после разделителя не больше двух знаков.
+///
+/// Формы без дробной части ("100,") и с одним знаком ("100,5") по стандарту SWIFT
+/// тоже допустимы, дополнение до двух знаков делает [`parse_mt940_amount`].
+pub(super) fn normalize_mt940_amount(raw: &str) -> Result<String, ParseError> {
+    let raw = raw.trim();
+    let invalid = || ParseError::InvalidAmount(format!("invalid mt940 amount: '{raw}'"));
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    let Some(pos) = raw.find([',', '.']) else {
+        if raw.is_empty() || !all_digits(raw) {
+            return Err(invalid());
+        }
+        return Ok(raw.to_string());
+    };
+
+    let int_part = raw[..pos].trim_end();
+    let sep = &raw[pos..pos + 1];
+    let frac_part = raw[pos + 1..].trim_start();
+
+    if int_part.is_empty() || !all_digits(int_part) || !all_digits(frac_part) || frac_part.len() > 2
+    {
+        return Err(invalid());
+    }
+
+    Ok(format!("{int_part}{sep}{frac_part}"))
+}
+
+/// Переводит сумму MT940 в "копейки"
+///
+/// По SWIFT запятая - единственный разделитель и она обязательна: `123,`
+/// означает `123.00`, `100,5` - `100.50`, групп разрядов не бывает. Точку принимаем так же
+/// (её пишут некоторые банки, см. [`Mt940ParseOptions::check_decimal_consistency`]).
+///
+/// [`Mt940ParseOptions::check_decimal_consistency`]: super::Mt940ParseOptions::check_decimal_consistency
+pub(super) fn parse_mt940_amount(raw: &str) -> Result<u64, ParseError> {
+    let normalized = normalize_mt940_amount(raw)?;
+    let (int_part, frac_part) = normalized
+        .split_once([',', '.'])
+        .unwrap_or((normalized.as_str(), ""));
+
+    let overflow = || ParseError::InvalidAmount(format!("mt940 amount is too large: '{raw}'"));
+    let units: u64 = int_part.parse().map_err(|_| overflow())?;
+    // дробная часть в 0..=2 знака, дополняем нулями до сотых
+    let minor: u64 = format!("{frac_part:0<2}").parse()?;
+
+    units
+        .checked_mul(100)
+        .and_then(|v| v.checked_add(minor))
+        .ok_or_else(overflow)
+}
+
+/// Достаёт содержимое заголовочного блока `{N:...}` (или `(N:...)`)
+fn header_block(headers: &str, n: char) -> Option<&str> {
+    [('{', '}'), ('(', ')')].iter().find_map(|&(open, close)| {
+        let start = headers.find(&format!("{open}{n}:"))? + 3;
+        let len = headers[start..].find(close)?;
+        Some(&headers[start..start + len])
+    })
+}
+
+/// Переводит LT-адрес (BIC8 + код терминала + филиал) в BIC.
+/// Филиал `XXX` (головной офис) отбрасывается.
+fn bic_from_lt_address(lt: &str) -> Option<String> {
+    if lt.len() != 12 || !lt.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let (bic8, branch) = (&lt[..8], &lt[9..]);
+    if branch == "XXX" {
+        Some(bic8.to_string())
+    } else {
+        Some(format!("{bic8}{branch}"))
+    }
+}
+
+/// Разбирает BIC отправителя и получателя из блоков `{1:}`/`{2:}`
+///
+/// Кто указан в `{1:}`, зависит от направления в `{2:}`:
+/// - `{2:I...}` (input) - в `{1:}` отправитель, в `{2:}` получатель;
+/// - `{2:O...}` (output) - в `{1:}` получатель, отправитель внутри MIR в `{2:}`.
+///
+/// Без `{2:}` направление неизвестно, поэтому BIC не заполняются.
+pub(super) fn parse_swift_headers(headers: &str) -> Mt940Header {
+    let basic_lt = header_block(headers, '1')
+        .and_then(|b| b.get(3..15))
+        .and_then(bic_from_lt_address);
+    let app = header_block(headers, '2');
+
+    match app.and_then(|b| b.chars().next()) {
+        Some('I') => Mt940Header {
+            sender_bic: basic_lt,
+            receiver_bic: app.and_then(|b| b.get(4..16)).and_then(bic_from_lt_address),
+        },
+        Some('O') => Mt940Header {
+            // O + тип(3) + время(4) + дата(6), затем LT-адрес отправителя
+            sender_bic: app
+                .and_then(|b| b.get(14..26))
+                .and_then(bic_from_lt_address),
+            receiver_bic: basic_lt,
+        },
+        _ => Mt940Header::default(),
+    }
+}
+
+/// Построчно читает вход, считая разделителем `\r\n`, `\n` и одиночный `\r`
+///
+/// `BufRead::lines` режет только по `\n`, и файл со старыми маковскими
+/// переводами строк превращается в одну гигантскую строку.
+/// UTF-8 BOM в начале первой строки отбрасывается.
+pub(super) fn normalized_lines<R: BufRead>(
+    mut reader: R,
+) -> impl Iterator<Item = io::Result<String>> {
+    let mut pending: VecDeque<String> = VecDeque::new();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut first_line = true;
+
+    std::iter::from_fn(move || {
+        loop {
+            if let Some(line) = pending.pop_front() {
+                return Some(Ok(line));
+            }
+
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if buf.last() == Some(&b'\n') {
+                        buf.pop();
+                    }
+                    if buf.last() == Some(&b'\r') {
+                        buf.pop();
+                    }
+
+                    if std::mem::take(&mut first_line) {
+                        let without_bom = strip_utf8_bom(&buf).len();
+                        buf.drain(..buf.len() - without_bom);
+                    }
+
+                    let text = match String::from_utf8(std::mem::take(&mut buf)) {
+                        Ok(text) => text,
+                        Err(e) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+                    };
+
+                    // внутри куска могут остаться одиночные \r
+                    pending.extend(text.split('\r').map(str::to_string));
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    })
 }
 
 #[cfg(test)]
@@ -268,6 +538,89 @@ mod tests {
     use crate::ParseError;
     use chrono::NaiveDate;
 
+    // normalized_lines
+
+    fn collect_lines(input: &str) -> Vec<String> {
+        normalized_lines(input.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn normalized_lines_splits_on_all_line_endings() {
+        assert_eq!(collect_lines("a\nb\r\nc\rd"), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn normalized_lines_keeps_empty_lines_and_drops_final_terminator() {
+        assert_eq!(collect_lines("a\r\rb\r\n"), vec!["a", "", "b"]);
+        assert!(collect_lines("").is_empty());
+    }
+
+    #[test]
+    fn normalized_lines_drops_leading_bom() {
+        assert_eq!(
+            collect_lines("\u{feff}{4:\n:20:REF"),
+            vec!["{4:", ":20:REF"]
+        );
+        // BOM посреди файла - не наша забота
+        assert_eq!(collect_lines("a\n\u{feff}b"), vec!["a", "\u{feff}b"]);
+    }
+
+    #[test]
+    fn normalized_lines_reports_invalid_utf8() {
+        let mut lines = normalized_lines(&b"\xff\xfe\n"[..]);
+        let err = lines.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    // split_account_owner / find_owner_name_in_lines
+
+    #[test]
+    fn split_account_owner_extracts_trailing_name() {
+        assert_eq!(
+            split_account_owner("DE89370400440532013000 ACME TRADING GMBH"),
+            ("DE89370400440532013000", Some("ACME TRADING GMBH"))
+        );
+    }
+
+    #[test]
+    fn split_account_owner_keeps_non_name_tail_in_account() {
+        assert_eq!(split_account_owner("12345678 EUR"), ("12345678 EUR", None));
+        assert_eq!(
+            split_account_owner("NDEAFIHH/123 456"),
+            ("NDEAFIHH/123 456", None)
+        );
+        assert_eq!(split_account_owner("ACC"), ("ACC", None));
+    }
+
+    #[test]
+    fn find_owner_name_in_lines_uses_owner_label() {
+        let lines = vec![
+            "Incoming payment".to_string(),
+            "Владелец счёта: ООО Ромашка".to_string(),
+        ];
+        assert_eq!(
+            find_owner_name_in_lines(&lines),
+            Some("ООО Ромашка".to_string())
+        );
+        assert_eq!(find_owner_name_in_lines(&lines[..1]), None);
+    }
+
+    #[test]
+    fn find_wrapped_owner_name_in_lines_joins_continuation_lines() {
+        let lines = vec![
+            "ACCOUNT OWNER: ОБЩЕСТВО С ОГРАНИЧЕННОЙ".to_string(),
+            "ОТВЕТСТВЕННОСТЬЮ \"РОМАШКА\"".to_string(),
+            "Period: January".to_string(),
+        ];
+        assert_eq!(
+            find_wrapped_owner_name_in_lines(&lines),
+            Some("ОБЩЕСТВО С ОГРАНИЧЕННОЙ ОТВЕТСТВЕННОСТЬЮ \"РОМАШКА\"".to_string())
+        );
+        assert_eq!(find_wrapped_owner_name_in_lines(&lines[1..]), None);
+    }
+
     // split_tag_line
 
     #[test]
@@ -471,6 +824,28 @@ mod tests {
         assert_eq!(name, Some("John Doe Full Name".to_string()));
     }
 
+    #[test]
+    fn find_iban_and_name_in_lines_skips_address_line_before_name() {
+        let lines = vec![
+            format!("IBAN: {VALID_IBAN}"),
+            "Hauptstrasse 5, 10115 Berlin".to_string(),
+            "Max Mustermann".to_string(),
+        ];
+
+        let (iban, name) = find_iban_and_name_in_lines(&lines).unwrap();
+        assert_eq!(iban, VALID_IBAN);
+        assert_eq!(name, Some("Max Mustermann".to_string()));
+    }
+
+    #[test]
+    fn find_iban_and_name_in_lines_falls_back_to_first_line() {
+        // ничего похожего на имя - берём первую строку, как раньше
+        let lines = vec![format!("IBAN: {VALID_IBAN}"), "REF 12345".to_string()];
+
+        let (_, name) = find_iban_and_name_in_lines(&lines).unwrap();
+        assert_eq!(name, Some("REF 12345".to_string()));
+    }
+
     #[test]
     fn find_iban_and_name_in_lines_returns_none_if_no_iban() {
         let lines = vec!["NO IBAN HERE".to_string(), "STILL NO IBAN".to_string()];
@@ -543,6 +918,28 @@ mod tests {
         assert_eq!(rest, "A");
     }
 
+    // decimal_separator
+
+    #[test]
+    fn decimal_separator_detects_comma_and_dot() {
+        assert_eq!(decimal_separator("100,00"), Some(','));
+        assert_eq!(decimal_separator("100.00"), Some('.'));
+        assert_eq!(decimal_separator("1.000,00"), Some(','));
+        assert_eq!(decimal_separator("100"), None);
+    }
+
+    // is_transaction_type
+
+    #[test]
+    fn is_transaction_type_accepts_swift_codes() {
+        for code in ["NTRF", "N020", "NMSC", "S103", "F0A1", "FCHK", "MSCX"] {
+            assert!(is_transaction_type(code), "{code}");
+        }
+        for code in ["0020", "S1A3", "X020", "NTR", "NTRF1", "N 20"] {
+            assert!(!is_transaction_type(code), "{code}");
+        }
+    }
+
     // parse_dc_and_amount
 
     #[test]
@@ -550,10 +947,11 @@ mod tests {
         let rest = "C100,00";
         let full = rest;
 
-        let (dc_mark, funds_code, amount, tail) =
+        let (dc_mark, reversal, funds_code, amount, tail) =
             parse_dc_and_amount(rest, full).expect("parse_dc_and_amount failed");
 
         assert_eq!(dc_mark, 'C');
+        assert!(!reversal);
         assert_eq!(funds_code, None);
         assert_eq!(amount, "100,00");
         assert_eq!(tail, "");
@@ -564,10 +962,11 @@ mod tests {
         let rest = "D250,00NTRFREF123//BANKREF some extra";
         let full = rest;
 
-        let (dc_mark, funds_code, amount, tail) =
+        let (dc_mark, reversal, funds_code, amount, tail) =
             parse_dc_and_amount(rest, full).expect("parse_dc_and_amount failed");
 
         assert_eq!(dc_mark, 'D');
+        assert!(!reversal);
         assert_eq!(funds_code, None);
         assert_eq!(amount, "250,00");
         assert!(tail.starts_with("NTRFREF123//BANKREF some extra"));
@@ -578,15 +977,45 @@ mod tests {
         let rest = "DR100,00"; // D + funds_code R + amount
         let full = rest;
 
-        let (dc_mark, funds_code, amount, tail) =
+        let (dc_mark, reversal, funds_code, amount, tail) =
             parse_dc_and_amount(rest, full).expect("parse_dc_and_amount failed");
 
         assert_eq!(dc_mark, 'D');
+        assert!(!reversal);
         assert_eq!(funds_code, Some('R'));
         assert_eq!(amount, "100,00");
         assert_eq!(tail, "");
     }
 
+    #[test]
+    fn parse_dc_and_amount_normalizes_swift_reversal_mark() {
+        let rest = "RD100,00NTRF";
+
+        let (dc_mark, reversal, funds_code, amount, tail) =
+            parse_dc_and_amount(rest, rest).unwrap();
+
+        // RD - сторно дебета, то есть кредитовая запись
+        assert_eq!(dc_mark, 'C');
+        assert!(reversal);
+        assert_eq!(funds_code, None);
+        assert_eq!(amount, "100,00");
+        assert_eq!(tail, "NTRF");
+    }
+
+    #[test]
+    fn parse_dc_and_amount_reads_funds_code_after_reversal_mark() {
+        let rest = "RCE100,00NTRF";
+
+        let (dc_mark, reversal, funds_code, amount, tail) =
+            parse_dc_and_amount(rest, rest).unwrap();
+
+        assert_eq!(dc_mark, 'D');
+        assert!(reversal);
+        assert_eq!(funds_code, Some('E'));
+        assert_eq!(amount, "100,00");
+        assert_eq!(tail, "NTRF");
+    }
+
     #[test]
     fn parse_dc_and_amount_errors_when_amount_missing() {
         // есть только D/C mark, но нет цифр суммы
@@ -597,4 +1026,69 @@ mod tests {
 
         assert!(result.is_err(), "expected error when amount is missing");
     }
+
+    #[test]
+    fn parse_dc_and_amount_accepts_spaces_around_decimal_comma() {
+        let rest = "C1000, 00NTRFREF";
+        let (_, _, _, amount, tail) = parse_dc_and_amount(rest, rest).unwrap();
+
+        assert_eq!(amount, "1000,00");
+        assert_eq!(tail, "NTRFREF");
+    }
+
+    // normalize_mt940_amount
+
+    #[test]
+    fn normalize_mt940_amount_removes_spaces_around_comma() {
+        assert_eq!(normalize_mt940_amount("1000, 00").unwrap(), "1000,00");
+        assert_eq!(normalize_mt940_amount(" 1000 ,00 ").unwrap(), "1000,00");
+        assert_eq!(normalize_mt940_amount("100,").unwrap(), "100,");
+        assert_eq!(normalize_mt940_amount("100, 5").unwrap(), "100,5");
+        assert_eq!(normalize_mt940_amount("42").unwrap(), "42");
+    }
+
+    #[test]
+    fn parse_mt940_amount_reads_swift_amounts() {
+        assert_eq!(parse_mt940_amount("123,").unwrap(), 12_300);
+        assert_eq!(parse_mt940_amount("0,05").unwrap(), 5);
+        assert_eq!(parse_mt940_amount("100,5").unwrap(), 10_050);
+        assert_eq!(parse_mt940_amount("1234567,89").unwrap(), 123_456_789);
+        assert_eq!(parse_mt940_amount("42").unwrap(), 4_200);
+        assert!(parse_mt940_amount("1.234,56").is_err());
+        assert!(parse_mt940_amount("99999999999999999999,00").is_err());
+    }
+
+    #[test]
+    fn normalize_mt940_amount_rejects_bad_fraction_and_inner_spaces() {
+        assert!(normalize_mt940_amount("100,123").is_err());
+        assert!(normalize_mt940_amount("1 000,00").is_err());
+        assert!(normalize_mt940_amount("").is_err());
+    }
+
+    #[test]
+    fn parse_swift_headers_input_message() {
+        let header = parse_swift_headers("{1:F01FOOBARBAAXXX0000000000}{2:I940BAZQUXCCA123N}");
+
+        assert_eq!(header.sender_bic.as_deref(), Some("FOOBARBA"));
+        assert_eq!(header.receiver_bic.as_deref(), Some("BAZQUXCC123"));
+    }
+
+    #[test]
+    fn parse_swift_headers_output_message() {
+        let header = parse_swift_headers(
+            "{1:F01FOOBARBAAXXX0000000000}{2:O9401200230102BAZQUXCCAXXX00000000002301021200N}",
+        );
+
+        assert_eq!(header.sender_bic.as_deref(), Some("BAZQUXCC"));
+        assert_eq!(header.receiver_bic.as_deref(), Some("FOOBARBA"));
+    }
+
+    #[test]
+    fn parse_swift_headers_without_app_header_is_empty() {
+        assert_eq!(
+            parse_swift_headers("{1:F01FOOBARBAAXXX0000000000}"),
+            Mt940Header::default()
+        );
+        assert_eq!(parse_swift_headers(""), Mt940Header::default());
+    }
 }
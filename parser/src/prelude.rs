@@ -0,0 +1,27 @@
+//! Часто используемые типы одним импортом
+//!
+//! Методы записи (`write_csv`, `write_camt053`, `write_mt940` и их `*_with`)
+//! реализованы прямо на [`Statement`], отдельный трейт импортировать не нужно.
+//!
+//! ```rust
+//! use parser::prelude::*;
+//!
+//! # fn main() -> Result<(), ParseError> {
+//! let input = "{4:\n:20:REF\n:25:DE89370400440532013000\n:60F:C230101EUR100,00\n\
+//!              :61:2301020102C50,00NTRFREF//BANK\n:62F:C230102EUR150,00\n-}";
+//!
+//! let statement = Statement::try_from(Mt940Data::parse(input.as_bytes())?)?;
+//! assert_eq!(statement.currency, Currency::EUR);
+//! assert_eq!(statement.transactions[0].direction, Direction::Credit);
+//!
+//! let mut csv = Vec::new();
+//! statement.write_csv_with(&mut csv, &CsvWriteOptions::default())?;
+//! #     Ok(())
+//! # }
+//! ```
+
+pub use crate::{
+    Camt053Data, Camt053ParseOptions, Camt053WriteOptions, CsvData, CsvParseOptions,
+    CsvWriteOptions, Currency, Direction, Format, Mt940Data, Mt940ParseOptions, Mt940WriteOptions,
+    Mt942Data, ParseError, ParseWarning, Statement, Transaction,
+};
@@ -1,24 +1,133 @@
 mod camt053_helpers;
 mod common;
 mod csv_helpers;
-use crate::error::ParseError;
+use crate::date::{self, Timestamp};
+use crate::error::{ParseError, ParseWarning};
 use crate::model::{Direction, Statement};
-use chrono::Utc;
 use csv::WriterBuilder;
 use std::io::Write;
 mod mt940_helpers;
 
-use crate::camt053::serde_models::*;
-use quick_xml::se::to_utf8_io_writer;
+use quick_xml::se::{to_string, to_writer_with_root};
+
+/// Настройки записи MT940
+///
+/// По умолчанию пишется только текстовый блок `{4:}`. Если задан BIC,
+/// перед ним добавляются заголовки `{1:}` (отправитель) и `{2:I940...}` (получатель).
+///
+/// С `swift_headers` всегда пишутся блоки `{1:}`/`{2:O940...}`/`{3:}` в виде
+/// выходного сообщения банка; недостающие BIC заменяются плейсхолдером `XXXXXXXX`.
+#[derive(Debug, Clone, Default)]
+pub struct Mt940WriteOptions {
+    /// BIC отправителя (банка, сформировавшего выписку)
+    pub sender_bic: Option<String>,
+    /// BIC получателя
+    pub receiver_bic: Option<String>,
+    /// Писать полный набор заголовков `{1:}`/`{2:O940...}`/`{3:}`
+    pub swift_headers: bool,
+    /// Писать имя владельца счёта ([`Statement::account_name`]) строкой
+    /// `:86:ACCOUNT OWNER: ...` сразу после `:25:`
+    ///
+    /// В MT940 нет отдельного поля для имени счёта; парсер этого крейта
+    /// читает его обратно из такой строки.
+    pub write_account_name: bool,
+}
+
+/// Настройки записи CSV
+///
+/// По умолчанию - разделитель `,` и без BOM.
+#[derive(Debug, Clone)]
+pub struct CsvWriteOptions {
+    /// Разделитель полей (например, `;` для русской локали Excel)
+    pub delimiter: u8,
+    /// Писать ли UTF-8 BOM в начало файла, чтобы Excel правильно определил кодировку
+    pub write_bom: bool,
+    /// Время формирования для шапки; `None` - текущее.
+    /// Фиксированное значение делает вывод воспроизводимым
+    pub created_at: Option<Timestamp>,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        CsvWriteOptions {
+            delimiter: b',',
+            write_bom: false,
+            created_at: None,
+        }
+    }
+}
+
+/// Настройки записи плоского CSV, см. [`Statement::write_simple_csv_with`]
+///
+/// По умолчанию - разделитель `,` и суммы без знака.
+#[derive(Debug, Clone)]
+pub struct SimpleCsvWriteOptions {
+    /// Разделитель полей
+    pub delimiter: u8,
+    /// Писать суммы со знаком: дебет с минусом, как у [`signed_amount`](crate::signed_amount)
+    pub signed_amounts: bool,
+}
+
+impl Default for SimpleCsvWriteOptions {
+    fn default() -> Self {
+        SimpleCsvWriteOptions {
+            delimiter: b',',
+            signed_amounts: false,
+        }
+    }
+}
+
+/// Колонки плоского CSV
+const SIMPLE_CSV_HEADER: [&str; 7] = [
+    "booking_date",
+    "value_date",
+    "direction",
+    "amount",
+    "counterparty",
+    "counterparty_name",
+    "description",
+];
+
+/// Настройки записи CAMT.053
+///
+/// По умолчанию отсутствующие балансы не пишутся.
+#[derive(Debug, Clone, Default)]
+pub struct Camt053WriteOptions {
+    /// Всегда писать `OPBD`/`CLBD`, подставляя `0.00 CRDT` вместо отсутствующего баланса
+    pub always_emit_balances: bool,
+    /// Время для `CreDtTm` и идентификаторов сообщения; `None` - текущее.
+    /// Фиксированное значение делает вывод воспроизводимым
+    pub created_at: Option<Timestamp>,
+}
+
+/// UTF-8 BOM
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
 
 impl Statement {
     /// Записывает выписку в CSV в формате
     pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), ParseError> {
-        let mut wtr = WriterBuilder::new().has_headers(false).from_writer(writer);
+        self.write_csv_with(writer, &CsvWriteOptions::default())
+    }
+
+    /// То же, что [`Statement::write_csv`], но с дополнительными настройками
+    pub fn write_csv_with<W: Write>(
+        &self,
+        mut writer: W,
+        options: &CsvWriteOptions,
+    ) -> Result<(), ParseError> {
+        if options.write_bom {
+            writer.write_all(UTF8_BOM)?;
+        }
+
+        let mut wtr = WriterBuilder::new()
+            .has_headers(false)
+            .delimiter(options.delimiter)
+            .from_writer(writer);
 
         // ---- ШАПКА ----
 
-        csv_helpers::write_header(&mut wtr, self)?;
+        let now = options.created_at.unwrap_or_else(date::now);
+        csv_helpers::write_header(&mut wtr, self, now)?;
 
         // ---- ТАБЛИЦА ОПЕРАЦИЙ ----
 
@@ -49,7 +158,7 @@ impl Statement {
             let mut row = csv_helpers::empty_row();
 
             // Дата проводки
-            row[1] = tx.booking_date.format("%d.%m.%Y").to_string();
+            row[1] = date::format_dd_mm_yyyy(tx.booking_date);
 
             // Блоки дебета/кредита
             let cp_acc = tx.counterparty.clone().unwrap_or_default();
@@ -81,6 +190,11 @@ impl Statement {
                 }
             }
 
+            // Вид операции
+            if let Some(code) = &tx.operation_code {
+                row[16] = code.to_string();
+            }
+
             // Назначение платежа
             row[20] = tx.description.clone();
 
@@ -94,54 +208,182 @@ impl Statement {
         Ok(())
     }
 
+    /// Записывает транзакции в плоский CSV: одна строка на операцию
+    ///
+    /// Колонки: `booking_date,value_date,direction,amount,counterparty,counterparty_name,description`.
+    /// Даты в формате `YYYY-MM-DD`, направление - `debit`/`credit`, сумма - десятичная
+    /// с точкой. Шапки, подвала и остатков нет, поэтому обратно такой файл не читается.
+    pub fn write_simple_csv<W: Write>(&self, writer: W) -> Result<(), ParseError> {
+        self.write_simple_csv_with(writer, &SimpleCsvWriteOptions::default())
+    }
+
+    /// То же, что [`Statement::write_simple_csv`], но с дополнительными настройками
+    pub fn write_simple_csv_with<W: Write>(
+        &self,
+        writer: W,
+        options: &SimpleCsvWriteOptions,
+    ) -> Result<(), ParseError> {
+        let mut wtr = WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .from_writer(writer);
+
+        wtr.write_record(SIMPLE_CSV_HEADER)?;
+
+        for tx in &self.transactions {
+            let direction = match tx.direction {
+                Direction::Debit => "debit",
+                Direction::Credit => "credit",
+            };
+            let sign = if options.signed_amounts && tx.direction == Direction::Debit {
+                "-"
+            } else {
+                ""
+            };
+
+            wtr.write_record([
+                date::format_iso(tx.booking_date),
+                tx.value_date.map(date::format_iso).unwrap_or_default(),
+                direction.to_string(),
+                format!("{sign}{}", common::format_minor_units(tx.amount, '.')),
+                tx.counterparty.clone().unwrap_or_default(),
+                tx.counterparty_name.clone().unwrap_or_default(),
+                tx.description.clone(),
+            ])?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
     /// Записывает выписку в формате CAMT.053 (XML)
+    ///
+    /// Обёртка над [`Statement::write_camt053_streaming`].
     pub fn write_camt053<W: Write>(&self, writer: W) -> Result<(), ParseError> {
-        let now = Utc::now();
-        let ccy_code = camt053_helpers::currency_code(&self.currency);
+        self.write_camt053_streaming(writer)
+    }
 
-        // Собираем Statement
-        let mut stmt = Camt053Statement::default();
+    /// То же, что [`Statement::write_camt053`], но с дополнительными настройками
+    ///
+    /// Возвращает предупреждения записи (например, о неизвестной валюте).
+    pub fn write_camt053_with<W: Write>(
+        &self,
+        writer: W,
+        options: &Camt053WriteOptions,
+    ) -> Result<Vec<ParseWarning>, ParseError> {
+        let now = options.created_at.unwrap_or_else(date::now);
+        self.write_camt053_streaming_at(writer, now, options)
+    }
 
-        stmt.id = Some(format!(
-            "stmt-{}-{}",
-            self.account_id,
-            now.format("%Y%m%d%H%M%S")
-        ));
+    /// Записывает выписку в CAMT.053, сериализуя `<Ntry>` по одной
+    ///
+    /// Полный документ со всеми проводками в памяти не собирается: сначала пишется
+    /// пролог (`GrpHdr`, шапка `Stmt`, балансы), затем проводки, затем закрывающие теги.
+    pub fn write_camt053_streaming<W: Write>(&self, writer: W) -> Result<(), ParseError> {
+        self.write_camt053_streaming_at(writer, date::now(), &Camt053WriteOptions::default())?;
+        Ok(())
+    }
 
-        stmt.sequence_number = Some(1);
-
-        stmt.created_at = Some(now.format("%Y-%m-%dT%H:%M:%S").to_string());
-        stmt.period = Some(Camt053Period {
-            from: Some(camt053_helpers::format_iso_date(self.period_from)),
-            to: Some(camt053_helpers::format_iso_date(self.period_until)),
-        });
-        stmt.account = Camt053Account {
-            id: Camt053AccountId {
-                iban: Some(self.account_id.clone()),
-            },
-            name: self.account_name.clone(),
-            currency: Some(ccy_code.to_string()),
-        };
-        stmt.balances = camt053_helpers::balances_from_statement(self, ccy_code);
-        stmt.entries = camt053_helpers::entries_from_transactions(&self.transactions, ccy_code);
-
-        // Заворачиваем в Document
-        let doc = Camt053Document {
-            bank_to_customer: Camt053BankToCustomer {
-                group_header: Some(Camt053GroupHeader {
-                    message_id: format!("serialized_via_parser-{}", now.format("%Y%m%d%H%M%S")),
-                    created_at: Some(now.format("%Y-%m-%dT%H:%M:%S").to_string()),
-                }),
-                statements: vec![stmt],
-            },
-        };
+    fn write_camt053_streaming_at<W: Write>(
+        &self,
+        mut writer: W,
+        now: Timestamp,
+        options: &Camt053WriteOptions,
+    ) -> Result<Vec<ParseWarning>, ParseError> {
+        let ccy_code = camt053_helpers::currency_code(&self.currency);
+        let warnings: Vec<ParseWarning> =
+            common::unknown_currency_warning(&self.currency, "camt053", ccy_code)
+                .into_iter()
+                .collect();
+
+        // Документ без проводок - из него берём пролог и эпилог
+        let skeleton = to_string(&camt053_helpers::document_from_statement(
+            self,
+            ccy_code,
+            now,
+            Vec::new(),
+            options.always_emit_balances,
+        ))?;
+
+        // Ntry - последнее поле Stmt, так что проводки вставляются прямо перед </Stmt>
+        let split = skeleton.rfind("</Stmt>").ok_or_else(|| {
+            ParseError::BadInput("serialized camt053 document has no </Stmt>".into())
+        })?;
+
+        let (prologue, epilogue) = skeleton.split_at(split);
+        writer.write_all(prologue.as_bytes())?;
+
+        let mut buf = String::new();
+        for tx in &self.transactions {
+            buf.clear();
+            let entry = camt053_helpers::entry_from_transaction(tx, ccy_code);
+            to_writer_with_root(&mut buf, "Ntry", &entry)?;
+            writer.write_all(buf.as_bytes())?;
+        }
 
-        to_utf8_io_writer(writer, &doc)?;
-        Ok(())
+        writer.write_all(epilogue.as_bytes())?;
+        Ok(warnings)
     }
 
     /// Записывает выписку в формате MT940
-    pub fn write_mt940<W: Write>(&self, mut writer: W) -> Result<(), ParseError> {
+    pub fn write_mt940<W: Write>(&self, writer: W) -> Result<(), ParseError> {
+        self.write_mt940_with(writer, &Mt940WriteOptions::default())?;
+        Ok(())
+    }
+
+    /// То же, что [`Statement::write_mt940`], но с дополнительными настройками
+    ///
+    /// Возвращает предупреждения о полях транзакций, которые MT940 не может передать
+    /// (FX, комиссии, коды назначения, сквозные референсы) и которые были отброшены.
+    pub fn write_mt940_with<W: Write>(
+        &self,
+        mut writer: W,
+        options: &Mt940WriteOptions,
+    ) -> Result<Vec<ParseWarning>, ParseError> {
+        let mut warnings = Vec::new();
+
+        // ---- Заголовочные блоки ----
+
+        // BIC проверяем до записи, чтобы не оставить в writer обрывок сообщения
+        let sender_lt = options
+            .sender_bic
+            .as_deref()
+            .map(mt940_helpers::lt_address)
+            .transpose()?;
+        let receiver_lt = options
+            .receiver_bic
+            .as_deref()
+            .map(mt940_helpers::lt_address)
+            .transpose()?;
+
+        if options.swift_headers {
+            let sender_lt = sender_lt
+                .as_deref()
+                .unwrap_or(mt940_helpers::PLACEHOLDER_LT);
+            let receiver_lt = receiver_lt
+                .as_deref()
+                .unwrap_or(mt940_helpers::PLACEHOLDER_LT);
+            let date = mt940_helpers::format_yymmdd(self.period_until);
+
+            // выходное сообщение: в {1:} получатель, отправитель - внутри MIR в {2:}
+            write!(writer, "{{1:F01{receiver_lt}0000000000}}")?;
+            // O940 + время ввода + MIR (дата, LT, session, ISN) + дата/время выдачи + приоритет
+            write!(
+                writer,
+                "{{2:O9400000{date}{sender_lt}0000000000{date}0000N}}"
+            )?;
+            // {3:} - только MUR (108) с тем же плейсхолдером, что и :20:
+            write!(writer, "{{3:{{108:SERIALIZED}}}}")?;
+        } else {
+            if let Some(lt) = sender_lt {
+                // F01 + LT-адрес + session/sequence number (нули)
+                write!(writer, "{{1:F01{lt}0000000000}}")?;
+            }
+            if let Some(lt) = receiver_lt {
+                // I940 + LT-адрес получателя + приоритет N (normal)
+                write!(writer, "{{2:I940{lt}N}}")?;
+            }
+        }
+
         writeln!(writer, "{{4:")?;
 
         // ---- Заголовочные теги ----
@@ -152,12 +394,41 @@ impl Statement {
         // :25: Account Identification - наш счёт
         writeln!(writer, ":25:{}", self.account_id)?;
 
+        // :86: уровня выписки - имя владельца счёта, в ограничениях SWIFT
+        if options.write_account_name
+            && let Some(name) = self.account_name.as_deref().map(str::trim)
+            && !name.is_empty()
+        {
+            let (lines, truncated) = mt940_helpers::wrap_86_text(&format!("ACCOUNT OWNER: {name}"));
+            if truncated {
+                warnings.push(ParseWarning::TruncatedInfo {
+                    index: None,
+                    date: None,
+                    max_lines: mt940_helpers::LINE_86_MAX_LINES,
+                    max_len: mt940_helpers::LINE_86_MAX_LEN,
+                });
+            }
+
+            for (n, line) in lines.iter().enumerate() {
+                if n == 0 {
+                    writeln!(writer, ":86:{line}")?;
+                } else {
+                    writeln!(writer, "{line}")?;
+                }
+            }
+        }
+
         // :28C: Statement Number - плейсхолдер "1/1"
         writeln!(writer, ":28C:1/1")?;
 
         // ---- :60F: Opening Balance ----
 
         let ccy_code = mt940_helpers::currency_code(&self.currency);
+        warnings.extend(common::unknown_currency_warning(
+            &self.currency,
+            "mt940",
+            ccy_code,
+        ));
 
         let opening_minor: i128 = self.opening_balance.unwrap_or(0);
         let (opening_dc, opening_abs) = if opening_minor >= 0 {
@@ -177,12 +448,38 @@ impl Statement {
 
         // ---- :61: / :86: Transactions ----
 
-        for tx in &self.transactions {
+        for (i, tx) in self.transactions.iter().enumerate() {
+            let dropped = mt940_helpers::dropped_fields(tx);
+            if !dropped.is_empty() {
+                warnings.push(ParseWarning::DroppedFields {
+                    index: i,
+                    date: tx.booking_date,
+                    fields: dropped,
+                });
+            }
+
             let line_61 = mt940_helpers::format_61_line(tx);
             writeln!(writer, ":61:{line_61}")?;
 
             if let Some(info) = mt940_helpers::format_86_line(tx) {
-                writeln!(writer, ":86:{info}")?;
+                let (lines, truncated) = mt940_helpers::wrap_86_text(&info);
+                if truncated {
+                    warnings.push(ParseWarning::TruncatedInfo {
+                        index: Some(i),
+                        date: Some(tx.booking_date),
+                        max_lines: mt940_helpers::LINE_86_MAX_LINES,
+                        max_len: mt940_helpers::LINE_86_MAX_LEN,
+                    });
+                }
+
+                // продолжения пишутся без тега, парсер склеивает их через пробел
+                for (n, line) in lines.iter().enumerate() {
+                    if n == 0 {
+                        writeln!(writer, ":86:{line}")?;
+                    } else {
+                        writeln!(writer, "{line}")?;
+                    }
+                }
             }
         }
 
@@ -208,6 +505,362 @@ impl Statement {
         // Закрываем блок 4
         writeln!(writer, "-}}")?;
 
+        Ok(warnings)
+    }
+}
+
+/// Переводит результат записи в память в `String`
+fn utf8_output(buf: Vec<u8>) -> Result<String, ParseError> {
+    String::from_utf8(buf)
+        .map_err(|err| ParseError::BadInput(format!("serialized output is not UTF-8: {err}")))
+}
+
+impl Statement {
+    /// Сериализует выписку в CSV-строку
+    ///
+    /// ```
+    /// use parser::{Currency, Statement, ymd};
+    ///
+    /// let date = ymd(2024, 1, 1).unwrap();
+    /// let statement = Statement::new(
+    ///     "40702810440000030888".into(),
+    ///     Some("ООО Ромашка".into()),
+    ///     Currency::RUB,
+    ///     Some(0),
+    ///     Some(0),
+    ///     Vec::new(),
+    ///     date,
+    ///     date,
+    /// );
+    ///
+    /// let csv = statement.to_csv_string().unwrap();
+    /// assert!(csv.contains("Дата проводки"));
+    /// ```
+    pub fn to_csv_string(&self) -> Result<String, ParseError> {
+        let mut buf = Vec::new();
+        self.write_csv(&mut buf)?;
+        utf8_output(buf)
+    }
+
+    /// Сериализует выписку в CAMT.053 XML-строку
+    ///
+    /// ```
+    /// use parser::{Currency, Statement, ymd};
+    ///
+    /// let date = ymd(2024, 1, 1).unwrap();
+    /// let statement = Statement::new(
+    ///     "DE1111222233334444".into(),
+    ///     None,
+    ///     Currency::EUR,
+    ///     None,
+    ///     None,
+    ///     Vec::new(),
+    ///     date,
+    ///     date,
+    /// );
+    ///
+    /// let xml = statement.to_camt053_string().unwrap();
+    /// assert!(xml.starts_with("<Document>"));
+    /// ```
+    pub fn to_camt053_string(&self) -> Result<String, ParseError> {
+        let mut buf = Vec::new();
+        self.write_camt053(&mut buf)?;
+        utf8_output(buf)
+    }
+
+    /// Сериализует выписку в MT940-строку
+    ///
+    /// ```
+    /// use parser::{Currency, Statement, ymd};
+    ///
+    /// let date = ymd(2024, 1, 1).unwrap();
+    /// let statement = Statement::new(
+    ///     "DE1111222233334444".into(),
+    ///     None,
+    ///     Currency::EUR,
+    ///     Some(100_00),
+    ///     None,
+    ///     Vec::new(),
+    ///     date,
+    ///     date,
+    /// );
+    ///
+    /// let mt940 = statement.to_mt940_string().unwrap();
+    /// assert!(mt940.contains(":25:DE1111222233334444"));
+    /// ```
+    pub fn to_mt940_string(&self) -> Result<String, ParseError> {
+        let mut buf = Vec::new();
+        self.write_mt940(&mut buf)?;
+        utf8_output(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Currency, Transaction};
+    use chrono::NaiveDate;
+    use quick_xml::se::to_utf8_io_writer;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    /// Прежняя реализация: весь документ целиком в памяти
+    fn write_camt053_buffered_at<W: Write>(
+        stmt: &Statement,
+        writer: W,
+        now: Timestamp,
+    ) -> Result<(), ParseError> {
+        let ccy_code = camt053_helpers::currency_code(&stmt.currency);
+        let entries = camt053_helpers::entries_from_transactions(&stmt.transactions, ccy_code);
+        let doc = camt053_helpers::document_from_statement(stmt, ccy_code, now, entries, false);
+
+        to_utf8_io_writer(writer, &doc)?;
         Ok(())
     }
+
+    fn sample_statement(transactions: Vec<Transaction>) -> Statement {
+        Statement::new(
+            "DE1111222233334444".to_string(),
+            Some("Test <Account>".to_string()),
+            Currency::EUR,
+            Some(100_000),
+            Some(-2_500),
+            transactions,
+            d(2023, 1, 1),
+            d(2023, 1, 31),
+        )
+    }
+
+    #[test]
+    fn write_simple_csv_writes_flat_columns() {
+        let mut debit = Transaction::new(
+            d(2023, 1, 10),
+            Some(d(2023, 1, 11)),
+            12_345,
+            Direction::Debit,
+            "Rent, January".to_string(),
+            Some("DE44500105175407324931".to_string()),
+            Some("Bob".to_string()),
+        );
+        debit.reference = Some("E2E".to_string());
+        let credit = Transaction::new(
+            d(2023, 1, 12),
+            None,
+            500,
+            Direction::Credit,
+            "Refund".to_string(),
+            None,
+            None,
+        );
+        let stmt = sample_statement(vec![debit, credit]);
+
+        let mut buf = Vec::new();
+        stmt.write_simple_csv(&mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "booking_date,value_date,direction,amount,counterparty,counterparty_name,description",
+                "2023-01-10,2023-01-11,debit,123.45,DE44500105175407324931,Bob,\"Rent, January\"",
+                "2023-01-12,,credit,5.00,,,Refund",
+            ]
+        );
+
+        let mut buf = Vec::new();
+        let options = SimpleCsvWriteOptions {
+            delimiter: b';',
+            signed_amounts: true,
+        };
+        stmt.write_simple_csv_with(&mut buf, &options).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(
+            text.contains("2023-01-10;2023-01-11;debit;-123.45;"),
+            "{text}"
+        );
+        assert!(text.contains("2023-01-12;;credit;5.00;"), "{text}");
+    }
+
+    #[test]
+    fn write_mt940_with_account_name_emits_statement_86() {
+        let stmt = sample_statement(Vec::new());
+
+        let mut plain = Vec::new();
+        stmt.write_mt940(&mut plain).unwrap();
+        assert!(!String::from_utf8(plain).unwrap().contains("ACCOUNT OWNER"));
+
+        let options = Mt940WriteOptions {
+            write_account_name: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        stmt.write_mt940_with(&mut out, &options).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(
+            text.contains(":25:DE1111222233334444\n:86:ACCOUNT OWNER: Test <Account>\n:28C:"),
+            "{text}"
+        );
+
+        let parsed = Statement::try_from(crate::Mt940Data::try_from(text).unwrap()).unwrap();
+        assert_eq!(parsed.account_name.as_deref(), Some("Test <Account>"));
+    }
+
+    #[test]
+    fn write_mt940_wraps_and_truncates_long_account_name() {
+        let mut stmt = sample_statement(Vec::new());
+        stmt.account_name = Some("Owner ".repeat(80));
+
+        let options = Mt940WriteOptions {
+            write_account_name: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let warnings = stmt.write_mt940_with(&mut out, &options).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(
+            text.lines()
+                .all(|line| line.chars().count() <= mt940_helpers::LINE_86_MAX_LEN),
+            "{text}"
+        );
+        assert!(
+            warnings.iter().any(|w| matches!(
+                w,
+                ParseWarning::TruncatedInfo {
+                    index: None,
+                    date: None,
+                    ..
+                }
+            )),
+            "{warnings:?}"
+        );
+    }
+
+    #[test]
+    fn injected_timestamp_makes_output_reproducible() {
+        use chrono::TimeZone;
+
+        let stmt = sample_statement(vec![Transaction::new(
+            d(2023, 1, 5),
+            None,
+            1_000,
+            Direction::Credit,
+            "payment".to_string(),
+            None,
+            None,
+        )]);
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 7, 9, 5, 1).unwrap();
+
+        let camt_options = Camt053WriteOptions {
+            created_at: Some(now),
+            ..Default::default()
+        };
+        let write_camt = || {
+            let mut buf = Vec::new();
+            stmt.write_camt053_with(&mut buf, &camt_options).unwrap();
+            buf
+        };
+        let camt = write_camt();
+        assert_eq!(camt, write_camt());
+        let camt = String::from_utf8(camt).unwrap();
+        assert!(
+            camt.contains("<CreDtTm>2024-03-07T09:05:01</CreDtTm>"),
+            "{camt}"
+        );
+
+        let csv_options = CsvWriteOptions {
+            created_at: Some(now),
+            ..Default::default()
+        };
+        let write_csv = || {
+            let mut buf = Vec::new();
+            stmt.write_csv_with(&mut buf, &csv_options).unwrap();
+            buf
+        };
+        let csv = write_csv();
+        assert_eq!(csv, write_csv());
+        assert!(String::from_utf8(csv).unwrap().contains("07.03.2024"));
+    }
+
+    #[test]
+    fn write_camt053_streaming_matches_buffered_output() {
+        let transactions = (0..50)
+            .map(|i| {
+                Transaction::new(
+                    d(2023, 1, 1 + (i % 28)),
+                    None,
+                    1_000 + i as u64,
+                    if i % 2 == 0 {
+                        Direction::Credit
+                    } else {
+                        Direction::Debit
+                    },
+                    format!("payment #{i}"),
+                    Some(format!("ACC{i}")),
+                    Some("Counterparty & Co".to_string()),
+                )
+            })
+            .collect();
+        let stmt = sample_statement(transactions);
+        let now = date::now();
+
+        let mut streamed = Vec::new();
+        stmt.write_camt053_streaming_at(&mut streamed, now, &Camt053WriteOptions::default())
+            .unwrap();
+
+        let mut buffered = Vec::new();
+        write_camt053_buffered_at(&stmt, &mut buffered, now).unwrap();
+
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            String::from_utf8(buffered).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_camt053_streaming_matches_buffered_output_without_entries() {
+        let stmt = sample_statement(Vec::new());
+        let now = date::now();
+
+        let mut streamed = Vec::new();
+        stmt.write_camt053_streaming_at(&mut streamed, now, &Camt053WriteOptions::default())
+            .unwrap();
+
+        let mut buffered = Vec::new();
+        write_camt053_buffered_at(&stmt, &mut buffered, now).unwrap();
+
+        assert_eq!(streamed, buffered);
+    }
+
+    #[test]
+    fn writers_warn_about_unknown_currency_placeholder() {
+        let mut stmt = sample_statement(Vec::new());
+        stmt.currency = Currency::Other("XAU".to_string());
+
+        let warnings = stmt
+            .write_mt940_with(Vec::new(), &Mt940WriteOptions::default())
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::UnknownCurrency {
+                currency: "XAU".to_string(),
+                format: "mt940",
+                placeholder: "XXX",
+            }]
+        );
+
+        let warnings = stmt
+            .write_camt053_with(Vec::new(), &Camt053WriteOptions::default())
+            .unwrap();
+        assert!(matches!(
+            warnings.as_slice(),
+            [ParseWarning::UnknownCurrency {
+                placeholder: "???",
+                ..
+            }]
+        ));
+    }
 }
@@ -1,7 +1,7 @@
 use super::common;
 
+use crate::date::{self, Date, Timestamp};
 use crate::model::{Balance, Currency, Direction, Statement, Transaction};
-use chrono::NaiveDate;
 
 use crate::camt053::serde_models::*;
 
@@ -12,28 +12,31 @@ pub(super) fn currency_code(cur: &Currency) -> &'static str {
         Currency::EUR => "EUR",
         Currency::USD => "USD",
         Currency::CNY => "CNY",
-        Currency::Other(c) => {
-            println!(
-                "found unknown currency {c} while converting to camt053. using placeholder '???'"
-            );
-            "???"
-        }
+        Currency::Other(_) => "???",
     }
 }
 
-pub(super) fn format_iso_date(d: NaiveDate) -> String {
-    d.format("%Y-%m-%d").to_string()
+pub(super) fn format_iso_date(d: Date) -> String {
+    date::format_iso(d)
 }
 
 /// Балансы (OPBD / CLBD)
-pub(super) fn balances_from_statement(stmt: &Statement, ccy_code: &str) -> Vec<Camt053Balance> {
+///
+/// При `always_emit` отсутствующий баланс пишется как `0.00 CRDT`
+pub(super) fn balances_from_statement(
+    stmt: &Statement,
+    ccy_code: &str,
+    always_emit: bool,
+) -> Vec<Camt053Balance> {
     let mut result = Vec::new();
 
-    if let Some(open) = stmt.opening_balance {
+    let fallback = always_emit.then_some(0);
+
+    if let Some(open) = stmt.opening_balance.or(fallback) {
         result.push(make_balance("OPBD", open, ccy_code));
     }
 
-    if let Some(close) = stmt.closing_balance {
+    if let Some(close) = stmt.closing_balance.or(fallback) {
         result.push(make_balance("CLBD", close, ccy_code));
     }
 
@@ -62,7 +65,54 @@ fn make_balance(code: &str, value: Balance, ccy_code: &str) -> Camt053Balance {
     }
 }
 
+/// Собирает документ CAMT.053 с одним Stmt и переданными проводками
+pub(super) fn document_from_statement(
+    stmt: &Statement,
+    ccy_code: &str,
+    now: Timestamp,
+    entries: Vec<Camt053Entry>,
+    always_emit_balances: bool,
+) -> Camt053Document {
+    let camt_stmt = Camt053Statement {
+        id: Some(format!(
+            "stmt-{}-{}",
+            stmt.account_id,
+            date::format_timestamp_compact(now)
+        )),
+        sequence_number: Some(1),
+        created_at: Some(date::format_timestamp_iso(now)),
+        period: Some(Camt053Period {
+            from: Some(format_iso_date(stmt.period_from)),
+            to: Some(format_iso_date(stmt.period_until)),
+        }),
+        account: Camt053Account {
+            id: Camt053AccountId {
+                iban: Some(stmt.account_id.clone()),
+                other_id: None,
+            },
+            name: stmt.account_name.clone(),
+            currency: Some(ccy_code.to_string()),
+        },
+        balances: balances_from_statement(stmt, ccy_code, always_emit_balances),
+        entries,
+    };
+
+    Camt053Document {
+        bank_to_customer: Camt053BankToCustomer {
+            group_header: Some(Camt053GroupHeader {
+                message_id: format!(
+                    "serialized_via_parser-{}",
+                    date::format_timestamp_compact(now)
+                ),
+                created_at: Some(date::format_timestamp_iso(now)),
+            }),
+            statements: vec![camt_stmt],
+        },
+    }
+}
+
 ///  Преобразует транзакции в Ntry
+#[cfg(test)]
 pub(super) fn entries_from_transactions(txs: &[Transaction], ccy_code: &str) -> Vec<Camt053Entry> {
     txs.iter()
         .map(|tx| entry_from_transaction(tx, ccy_code))
@@ -96,18 +146,29 @@ pub(super) fn entry_from_transaction(tx: &Transaction, ccy_code: &str) -> Camt05
         })
     };
 
-    // RltdPties - контрагент: учитываем и счёт, и имя
+    // RltdPties - контрагент: учитываем счёт, имя и адрес
     let related_parties = {
         let cp_acc = tx.counterparty.as_ref();
         let cp_name = tx.counterparty_name.clone();
+        let cp_address = tx
+            .counterparty_address
+            .as_ref()
+            .filter(|address| !address.is_empty())
+            .map(|address| CamtPostalAddress {
+                street: address.street.clone(),
+                postcode: address.postcode.clone(),
+                town: address.town.clone(),
+                country: address.country.clone(),
+                lines: address.lines.clone(),
+            });
 
-        // если нет ни счёта, ни имени - не пишем RltdPties вообще
-        if cp_acc.is_none() && cp_name.is_none() {
+        // если нет ни счёта, ни имени, ни адреса - не пишем RltdPties вообще
+        if cp_acc.is_none() && cp_name.is_none() && cp_address.is_none() {
             None
         } else {
             let party = CamtParty {
                 name: cp_name,
-                postal_address: None,
+                postal_address: cp_address,
                 id: None,
             };
 
@@ -142,8 +203,11 @@ pub(super) fn entry_from_transaction(tx: &Transaction, ccy_code: &str) -> Camt05
 
     let tx_dtls = CamtTxDtls {
         refs: None,
+        cdt_dbt_ind: None,
         amount_details: None,
+        charges: None,
         related_parties,
+        purpose: None,
         rmt_inf,
         related_datetimes: None,
     };
@@ -153,18 +217,31 @@ pub(super) fn entry_from_transaction(tx: &Transaction, ccy_code: &str) -> Camt05
     };
 
     Camt053Entry {
+        entry_reference: None,
         amount: CamtAmtXml {
-            currency: ccy_code.to_string(),
+            currency: tx
+                .original_currency
+                .as_ref()
+                .map_or(ccy_code, |c| c.code())
+                .to_string(),
             value: amount_str,
         },
         cdt_dbt_ind,
+        reversal_indicator: tx.is_reversal.then_some(true),
+        status: Some(CamtEntryStatus {
+            text: Some(if tx.pending { "PDNG" } else { "BOOK" }.to_string()),
+            code: None,
+        }),
         booking_date,
-        value_date,
+        value_date: Some(value_date),
+        account_servicer_reference: tx.bank_reference.clone(),
         details: Some(details),
     }
 }
 
+// суммы в тестах записаны как 100_00 - "рубли_копейки"
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod tests {
     use super::*;
     use crate::model::{Currency, Direction, Statement, Transaction};
@@ -210,7 +287,7 @@ mod tests {
             d(2023, 1, 31),
         );
 
-        let balances = balances_from_statement(&stmt, "EUR");
+        let balances = balances_from_statement(&stmt, "EUR", false);
         assert_eq!(balances.len(), 2);
 
         let opbd = &balances[0];
@@ -245,10 +322,31 @@ mod tests {
             d(2023, 1, 31),
         );
 
-        let balances = balances_from_statement(&stmt, "EUR");
+        let balances = balances_from_statement(&stmt, "EUR", false);
         assert!(balances.is_empty());
     }
 
+    #[test]
+    fn balances_from_statement_always_emit_writes_zero_for_absent() {
+        let stmt = Statement::new(
+            "ACC".to_string(),
+            None,
+            Currency::EUR,
+            None,
+            Some(-50_00),
+            Vec::new(),
+            d(2023, 1, 1),
+            d(2023, 1, 31),
+        );
+
+        let balances = balances_from_statement(&stmt, "EUR", true);
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].amount.value, "0.00");
+        assert_eq!(balances[0].cdt_dbt_ind.as_deref(), Some("CRDT"));
+        assert_eq!(balances[1].amount.value, "50.00");
+        assert_eq!(balances[1].cdt_dbt_ind.as_deref(), Some("DBIT"));
+    }
+
     #[test]
     fn entry_from_transaction_credit_with_description() {
         let tx = Transaction::new(
@@ -269,7 +367,7 @@ mod tests {
 
         assert_eq!(entry.booking_date.date, "2023-04-19");
         // value_date = booking_date, т.к. value_date == None
-        assert_eq!(entry.value_date.date, "2023-04-19");
+        assert_eq!(entry.value_date.unwrap().date, "2023-04-19");
 
         // проверяем, что описание попало в RmtInf/Ustrd
         let details = entry.details.expect("details must be present");
@@ -299,7 +397,7 @@ mod tests {
         assert_eq!(entry.cdt_dbt_ind, "DBIT");
 
         assert_eq!(entry.booking_date.date, "2023-04-20");
-        assert_eq!(entry.value_date.date, "2023-04-21");
+        assert_eq!(entry.value_date.unwrap().date, "2023-04-21");
 
         // если description пустой, RmtInf не создаётся
         let details = entry.details.expect("details must be present");
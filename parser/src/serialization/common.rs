@@ -1,14 +1,29 @@
+use crate::error::ParseWarning;
+use crate::model::Currency;
+use crate::utils::MINOR_UNIT_DECIMALS;
+
+/// Предупреждение о том, что вместо кода валюты записан плейсхолдер
+pub(super) fn unknown_currency_warning(
+    currency: &Currency,
+    format: &'static str,
+    placeholder: &'static str,
+) -> Option<ParseWarning> {
+    match currency {
+        Currency::Other(c) => Some(ParseWarning::UnknownCurrency {
+            currency: c.clone(),
+            format,
+            placeholder,
+        }),
+        _ => None,
+    }
+}
+
 /// Форматирует целочисленное значение баланса (копейки) в человекочитаемый формат
 pub(super) fn format_minor_units<T>(value: T, decimal_separator: char) -> String
 where
     T: Into<i128>,
 {
-    let v: i128 = value.into();
-    let v = v.unsigned_abs();
-    let units = v / 100;
-    let frac = v % 100;
-
-    format!("{units}{decimal_separator}{frac:02}")
+    crate::money::format_minor_units(value, MINOR_UNIT_DECIMALS, decimal_separator)
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
 use super::common;
+use crate::date::{self, Date, Timestamp};
 use crate::error::ParseError;
 use crate::model::{Balance, Currency, Direction, Statement};
-use chrono::{Datelike, Utc};
 use csv::Writer;
 use std::io::Write;
 
@@ -11,10 +11,10 @@ pub(super) fn empty_row() -> Vec<String> {
     vec![String::new(); COLS]
 }
 
-pub(super) fn format_rus_date(d: chrono::NaiveDate) -> String {
-    let day = d.day();
-    let year = d.year();
-    let month = match d.month() {
+pub(super) fn format_rus_date(d: Date) -> String {
+    let day = date::day(d);
+    let year = date::year(d);
+    let month = match date::month(d) {
         1 => "января",
         2 => "февраля",
         3 => "марта",
@@ -56,11 +56,10 @@ pub(super) fn make_party_block(account: &str, name: &str) -> String {
 pub(super) fn write_header<W: Write>(
     wtr: &mut Writer<W>,
     stmt: &Statement,
+    now: Timestamp,
 ) -> Result<(), ParseError> {
-    let now = Utc::now();
-
     let mut row0 = empty_row();
-    row0[1] = now.format("%d.%m.%Y").to_string();
+    row0[1] = date::format_dd_mm_yyyy(date::timestamp_date(now));
     wtr.write_record(&row0)?;
 
     let mut row1 = empty_row();
@@ -74,8 +73,8 @@ pub(super) fn write_header<W: Write>(
     let mut row3 = empty_row();
     row3[1] = format!(
         "Дата формирования выписки {} в {}",
-        now.format("%d.%m.%Y"),
-        now.format("%H:%M:%S"),
+        date::format_dd_mm_yyyy(date::timestamp_date(now)),
+        date::format_time_hms(now),
     );
     wtr.write_record(&row3)?;
 
@@ -205,7 +204,9 @@ pub(super) fn write_footer<W: Write>(
     Ok(())
 }
 
+// суммы в тестах записаны как 100_00 - "рубли_копейки"
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod tests {
     use super::*;
     use crate::model::{Currency, Direction, Statement, Transaction};
@@ -319,7 +320,7 @@ mod tests {
         let mut buffer: Vec<u8> = Vec::new();
         {
             let mut wtr = Writer::from_writer(&mut buffer);
-            write_header(&mut wtr, &stmt).unwrap();
+            write_header(&mut wtr, &stmt, date::now()).unwrap();
             wtr.flush().unwrap();
         }
 
@@ -1,6 +1,7 @@
 use super::common;
+use crate::date::{self, Date};
+use crate::error::ParseError;
 use crate::model::{Currency, Direction, Transaction};
-use chrono::NaiveDate;
 
 /// Преобразует Currency в 3-буквенный код для MT940
 pub(super) fn currency_code(cur: &Currency) -> &'static str {
@@ -9,18 +10,57 @@ pub(super) fn currency_code(cur: &Currency) -> &'static str {
         Currency::EUR => "EUR",
         Currency::USD => "USD",
         Currency::CNY => "CNY",
-        Currency::Other(c) => {
-            println!(
-                "found unknown currency {c} while converting to mt940. using placeholder 'XXX'"
-            );
-            "XXX"
-        }
+        Currency::Other(_) => "XXX",
+    }
+}
+
+/// LT-адрес для заголовков, когда BIC не задан
+pub(super) const PLACEHOLDER_LT: &str = "XXXXXXXXAXXX";
+
+/// Формирует 12-символьный LT-адрес из BIC: BIC8 + код терминала `A` + филиал
+/// (`XXX` для головного офиса, если BIC 8-символьный)
+pub(super) fn lt_address(bic: &str) -> Result<String, ParseError> {
+    let bic = bic.trim().to_ascii_uppercase();
+    if !matches!(bic.len(), 8 | 11) || !bic.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ParseError::BadInput(format!("invalid BIC: {bic}")));
     }
+
+    let branch = bic.get(8..).filter(|b| !b.is_empty()).unwrap_or("XXX");
+    Ok(format!("{}A{branch}", &bic[..8]))
+}
+
+/// Возвращает названия полей транзакции, которые нельзя передать в MT940 и которые будут потеряны
+pub(super) fn dropped_fields(tx: &Transaction) -> Vec<&'static str> {
+    let mut dropped = Vec::new();
+
+    if tx.fx.is_some() {
+        dropped.push("FX details");
+    }
+    if tx.charges.is_some() {
+        dropped.push("charges");
+    }
+    if tx.purpose_code.is_some() {
+        dropped.push("purpose code");
+    }
+    if tx.reference.is_some() {
+        dropped.push("reference");
+    }
+    if tx.operation_code.is_some() {
+        dropped.push("operation code");
+    }
+    if tx.pending {
+        dropped.push("pending status");
+    }
+    if tx.original_currency.is_some() {
+        dropped.push("original currency");
+    }
+
+    dropped
 }
 
 /// Форматируем дату как YYMMDD для MT940
-pub(super) fn format_yymmdd(date: NaiveDate) -> String {
-    date.format("%y%m%d").to_string()
+pub(super) fn format_yymmdd(d: Date) -> String {
+    date::format_yymmdd(d)
 }
 
 /// Форматируем одну строку :61: из Transaction
@@ -30,18 +70,27 @@ pub(super) fn format_61_line(tx: &Transaction) -> String {
     let value_part = format_yymmdd(value_date);
 
     // entry_date: MMDD из booking_date
-    let entry_part = tx.booking_date.format("%m%d").to_string();
-
-    // D / C
-    let dc_mark = match tx.direction {
-        Direction::Debit => 'D',
-        Direction::Credit => 'C',
+    let entry_part = date::format_mmdd(tx.booking_date);
+
+    // D / C, для сторно - SWIFT-пара RC (сторно кредита, дебет) / RD (сторно дебета, кредит)
+    let dc_mark = match (tx.direction, tx.is_reversal) {
+        (Direction::Debit, false) => "D",
+        (Direction::Credit, false) => "C",
+        (Direction::Debit, true) => "RC",
+        (Direction::Credit, true) => "RD",
     };
 
     // Сумма в формате "1234,56" (с разделителем ',')
     let amount_str = common::format_minor_units(tx.amount, ',');
 
-    format!("{value_part}{entry_part}{dc_mark}{amount_str}")
+    // bank reference - после "//", как его читает парсер
+    let bank_part = tx
+        .bank_reference
+        .as_deref()
+        .map(|bank| format!("//{bank}"))
+        .unwrap_or_default();
+
+    format!("{value_part}{entry_part}{dc_mark}{amount_str}{bank_part}")
 }
 
 /// Формирует строку :86: на основе контрагента и описания.
@@ -76,6 +125,67 @@ pub(super) fn format_86_line(tx: &Transaction) -> Option<String> {
     if base.is_empty() { None } else { Some(base) }
 }
 
+/// Максимальная длина строки поля :86: по правилам SWIFT
+pub(super) const LINE_86_MAX_LEN: usize = 65;
+
+/// Максимальное число строк поля :86: по правилам SWIFT
+pub(super) const LINE_86_MAX_LINES: usize = 6;
+
+/// Разбивает текст :86: на строки не длиннее 65 символов, не больше 6 штук
+///
+/// Первая строка укорочена на длину тега `:86:`, чтобы и физическая строка
+/// влезала в 65 символов. Переносим по пробелам, слишком длинные слова режем.
+/// Второе значение - `true`, если текст не поместился и был обрезан.
+pub(super) fn wrap_86_text(text: &str) -> (Vec<String>, bool) {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0usize;
+
+    let limit = |lines: &Vec<String>| {
+        if lines.is_empty() {
+            LINE_86_MAX_LEN - ":86:".len()
+        } else {
+            LINE_86_MAX_LEN
+        }
+    };
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        while !word.is_empty() {
+            let max = limit(&lines);
+            let sep = usize::from(current_len > 0);
+
+            if current_len + sep + word.len() <= max {
+                if sep == 1 {
+                    current.push(' ');
+                }
+                current.extend(word.iter());
+                current_len += sep + word.len();
+                break;
+            }
+
+            if current_len == 0 {
+                // слово длиннее целой строки - режем
+                current.extend(word.drain(..max));
+            }
+
+            lines.push(std::mem::take(&mut current));
+            current_len = 0;
+
+            if lines.len() == LINE_86_MAX_LINES {
+                return (lines, true);
+            }
+        }
+    }
+
+    if current_len > 0 {
+        lines.push(current);
+    }
+
+    (lines, false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +210,34 @@ mod tests {
         assert_eq!(currency_code(&cur), "XXX");
     }
 
+    #[test]
+    fn lt_address_pads_bic8_and_keeps_branch_of_bic11() {
+        assert_eq!(lt_address("foobarba").unwrap(), "FOOBARBAAXXX");
+        assert_eq!(lt_address("BAZQUXCC123").unwrap(), "BAZQUXCCA123");
+        assert!(matches!(
+            lt_address("TOOSHORT1"),
+            Err(ParseError::BadInput(_))
+        ));
+    }
+
+    #[test]
+    fn dropped_fields_lists_non_representable_fields() {
+        let mut t = Transaction::new(
+            d(2023, 1, 1),
+            None,
+            100,
+            Direction::Credit,
+            String::new(),
+            None,
+            None,
+        );
+        assert!(dropped_fields(&t).is_empty());
+
+        t.charges = Some(150);
+        t.reference = Some("E2E".to_string());
+        assert_eq!(dropped_fields(&t), vec!["charges", "reference"]);
+    }
+
     #[test]
     fn format_yymmdd_formats_correctly() {
         assert_eq!(format_yymmdd(d(2023, 4, 19)), "230419");
@@ -169,6 +307,25 @@ mod tests {
         assert_ne!(line_c, line_d);
     }
 
+    #[test]
+    fn format_61_line_writes_swift_reversal_marks() {
+        let mut debit = tx(d(2023, 1, 1), None, 100, Direction::Debit, "", None, None);
+        debit.is_reversal = true;
+        let mut credit = tx(d(2023, 1, 1), None, 100, Direction::Credit, "", None, None);
+        credit.is_reversal = true;
+
+        assert_eq!(format_61_line(&debit), "2301010101RC1,00");
+        assert_eq!(format_61_line(&credit), "2301010101RD1,00");
+    }
+
+    #[test]
+    fn format_61_line_writes_bank_reference() {
+        let mut t = tx(d(2023, 1, 1), None, 100, Direction::Credit, "", None, None);
+        t.bank_reference = Some("BANKREF".to_string());
+
+        assert_eq!(format_61_line(&t), "2301010101C1,00//BANKREF");
+    }
+
     #[test]
     fn format_86_line_returns_none_when_all_empty() {
         let t = tx(d(2023, 1, 1), None, 100, Direction::Credit, "", None, None);
@@ -242,4 +399,45 @@ mod tests {
         // пустой account (после trim) должен игнорироваться
         assert_eq!(format_86_line(&t), Some("Name // Desc".to_string()));
     }
+
+    #[test]
+    fn wrap_86_text_keeps_short_text_on_one_line() {
+        assert_eq!(
+            wrap_86_text("Invoice 123"),
+            (vec!["Invoice 123".to_string()], false)
+        );
+        assert_eq!(wrap_86_text("   "), (Vec::<String>::new(), false));
+    }
+
+    #[test]
+    fn wrap_86_text_wraps_on_whitespace_within_limits() {
+        let text = "word ".repeat(40);
+        let (lines, truncated) = wrap_86_text(&text);
+
+        assert!(!truncated);
+        assert!(lines.len() > 1);
+        assert!(lines[0].chars().count() <= LINE_86_MAX_LEN - 4);
+        assert!(lines.iter().all(|l| l.chars().count() <= LINE_86_MAX_LEN));
+        assert_eq!(lines.join(" "), text.trim());
+    }
+
+    #[test]
+    fn wrap_86_text_splits_long_words_and_counts_chars() {
+        let word = "ж".repeat(100);
+        let (lines, truncated) = wrap_86_text(&word);
+
+        assert!(!truncated);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].chars().count(), LINE_86_MAX_LEN - 4);
+        assert_eq!(lines.concat(), word);
+    }
+
+    #[test]
+    fn wrap_86_text_truncates_after_six_lines() {
+        let text = "x".repeat(LINE_86_MAX_LEN * 10);
+        let (lines, truncated) = wrap_86_text(&text);
+
+        assert!(truncated);
+        assert_eq!(lines.len(), LINE_86_MAX_LINES);
+    }
 }
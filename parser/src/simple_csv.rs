@@ -0,0 +1,266 @@
+//! Чтение плоского CSV, который пишет [`Statement::write_simple_csv`]
+
+use crate::date::{self, Date};
+use crate::error::ParseError;
+use crate::model::{Currency, Direction, SourceFormat, Statement, Transaction};
+use crate::money::parse_amount;
+use crate::utils::{MINOR_UNIT_DECIMALS, RoundingMode, excerpt_around, strip_utf8_bom};
+use csv::{ReaderBuilder, StringRecord};
+use std::io::Read;
+
+/// Настройки парсинга плоского CSV
+///
+/// В файле нет ни счёта, ни валюты, ни остатков: счёт и валюта выписки
+/// берутся отсюда, остатки остаются `None`.
+#[derive(Debug, Clone)]
+pub struct SimpleCsvParseOptions {
+    /// Разделитель полей
+    pub delimiter: u8,
+    /// Счёт выписки
+    pub account_id: String,
+    /// Валюта выписки
+    pub currency: Currency,
+}
+
+impl Default for SimpleCsvParseOptions {
+    fn default() -> Self {
+        SimpleCsvParseOptions {
+            delimiter: b',',
+            account_id: String::new(),
+            currency: Currency::RUB,
+        }
+    }
+}
+
+/// Разобранный плоский CSV
+///
+/// Колонки ищутся по именам из заголовка, порядок не важен. Обязательны
+/// `booking_date` и `amount`; без колонки `direction` направление берётся
+/// из знака суммы (минус - дебет).
+///
+/// ```rust
+/// use parser::{Direction, SimpleCsvData, Statement};
+///
+/// # fn main() -> Result<(), parser::ParseError> {
+/// let input = "booking_date,direction,amount,description\n2024-01-05,debit,123.45,Rent\n";
+/// let statement = Statement::try_from(SimpleCsvData::parse(input.as_bytes())?)?;
+///
+/// assert_eq!(statement.transactions[0].amount, 12345);
+/// assert_eq!(statement.transactions[0].direction, Direction::Debit);
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SimpleCsvData {
+    account_id: String,
+    currency: Currency,
+    transactions: Vec<Transaction>,
+}
+
+/// Индексы колонок плоского CSV
+struct Columns {
+    booking_date: usize,
+    value_date: Option<usize>,
+    direction: Option<usize>,
+    amount: usize,
+    counterparty: Option<usize>,
+    counterparty_name: Option<usize>,
+    description: Option<usize>,
+}
+
+impl Columns {
+    fn from_header(header: &StringRecord) -> Result<Self, ParseError> {
+        let find = |name: &str| header.iter().position(|h| h.trim() == name);
+        let require = |name: &str| {
+            find(name).ok_or_else(|| ParseError::Header(format!("simple CSV: no '{name}' column")))
+        };
+
+        Ok(Columns {
+            booking_date: require("booking_date")?,
+            value_date: find("value_date"),
+            direction: find("direction"),
+            amount: require("amount")?,
+            counterparty: find("counterparty"),
+            counterparty_name: find("counterparty_name"),
+            description: find("description"),
+        })
+    }
+
+    fn transaction(&self, row: &StringRecord) -> Result<Transaction, ParseError> {
+        let field = |idx: Option<usize>| {
+            idx.and_then(|idx| row.get(idx))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let booking_date = field(Some(self.booking_date))
+            .ok_or(ParseError::MissingField("booking_date"))
+            .and_then(date::parse_iso_date)?;
+        let value_date = field(self.value_date)
+            .map(date::parse_iso_date)
+            .transpose()?;
+
+        let raw_amount = field(Some(self.amount)).ok_or(ParseError::MissingField("amount"))?;
+        let (negative, raw_amount) = match raw_amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, raw_amount),
+        };
+        let amount = parse_amount(raw_amount, MINOR_UNIT_DECIMALS, RoundingMode::Error)?;
+
+        let direction = match field(self.direction) {
+            Some(raw) => {
+                let direction: Direction = raw.parse()?;
+                if negative && direction == Direction::Credit {
+                    return Err(ParseError::InvalidAmount(format!(
+                        "negative credit amount: -{raw_amount}"
+                    )));
+                }
+                direction
+            }
+            None if negative => Direction::Debit,
+            None => Direction::Credit,
+        };
+
+        Ok(Transaction::new(
+            booking_date,
+            value_date,
+            amount,
+            direction,
+            field(self.description).unwrap_or_default().to_string(),
+            field(self.counterparty).map(str::to_string),
+            field(self.counterparty_name).map(str::to_string),
+        ))
+    }
+}
+
+impl SimpleCsvData {
+    /// Парсит плоский CSV с настройками по умолчанию
+    pub fn parse<R: Read>(reader: R) -> Result<Self, ParseError> {
+        Self::parse_with_options(reader, &SimpleCsvParseOptions::default())
+    }
+
+    /// То же, что [`SimpleCsvData::parse`], но с дополнительными настройками
+    pub fn parse_with_options<R: Read>(
+        mut reader: R,
+        options: &SimpleCsvParseOptions,
+    ) -> Result<Self, ParseError> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input)?;
+        let input = strip_utf8_bom(&input);
+
+        let mut rdr = ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .from_reader(input);
+        let columns = Columns::from_header(rdr.headers()?)?;
+
+        // исходные строки файла нужны только для отрывков в ошибках
+        let text = String::from_utf8_lossy(input);
+        let lines: Vec<&str> = text.lines().collect();
+
+        let mut transactions = Vec::new();
+        for record in rdr.records() {
+            let record = record?;
+            let tx = columns.transaction(&record).map_err(|err| {
+                match record.position().map(|pos| pos.line() as usize) {
+                    Some(line) => {
+                        let numbered = lines.iter().enumerate().map(|(i, l)| (i + 1, *l));
+                        err.at_line(line, excerpt_around(numbered, line..=line))
+                    }
+                    None => err,
+                }
+            })?;
+            transactions.push(tx);
+        }
+
+        Ok(SimpleCsvData {
+            account_id: options.account_id.clone(),
+            currency: options.currency.clone(),
+            transactions,
+        })
+    }
+}
+
+impl TryFrom<SimpleCsvData> for Statement {
+    type Error = ParseError;
+
+    /// Период выписки - от самой ранней до самой поздней даты проводки
+    fn try_from(data: SimpleCsvData) -> Result<Self, Self::Error> {
+        let dates = || data.transactions.iter().map(|tx| tx.booking_date);
+        let (Some(period_from), Some(period_until)) = (dates().min(), dates().max()) else {
+            return Err(ParseError::BadInput(
+                "simple CSV has no transactions to derive the statement period from".into(),
+            ));
+        };
+        let (period_from, period_until): (Date, Date) = (period_from, period_until);
+
+        let mut statement = Statement::new(
+            data.account_id,
+            None,
+            data.currency,
+            None,
+            None,
+            data.transactions,
+            period_from,
+            period_until,
+        );
+        statement.source_format = Some(SourceFormat::SimpleCsv);
+
+        Ok(statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Statement, ParseError> {
+        Statement::try_from(SimpleCsvData::parse(input.as_bytes())?)
+    }
+
+    #[test]
+    fn columns_are_found_by_name() {
+        let stmt = parse(
+            "description,amount,booking_date,direction\n\
+             Rent,\"1,234.50\",2024-01-05,debit\n\
+             Salary,100,2024-01-07,CREDIT\n",
+        )
+        .unwrap();
+
+        assert_eq!(stmt.transactions.len(), 2);
+        assert_eq!(stmt.transactions[0].amount, 123_450);
+        assert_eq!(stmt.transactions[0].description, "Rent");
+        assert_eq!(stmt.transactions[1].direction, Direction::Credit);
+        assert_eq!(stmt.period_from, date::ymd(2024, 1, 5).unwrap());
+        assert_eq!(stmt.period_until, date::ymd(2024, 1, 7).unwrap());
+        assert_eq!(stmt.source_format, Some(SourceFormat::SimpleCsv));
+    }
+
+    #[test]
+    fn sign_gives_direction_without_direction_column() {
+        let stmt = parse("booking_date,amount\n2024-01-05,-5.00\n2024-01-06,7.00\n").unwrap();
+
+        assert_eq!(stmt.transactions[0].direction, Direction::Debit);
+        assert_eq!(stmt.transactions[0].amount, 500);
+        assert_eq!(stmt.transactions[1].direction, Direction::Credit);
+    }
+
+    #[test]
+    fn negative_credit_is_error() {
+        let err = parse("booking_date,direction,amount\n2024-01-05,credit,-5.00\n").unwrap_err();
+
+        assert_eq!(err.line(), Some(2));
+        assert!(matches!(err.root(), ParseError::InvalidAmount(_)));
+    }
+
+    #[test]
+    fn missing_required_column_is_header_error() {
+        let err = parse("booking_date,direction\n2024-01-05,credit\n").unwrap_err();
+        assert!(matches!(err, ParseError::Header(_)));
+    }
+
+    #[test]
+    fn empty_file_has_no_period() {
+        let err = parse("booking_date,amount\n").unwrap_err();
+        assert!(matches!(err, ParseError::BadInput(_)));
+    }
+}
@@ -1,83 +1,218 @@
+use crate::date::Date;
 use crate::error::ParseError;
 use crate::model::{Balance, Currency, Direction};
+use lazy_regex::lazy_regex;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::RwLock;
+
+/// IBAN в формате:
+/// (?i) - case-insensitive
+/// ^[A-Z]{2} - 2 буквы страны
+/// \d{2} - 2 цифры
+/// [A-Z0-9]{11,30} - хвост
+static IBAN_RE: Lazy<Regex> = lazy_regex!(r"(?i)^[A-Z]{2}\d{2}[A-Z0-9]{11,30}$");
+
+/// Таблица синонимов, которой пользуются все парсеры: встроенная плюс
+/// зарегистрированные через [`register_currency_synonym`]
+static CURRENCY_TABLE: Lazy<RwLock<HashMap<String, Currency>>> =
+    Lazy::new(|| RwLock::new(default_currency_table()));
+
+/// Встроенная таблица синонимов валют: ключи в нижнем регистре
+///
+/// Подходит как основа для своей таблицы в [`parse_currency_with`].
+pub fn default_currency_table() -> HashMap<String, Currency> {
+    let synonyms: [(&str, Currency); 13] = [
+        ("российский рубль", Currency::RUB),
+        ("рубль", Currency::RUB),
+        ("руб.", Currency::RUB),
+        ("rub", Currency::RUB),
+        ("rur", Currency::RUB),
+        ("американский доллар", Currency::USD),
+        ("доллар сша", Currency::USD),
+        ("usd", Currency::USD),
+        ("евро", Currency::EUR),
+        ("eur", Currency::EUR),
+        ("китайский юань", Currency::CNY),
+        ("юань", Currency::CNY),
+        ("cny", Currency::CNY),
+    ];
+
+    synonyms
+        .into_iter()
+        .map(|(name, currency)| (name.to_string(), currency))
+        .collect()
+}
 
-pub(crate) fn parse_currency(raw: &str) -> Currency {
+/// Разбирает валюту по переданной таблице синонимов
+///
+/// Поиск идёт без учёта регистра и пробелов по краям (ключи таблицы должны быть
+/// в нижнем регистре). Не найденное значение возвращается как [`Currency::Other`].
+pub fn parse_currency_with(raw: &str, table: &HashMap<String, Currency>) -> Currency {
     let s = raw.trim();
-    let lower = s.to_lowercase();
-
-    match lower.as_str() {
-        "российский рубль" | "рубль" | "руб." | "rub" | "rur" => {
-            Currency::RUB
-        }
-        "американский доллар" | "доллар сша" | "usd" => Currency::USD,
-        "евро" | "eur" => Currency::EUR,
-        "китайский юань" | "юань" | "cny" => Currency::CNY,
 
+    match table.get(&s.to_lowercase()) {
+        Some(currency) => currency.clone(),
         // Всё остальное - как есть:
-        _ => Currency::Other(s.to_string()),
+        None => Currency::Other(s.to_string()),
     }
 }
 
-pub(crate) fn parse_amount(raw: &str) -> Result<u64, ParseError> {
-    let mut cleaned = raw.trim().replace(' ', "");
+/// Добавляет синоним валюты в таблицу, которой пользуются все парсеры
+///
+/// Действует на весь процесс. Уже известный синоним перезаписывается.
+pub fn register_currency_synonym(synonym: &str, currency: Currency) {
+    let mut table = CURRENCY_TABLE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    table.insert(synonym.trim().to_lowercase(), currency);
+}
 
-    if raw.contains(',') {
-        if raw.contains('.') {
-            cleaned = cleaned.replace(',', "");
-        } else {
-            cleaned = cleaned.replace(',', ".");
-        }
+pub(crate) fn parse_currency(raw: &str) -> Currency {
+    let table = CURRENCY_TABLE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    parse_currency_with(raw, &table)
+}
+
+/// Валюты стран по двухбуквенному коду ISO 3166, см. [`currency_for_iban`]
+const IBAN_COUNTRY_CURRENCIES: &[(&str, &str)] = &[
+    ("AD", "EUR"),
+    ("AT", "EUR"),
+    ("BE", "EUR"),
+    ("BG", "BGN"),
+    ("BY", "BYN"),
+    ("CH", "CHF"),
+    ("CY", "EUR"),
+    ("CZ", "CZK"),
+    ("DE", "EUR"),
+    ("DK", "DKK"),
+    ("EE", "EUR"),
+    ("ES", "EUR"),
+    ("FI", "EUR"),
+    ("FR", "EUR"),
+    ("GB", "GBP"),
+    ("GR", "EUR"),
+    ("HR", "EUR"),
+    ("HU", "HUF"),
+    ("IE", "EUR"),
+    ("IS", "ISK"),
+    ("IT", "EUR"),
+    ("KZ", "KZT"),
+    ("LI", "CHF"),
+    ("LT", "EUR"),
+    ("LU", "EUR"),
+    ("LV", "EUR"),
+    ("MC", "EUR"),
+    ("MT", "EUR"),
+    ("NL", "EUR"),
+    ("NO", "NOK"),
+    ("PL", "PLN"),
+    ("PT", "EUR"),
+    ("RO", "RON"),
+    ("RS", "RSD"),
+    ("RU", "RUB"),
+    ("SE", "SEK"),
+    ("SI", "EUR"),
+    ("SK", "EUR"),
+    ("SM", "EUR"),
+    ("TR", "TRY"),
+    ("UA", "UAH"),
+    ("VA", "EUR"),
+];
+
+/// Вероятная валюта счёта по стране его IBAN
+///
+/// Это догадка на крайний случай: валюта счёта не обязана совпадать с
+/// валютой страны. `None`, если счёт не похож на IBAN или страна неизвестна.
+pub(crate) fn currency_for_iban(account: &str) -> Option<Currency> {
+    let compact: String = account.chars().filter(|c| !c.is_whitespace()).collect();
+    let iban = normalize_and_check_iban(&compact)?;
+
+    IBAN_COUNTRY_CURRENCIES
+        .iter()
+        .find(|(country, _)| iban.starts_with(country))
+        .map(|(_, code)| parse_currency(code))
+}
+
+/// Отрезает UTF-8 BOM (`EF BB BF`) в начале входных данных, если он есть
+///
+/// Excel и Блокнот в Windows любят его добавлять, а парсерам он мешает
+/// распознать первую ячейку или маркер блока.
+pub(crate) fn strip_utf8_bom(input: &[u8]) -> &[u8] {
+    input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input)
+}
+
+/// Политика округления сумм, у которых дробных знаков больше, чем в минорных единицах
+///
+/// Например, CAMT.053 допускает суммы вида `123.456`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Банковское округление: половина округляется к чётному (`0.125` -> `0.12`)
+    HalfEven,
+    /// Лишние знаки отбрасываются
+    Truncate,
+    /// Лишние знаки считаются ошибкой (поведение по умолчанию)
+    #[default]
+    Error,
+}
+
+/// Проверяет, что начало периода выписки не позже его конца
+///
+/// Перевёрнутый период (`from > until`) - признак испорченного файла: с `swap`
+/// границы меняются местами, иначе возвращается [`ParseError::BadInput`].
+pub(crate) fn ordered_period(
+    from: Date,
+    until: Date,
+    swap: bool,
+) -> Result<(Date, Date), ParseError> {
+    match (from > until, swap) {
+        (false, _) => Ok((from, until)),
+        (true, true) => Ok((until, from)),
+        (true, false) => Err(ParseError::BadInput(format!(
+            "statement period is inverted: {from} > {until}"
+        ))),
     }
+}
 
-    if cleaned.is_empty() {
-        return Err(ParseError::InvalidAmount("empty amount".into()));
-    }
-    if cleaned.starts_with('-') {
-        return Err(ParseError::InvalidAmount(format!(
-            "negative amount: {cleaned}"
-        )));
-    }
-
-    let mut split = cleaned.split('.');
-    // cleaned точно не пусто, так что ошибки здесь быть не может
-    let int_part = split
-        .next()
-        .expect("cleaned is verified to be non-empty so panic! must be impossible to happen");
-    let dec_part = split.next().unwrap_or("");
-    if split.next().is_some() {
-        // больше одной точки - странный формат
-        return Err(ParseError::InvalidAmount(format!(
-            "too many dots in amount: {cleaned}"
-        )));
-    }
-
-    let int_part: u64 = int_part.parse()?;
-
-    let dec_part: u64 = match dec_part.len() {
-        0 => 0,
-        1 => {
-            let d = dec_part
-                .chars()
-                .next()
-                .and_then(|c| c.to_digit(10))
-                .ok_or_else(|| {
-                    ParseError::InvalidAmount(format!("invalid fractional part: {cleaned}"))
-                })?;
-            d as u64 * 10
-        }
-        2 => dec_part.parse()?,
-        _ => {
-            return Err(ParseError::InvalidAmount(format!(
-                "too many fractional digits in amount: {cleaned}"
-            )));
-        }
-    };
+/// Что делать, если во входных данных больше одной выписки
+///
+/// Действует на `parse`/`parse_with_options` парсеров MT940 и CAMT.053, которые
+/// возвращают одну выписку. `parse_all` всегда читает все выписки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultipleStatementsPolicy {
+    /// Взять первую выписку, остальные отбросить с предупреждением
+    /// [`ParseWarning::MultipleStatements`](crate::ParseWarning::MultipleStatements)
+    /// (поведение по умолчанию)
+    #[default]
+    KeepFirst,
+    /// Вернуть ошибку [`ParseError::MultipleStatements`]
+    ErrorOut,
+    /// Не терять ни одной выписки: первая возвращается как обычно, остальные
+    /// лежат в поле `rest` ([`Mt940Data::rest`](crate::Mt940Data::rest),
+    /// [`Camt053Data::rest`](crate::Camt053Data::rest)), все вместе - `into_all`
+    KeepAll,
+}
 
-    Ok(int_part * 100 + dec_part)
+/// Знаков после запятой в минорных единицах: все суммы хранятся в сотых долях
+pub(crate) const MINOR_UNIT_DECIMALS: u32 = 2;
+
+pub(crate) fn parse_amount(raw: &str) -> Result<u64, ParseError> {
+    parse_amount_with(raw, RoundingMode::Error)
 }
 
-pub(crate) fn parse_signed_balance(raw: &str, direction: Direction) -> Result<Balance, ParseError> {
-    let minor = parse_amount(raw)? as i128;
+pub(crate) fn parse_amount_with(raw: &str, rounding: RoundingMode) -> Result<u64, ParseError> {
+    crate::money::parse_amount(raw, MINOR_UNIT_DECIMALS, rounding)
+}
+
+pub(crate) fn parse_signed_balance(
+    raw: &str,
+    direction: Direction,
+    rounding: RoundingMode,
+) -> Result<Balance, ParseError> {
+    let minor = parse_amount_with(raw, rounding)? as i128;
 
     let signed = match direction {
         Direction::Credit => minor,
@@ -87,12 +222,202 @@ pub(crate) fn parse_signed_balance(raw: &str, direction: Direction) -> Result<Ba
     Ok(signed)
 }
 
+/// Ошибки отдельных строк, пропущенных в мягком режиме: (индекс строки, ошибка)
+pub(crate) type RowErrors = Vec<(usize, ParseError)>;
+
+/// Собирает транзакции из результатов разбора отдельных строк
+///
+/// В строгом режиме первая ошибка прерывает разбор, в мягком - строка
+/// пропускается, а ошибка возвращается вместе с её индексом.
+/// Обычно `T` - это [`Transaction`](crate::model::Transaction), но строка может давать и несколько транзакций.
+pub(crate) fn collect_transactions<I, T>(
+    items: I,
+    lenient: bool,
+) -> Result<(Vec<T>, RowErrors), ParseError>
+where
+    I: IntoIterator<Item = Result<T, ParseError>>,
+{
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, item) in items.into_iter().enumerate() {
+        match item {
+            Ok(tx) => transactions.push(tx),
+            Err(e) if lenient => errors.push((idx, e)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok((transactions, errors))
+}
+
+/// Сколько соседних строк показывать в [`excerpt_around`] с каждой стороны
+const EXCERPT_CONTEXT: usize = 1;
+/// Длиннее строки отрывка обрезаются
+const EXCERPT_MAX_LEN: usize = 100;
+
+/// Отрывок входных данных вокруг строк `bad` для [`ParseError::AtLine`]
+///
+/// `lines` - пары (номер строки, текст) в порядке возрастания номеров.
+/// Ошибочные строки (их несколько у многострочной записи CSV) отмечаются `>`,
+/// слишком длинные строки обрезаются.
+pub(crate) fn excerpt_around<'a>(
+    lines: impl IntoIterator<Item = (usize, &'a str)>,
+    bad: RangeInclusive<usize>,
+) -> String {
+    let shown_range = bad.start().saturating_sub(EXCERPT_CONTEXT)..=bad.end() + EXCERPT_CONTEXT;
+    let shown: Vec<(usize, &str)> = lines
+        .into_iter()
+        .filter(|(number, _)| shown_range.contains(number))
+        .collect();
+    let width = shown
+        .iter()
+        .map(|(number, _)| number.to_string().len())
+        .max()
+        .unwrap_or(1);
+
+    shown
+        .into_iter()
+        .map(|(number, text)| {
+            let marker = if bad.contains(&number) { '>' } else { ' ' };
+            let text = text.trim_end();
+            let text: String = if text.chars().count() > EXCERPT_MAX_LEN {
+                text.chars().take(EXCERPT_MAX_LEN).chain(['…']).collect()
+            } else {
+                text.to_string()
+            };
+            format!("{marker} {number:>width$} | {text}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Обрезает пунктуацию по краям, переводит в верхний регистр и проверяет форму IBAN
+///
+/// Контрольная сумма не проверяется, см. [`iban_checksum_ok`].
+pub(crate) fn normalize_and_check_iban(token: &str) -> Option<String> {
+    let cleaned = token
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_uppercase();
+
+    if cleaned.is_empty() {
+        return None;
+    }
+
+    if IBAN_RE.is_match(&cleaned) {
+        Some(cleaned)
+    } else {
+        None
+    }
+}
+
+/// Проверка контрольных цифр IBAN по mod-97 (ISO 13616)
+///
+/// Ожидает уже нормализованный IBAN (только `A-Z0-9`).
+pub(crate) fn iban_checksum_ok(iban: &str) -> bool {
+    iban_mod97(iban) == Some(1)
+}
+
+/// Остаток от деления IBAN на 97 с переносом первых четырёх символов в конец
+fn iban_mod97(iban: &str) -> Option<u32> {
+    if iban.len() < 4 {
+        return None;
+    }
+    let (head, tail) = iban.split_at(4);
+
+    // Остаток считаем по ходу, чтобы не собирать огромное число
+    let mut remainder: u32 = 0;
+    for c in tail.chars().chain(head.chars()) {
+        let value = c.to_digit(36)?;
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    Some(remainder)
+}
+
+/// Подставной номер счёта с порядковым номером `n`, похожий по форме на исходный
+///
+/// IBAN остаётся IBAN той же страны и длины с верными контрольными цифрами,
+/// номер из одних цифр - номером той же длины, остальное становится `ACCOUNT{n}`.
+pub(crate) fn pseudonymous_account(account: &str, n: usize) -> String {
+    let compact: String = account.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if let Some(iban) = normalize_and_check_iban(&compact) {
+        let country = &iban[..2];
+        let width = iban.len() - 4;
+        let bban = format!("{n:0>width$}");
+        if let Some(remainder) = iban_mod97(&format!("{country}00{bban}")) {
+            return format!("{country}{:02}{bban}", 98 - remainder);
+        }
+    }
+
+    if !compact.is_empty() && compact.chars().all(|c| c.is_ascii_digit()) {
+        let width = compact.len();
+        return format!("{n:0>width$}");
+    }
+
+    format!("ACCOUNT{n}")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::ParseError;
+    use crate::model::Transaction;
     use crate::model::{Currency, Direction};
 
+    // ordered_period
+
+    #[test]
+    fn ordered_period_keeps_or_swaps_inverted_bounds() {
+        let early = crate::date::ymd(2024, 1, 1).unwrap();
+        let late = crate::date::ymd(2024, 1, 31).unwrap();
+
+        assert_eq!(ordered_period(early, late, false).unwrap(), (early, late));
+        assert_eq!(ordered_period(early, early, false).unwrap(), (early, early));
+        assert_eq!(ordered_period(late, early, true).unwrap(), (early, late));
+        assert!(matches!(
+            ordered_period(late, early, false),
+            Err(ParseError::BadInput(_))
+        ));
+    }
+
+    // currency_for_iban
+
+    #[test]
+    fn currency_for_iban_guesses_by_country() {
+        assert_eq!(
+            currency_for_iban("DE89 3704 0044 0532 0130 00"),
+            Some(Currency::EUR)
+        );
+        assert_eq!(
+            currency_for_iban("GB82WEST12345698765432"),
+            Some(Currency::Other("GBP".into()))
+        );
+        assert_eq!(currency_for_iban("40702810900000012345"), None);
+        assert_eq!(currency_for_iban("ZZ82WEST12345698765432"), None);
+    }
+
+    // pseudonymous_account
+
+    #[test]
+    fn pseudonymous_account_keeps_account_shape() {
+        let iban = pseudonymous_account("GB82 WEST 1234 5698 7654 32", 1);
+        assert_eq!(iban.len(), 22);
+        assert!(iban.starts_with("GB"));
+        assert!(iban_checksum_ok(&iban), "{iban}");
+        assert_ne!(iban, "GB82WEST12345698765432");
+
+        assert_eq!(
+            pseudonymous_account("40702810900000012345", 7),
+            "00000000000000000007"
+        );
+        assert_eq!(pseudonymous_account("ACC-42", 3), "ACCOUNT3");
+    }
+
     // parse_currency
 
     #[test]
@@ -123,6 +448,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_currency_with_uses_custom_table() {
+        let mut table = default_currency_table();
+        table.insert("фунт стерлингов".to_string(), Currency::Other("GBP".into()));
+
+        assert_eq!(
+            parse_currency_with(" Фунт стерлингов ", &table),
+            Currency::Other("GBP".into())
+        );
+        assert_eq!(parse_currency_with("евро", &table), Currency::EUR);
+        // встроенная таблица не изменилась
+        assert_eq!(
+            parse_currency_with("фунт стерлингов", &default_currency_table()),
+            Currency::Other("фунт стерлингов".into())
+        );
+    }
+
+    #[test]
+    fn registered_synonym_is_used_by_parse_currency() {
+        register_currency_synonym("Фунт стерлингов", Currency::Other("GBP".into()));
+
+        assert_eq!(
+            parse_currency("фунт стерлингов"),
+            Currency::Other("GBP".into())
+        );
+    }
+
     // parse_amount
 
     #[test]
@@ -140,6 +492,17 @@ mod tests {
         assert_eq!(parse_amount("1,23").unwrap(), 123);
     }
 
+    #[test]
+    fn parse_amount_without_integer_part() {
+        assert_eq!(parse_amount(",50").unwrap(), 50);
+        assert_eq!(parse_amount(".5").unwrap(), 50);
+        assert_eq!(parse_amount("0,50").unwrap(), 50);
+
+        // одного разделителя без цифр недостаточно
+        assert!(parse_amount(",").is_err());
+        assert!(parse_amount(".").is_err());
+    }
+
     #[test]
     fn parse_amount_with_spaces_and_thousand_separators() {
         // пробелы как разделитель тысяч
@@ -186,6 +549,44 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parse_amount_with_rounding_modes_on_three_fraction_digits() {
+        assert_eq!(
+            parse_amount_with("123.456", RoundingMode::HalfEven).unwrap(),
+            12_346
+        );
+        assert_eq!(
+            parse_amount_with("123.456", RoundingMode::Truncate).unwrap(),
+            12_345
+        );
+        assert!(matches!(
+            parse_amount_with("123.456", RoundingMode::Error),
+            Err(ParseError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn parse_amount_with_half_even_rounds_ties_to_even() {
+        assert_eq!(
+            parse_amount_with("0.125", RoundingMode::HalfEven).unwrap(),
+            12
+        );
+        assert_eq!(
+            parse_amount_with("0.135", RoundingMode::HalfEven).unwrap(),
+            14
+        );
+        // не ровно половина - округляем вверх
+        assert_eq!(
+            parse_amount_with("0.1251", RoundingMode::HalfEven).unwrap(),
+            13
+        );
+        // перенос в целую часть
+        assert_eq!(
+            parse_amount_with("0.995", RoundingMode::HalfEven).unwrap(),
+            100
+        );
+    }
+
     #[test]
     fn parse_amount_too_many_dots_is_error() {
         assert!(matches!(
@@ -203,20 +604,64 @@ mod tests {
 
     #[test]
     fn parse_signed_balance_credit_is_positive() {
-        let v = parse_signed_balance("1.23", Direction::Credit).unwrap();
+        let v = parse_signed_balance("1.23", Direction::Credit, RoundingMode::Error).unwrap();
         assert_eq!(v, 123i128);
     }
 
     #[test]
     fn parse_signed_balance_debit_is_negative() {
-        let v = parse_signed_balance("1.23", Direction::Debit).unwrap();
+        let v = parse_signed_balance("1.23", Direction::Debit, RoundingMode::Error).unwrap();
         assert_eq!(v, -123i128);
     }
 
     #[test]
     fn parse_signed_balance_propagates_parse_errors() {
         // отрицательное значение внутри должно упасть с InvalidAmount
-        let res = parse_signed_balance("-1.00", Direction::Credit);
+        let res = parse_signed_balance("-1.00", Direction::Credit, RoundingMode::Error);
         assert!(matches!(res, Err(ParseError::InvalidAmount(_))));
     }
+
+    // collect_transactions
+
+    fn sample_tx() -> Transaction {
+        Transaction::new(
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            None,
+            100,
+            Direction::Credit,
+            String::new(),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn collect_transactions_strict_stops_on_first_error() {
+        let items = vec![
+            Ok(sample_tx()),
+            Err(ParseError::InvalidAmount("bad".into())),
+        ];
+        let res = collect_transactions(items, false);
+        assert!(matches!(res, Err(ParseError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn collect_transactions_lenient_skips_and_reports_errors() {
+        let items = vec![
+            Ok(sample_tx()),
+            Err(ParseError::InvalidAmount("bad".into())),
+            Ok(sample_tx()),
+        ];
+        let (txs, errors) = collect_transactions(items, true).unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 1);
+    }
+
+    #[test]
+    fn iban_checksum_ok_checks_mod_97() {
+        assert!(iban_checksum_ok("GB82WEST12345698765432"));
+        assert!(!iban_checksum_ok("GB83WEST12345698765432"));
+        assert!(!iban_checksum_ok("GB8"));
+    }
 }
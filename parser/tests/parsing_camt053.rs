@@ -1,5 +1,8 @@
 use chrono::NaiveDate;
-use parser::{Camt053Data, Direction, Statement};
+use parser::{
+    Camt053Data, Camt053ParseOptions, Currency, Direction, MultipleStatementsPolicy, ParseError,
+    ParseWarning, Statement,
+};
 use std::{fs::File, io::BufReader, path::PathBuf};
 
 fn fixture_path() -> PathBuf {
@@ -80,3 +83,108 @@ fn camt053_danske_example_first_and_last_entries_look_ok() {
         NaiveDate::from_ymd_opt(2023, 5, 9).unwrap()
     );
 }
+
+#[test]
+fn camt053_danske_example_reads_counterparty_address() {
+    let stmt = parse_camt053_fixture();
+
+    let tx = stmt
+        .transactions
+        .iter()
+        .find(|tx| tx.counterparty_name.as_deref() == Some("Debtor"))
+        .expect("fixture must contain a payment from 'Debtor'");
+    let address = tx
+        .counterparty_address
+        .as_ref()
+        .expect("debtor has <PstlAdr>");
+
+    assert_eq!(address.country.as_deref(), Some("SE"));
+    assert_eq!(address.lines, ["First addressline"]);
+}
+
+fn two_statements_fixture() -> File {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("camt053")
+        .join("camt053_two_statements");
+    File::open(&path).unwrap_or_else(|e| panic!("failed to open CAMT053 fixture {path:?}: {e}"))
+}
+
+#[test]
+fn camt053_statements_to_vec_returns_every_stmt() {
+    let statements = Camt053Data::statements_to_vec(BufReader::new(two_statements_fixture()))
+        .expect("failed to parse multi-statement CAMT053");
+
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(statements[0].account_id, "DE89370400440532013000");
+    assert_eq!(statements[0].currency, Currency::EUR);
+    assert_eq!(statements[0].transactions.len(), 1);
+
+    assert_eq!(statements[1].account_id, "DE02120300000000202051");
+    assert_eq!(statements[1].currency, Currency::USD);
+    assert_eq!(statements[1].transactions.len(), 2);
+    assert_eq!(statements[1].closing_balance, Some(48_000));
+}
+
+#[test]
+fn camt053_parse_keeps_first_stmt_and_warns() {
+    let data = Camt053Data::parse(BufReader::new(two_statements_fixture()))
+        .expect("failed to parse multi-statement CAMT053");
+
+    assert_eq!(
+        data.warnings,
+        vec![ParseWarning::MultipleStatements { format: "camt053" }]
+    );
+
+    let all = Camt053Data::parse_all(BufReader::new(two_statements_fixture()))
+        .expect("failed to parse multi-statement CAMT053");
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().all(|data| data.warnings.is_empty()));
+
+    let stmt = Statement::try_from(data).expect("failed to convert first statement");
+    assert_eq!(stmt.account_id, "DE89370400440532013000");
+}
+
+#[test]
+fn camt053_on_multiple_policy_controls_multi_statement_documents() {
+    let parse = |on_multiple| {
+        let options = Camt053ParseOptions {
+            on_multiple,
+            ..Default::default()
+        };
+        Camt053Data::parse_with_options(BufReader::new(two_statements_fixture()), &options)
+    };
+
+    let first = parse(MultipleStatementsPolicy::KeepFirst).expect("keep first must succeed");
+    assert_eq!(
+        first.warnings,
+        vec![ParseWarning::MultipleStatements { format: "camt053" }]
+    );
+
+    let err = parse(MultipleStatementsPolicy::ErrorOut)
+        .expect_err("multi-statement document must be rejected");
+    assert!(
+        matches!(
+            err,
+            ParseError::MultipleStatements {
+                format: "camt053",
+                count: 2
+            }
+        ),
+        "{err:?}"
+    );
+
+    let kept = parse(MultipleStatementsPolicy::KeepAll).expect("keep all must succeed");
+    assert!(kept.warnings.is_empty());
+    assert_eq!(kept.rest.len(), 1);
+
+    let statements: Vec<Statement> = kept
+        .into_all()
+        .into_iter()
+        .map(|data| Statement::try_from(data).expect("failed to convert statement"))
+        .collect();
+    assert_eq!(statements.len(), 2);
+    assert_ne!(statements[0].account_id, statements[1].account_id);
+}
@@ -1,4 +1,7 @@
-use parser::{CsvData, Statement};
+use parser::{
+    CsvData, CsvLayoutConfig, CsvParseOptions, Currency, OperationType, ParseError, ParseWarning,
+    Statement, SubheaderRow,
+};
 use std::{fs::File, io::BufReader, path::PathBuf};
 
 fn fixture_path(rel: &str) -> PathBuf {
@@ -65,3 +68,421 @@ fn csv_example_transactions_within_statement_period() {
         );
     }
 }
+
+#[test]
+fn csv_amount_with_currency_suffix_parses_and_warns_on_conflict() {
+    let path = fixture_path("csv/example.csv");
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read CSV fixture {path:?}: {e}"));
+
+    // выписка в рублях, а у суммы приписан доллар
+    let patched = text.replacen(",1540.00,", ",1540.00 USD,", 1);
+    assert_ne!(patched, text, "fixture must contain amount 1540.00");
+
+    let csv_data = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    assert_eq!(csv_data.warnings.len(), 1, "{:?}", csv_data.warnings);
+    assert!(matches!(
+        &csv_data.warnings[0],
+        ParseWarning::AmountCurrencyMismatch {
+            currency: Currency::USD,
+            ..
+        }
+    ));
+
+    let stmt: Statement = csv_data.try_into().expect("failed to convert CsvData");
+    assert!(stmt.transactions.iter().any(|tx| tx.amount == 154_000));
+}
+
+#[test]
+fn csv_lenient_mode_skips_and_reports_malformed_row() {
+    let path = fixture_path("csv/example.csv");
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read CSV fixture {path:?}: {e}"));
+    let total = parse_csv_fixture().transactions.len();
+
+    // портим сумму в одной строке
+    let patched = text.replacen(",1540.00,", ",15x40.00,", 1);
+    assert_ne!(patched, text, "fixture must contain amount 1540.00");
+
+    let strict = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    assert!(Statement::try_from(strict).is_err());
+
+    let csv_data = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    let (stmt, errors) = csv_data
+        .try_into_statement_lenient()
+        .expect("lenient conversion must succeed");
+
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert_eq!(stmt.transactions.len(), total - 1);
+    assert!(stmt.transactions.iter().all(|tx| tx.amount != 154_000));
+}
+
+#[test]
+fn csv_turnovers_match_footer_when_verified() {
+    let file = File::open(fixture_path("csv/example.csv")).expect("failed to open CSV fixture");
+    let options = CsvParseOptions {
+        verify_turnovers: true,
+        ..Default::default()
+    };
+
+    let csv_data = CsvData::parse_with_options(BufReader::new(file), &options)
+        .expect("failed to parse CSV fixture");
+    Statement::try_from(csv_data).expect("fixture turnovers must match its rows");
+}
+
+#[test]
+fn csv_turnover_mismatch_is_reported() {
+    let path = fixture_path("csv/example.csv");
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read CSV fixture {path:?}: {e}"));
+
+    // как будто выгрузка оборвалась: в подвале оборот по кредиту больше, чем в строках
+    let patched = text.replacen(
+        ",Итого оборотов,,,,,,152357.50,,,,157000.00,",
+        ",Итого оборотов,,,,,,152357.50,,,,158000.00,",
+        1,
+    );
+    assert_ne!(patched, text, "fixture must contain the turnover row");
+
+    let options = CsvParseOptions {
+        verify_turnovers: true,
+        ..Default::default()
+    };
+    let csv_data = CsvData::parse_with_options(patched.as_bytes(), &options)
+        .expect("failed to parse patched CSV");
+
+    match Statement::try_from(csv_data) {
+        Err(ParseError::TurnoverMismatch {
+            footer_debit,
+            footer_credit,
+            rows_debit,
+            rows_credit,
+        }) => {
+            assert_eq!(footer_debit, rows_debit);
+            assert_eq!(footer_credit, 15_800_000);
+            assert_eq!(rows_credit, 15_700_000);
+        }
+        other => panic!("expected TurnoverMismatch, got {other:?}"),
+    }
+
+    // без опции сверка не выполняется
+    let csv_data = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    assert!(Statement::try_from(csv_data).is_ok());
+}
+
+#[test]
+fn csv_inverted_period_is_error_or_swapped() {
+    let path = fixture_path("csv/example.csv");
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read CSV fixture {path:?}: {e}"));
+
+    let patched = text.replacen(
+        "за период с 01 января 2024 г.,,,,,,,,,,,, по ,31 декабря 2024 г.",
+        "за период с 31 декабря 2024 г.,,,,,,,,,,,, по ,01 января 2024 г.",
+        1,
+    );
+    assert_ne!(patched, text, "fixture must contain the period row");
+
+    let csv_data = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    assert!(matches!(
+        Statement::try_from(csv_data),
+        Err(ParseError::BadInput(_))
+    ));
+
+    let options = CsvParseOptions {
+        swap_inverted_period: true,
+        ..Default::default()
+    };
+    let csv_data = CsvData::parse_with_options(patched.as_bytes(), &options)
+        .expect("failed to parse patched CSV");
+    let stmt = Statement::try_from(csv_data).expect("inverted period must be swapped");
+    assert_eq!(
+        stmt.period_from,
+        chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+    );
+    assert_eq!(
+        stmt.period_until,
+        chrono::NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+    );
+}
+
+#[test]
+fn csv_operation_codes_are_mapped() {
+    let stmt = parse_csv_fixture();
+
+    assert_eq!(
+        stmt.transactions[0].operation_code,
+        Some(OperationType::PaymentOrder)
+    );
+    assert!(
+        stmt.transactions
+            .iter()
+            .any(|tx| tx.operation_code == Some(OperationType::BankOrder))
+    );
+    assert!(
+        stmt.transactions
+            .iter()
+            .all(|tx| tx.operation_code.is_some())
+    );
+}
+
+/// Переписывает фикстуру с другим разделителем полей
+fn fixture_with_delimiter(delimiter: u8) -> Vec<u8> {
+    let path = fixture_path("csv/example.csv");
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&path)
+        .unwrap_or_else(|e| panic!("failed to open CSV fixture {path:?}: {e}"));
+
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_writer(Vec::new());
+    for record in rdr.records() {
+        wtr.write_record(&record.expect("failed to read fixture record"))
+            .expect("failed to write record");
+    }
+    wtr.into_inner().expect("failed to flush CSV writer")
+}
+
+/// Переписывает фикстуру с одной строкой заголовков таблицы: подписи
+/// "Дебет"/"Кредит" из строки подзаголовков переносятся в основную
+fn fixture_with_single_header_row() -> Vec<u8> {
+    let path = fixture_path("csv/example.csv");
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&path)
+        .unwrap_or_else(|e| panic!("failed to open CSV fixture {path:?}: {e}"));
+    let records: Vec<csv::StringRecord> = rdr
+        .records()
+        .map(|r| r.expect("failed to read fixture record"))
+        .collect();
+
+    let header_idx = records
+        .iter()
+        .position(|r| r.iter().any(|f| f.contains("Дата проводки")))
+        .expect("fixture must have a table header row");
+
+    let mut wtr = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(Vec::new());
+    for (i, record) in records.iter().enumerate() {
+        if i == header_idx + 1 {
+            continue;
+        }
+        if i == header_idx {
+            let subheaders = &records[i + 1];
+            let merged: Vec<&str> = record
+                .iter()
+                .enumerate()
+                .map(|(col, field)| match subheaders.get(col).map(str::trim) {
+                    Some(sub) if !sub.is_empty() => sub,
+                    _ => field,
+                })
+                .collect();
+            wtr.write_record(&merged).expect("failed to write record");
+        } else {
+            wtr.write_record(record).expect("failed to write record");
+        }
+    }
+    wtr.into_inner().expect("failed to flush CSV writer")
+}
+
+#[test]
+fn csv_with_single_header_row_parses() {
+    let expected = parse_csv_fixture();
+    let input = fixture_with_single_header_row();
+
+    for subheader_row in [SubheaderRow::Auto, SubheaderRow::Absent] {
+        let options = CsvParseOptions {
+            layout: CsvLayoutConfig {
+                subheader_row,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let stmt: Statement = CsvData::parse_with_options(input.as_slice(), &options)
+            .unwrap_or_else(|e| panic!("{subheader_row:?}: failed to parse: {e}"))
+            .try_into()
+            .expect("failed to convert CsvData into Statement");
+
+        assert_eq!(
+            stmt.transactions, expected.transactions,
+            "{subheader_row:?}"
+        );
+        assert_eq!(stmt.closing_balance, expected.closing_balance);
+    }
+
+    // обязательные подзаголовки: первая строка данных за них не сойдёт
+    let strict = CsvParseOptions {
+        layout: CsvLayoutConfig {
+            subheader_row: SubheaderRow::Present,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(matches!(
+        CsvData::parse_with_options(input.as_slice(), &strict),
+        Err(ParseError::Header(_))
+    ));
+}
+
+#[test]
+fn csv_delimiter_is_detected_for_semicolon_and_tab() {
+    let expected = parse_csv_fixture();
+
+    for delimiter in [b';', b'\t'] {
+        let input = fixture_with_delimiter(delimiter);
+
+        let csv_data = CsvData::parse(input.as_slice()).unwrap_or_else(|e| {
+            panic!("failed to parse {:?}-separated CSV: {e}", delimiter as char)
+        });
+        let stmt: Statement = csv_data
+            .try_into()
+            .expect("failed to convert CsvData into Statement");
+
+        assert_eq!(stmt.account_id, expected.account_id);
+        assert_eq!(stmt.opening_balance, expected.opening_balance);
+        assert_eq!(stmt.closing_balance, expected.closing_balance);
+        assert_eq!(stmt.transactions, expected.transactions);
+    }
+}
+
+#[test]
+fn csv_booking_dates_use_configured_formats() {
+    let expected = parse_csv_fixture();
+
+    // даты проводки DD.MM.YYYY -> YYYY-MM-DD, шапка и подвал не меняются
+    let text = std::fs::read_to_string(fixture_path("csv/example.csv"))
+        .expect("failed to read CSV fixture");
+    let date_re = regex::Regex::new(r"(?m)^,(\d{2})\.(\d{2})\.(\d{4}),,,").unwrap();
+    let iso = date_re.replace_all(&text, ",$3-$2-$1,,,");
+    assert_ne!(iso, text, "fixture must contain booking dates");
+
+    // формат по умолчанию не подходит
+    let csv_data = CsvData::parse(iso.as_bytes()).expect("failed to parse ISO-dated CSV");
+    let err = Statement::try_from(csv_data).unwrap_err();
+    assert!(matches!(err.root(), ParseError::Date(_)), "{err}");
+
+    let options = CsvParseOptions {
+        layout: CsvLayoutConfig {
+            date_formats: vec!["%d.%m.%Y".into(), "%Y-%m-%d".into()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let csv_data = CsvData::parse_with_options(iso.as_bytes(), &options)
+        .expect("failed to parse ISO-dated CSV");
+    let stmt: Statement = csv_data
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+
+    assert_eq!(stmt.period_from, expected.period_from);
+    assert_eq!(stmt.transactions, expected.transactions);
+}
+
+#[test]
+fn csv_data_try_from_str_matches_reader_parse() {
+    let expected = parse_csv_fixture();
+    let text = std::fs::read_to_string(fixture_path("csv/example.csv"))
+        .expect("failed to read CSV fixture");
+
+    let stmt: Statement = CsvData::try_from(text.as_str())
+        .expect("failed to parse CSV from &str")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+    assert_eq!(stmt.transactions, expected.transactions);
+
+    let stmt: Statement = CsvData::try_from(text)
+        .expect("failed to parse CSV from String")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+    assert_eq!(stmt.account_id, expected.account_id);
+}
+
+#[test]
+fn csv_record_error_points_to_bad_line() {
+    let text = std::fs::read_to_string(fixture_path("csv/example.csv"))
+        .expect("failed to read CSV fixture");
+    let patched = text.replacen(",1540.00,", ",15x0.00,", 1);
+    assert_ne!(patched, text, "fixture must contain amount 1540.00");
+
+    let csv_data = CsvData::parse(patched.as_bytes()).expect("failed to parse patched CSV");
+    let err = Statement::try_from(csv_data).unwrap_err();
+
+    // запись с ошибкой начинается строкой выше и занимает несколько строк
+    let bad_line = patched
+        .lines()
+        .position(|line| line.contains("15x0.00"))
+        .unwrap()
+        + 1;
+    let line = err.line().expect("record errors must carry a line number");
+    assert!(line < bad_line, "{err}");
+
+    let excerpt = err.excerpt().expect("record errors must carry an excerpt");
+    let marked: Vec<&str> = excerpt.lines().filter(|l| l.starts_with('>')).collect();
+    assert_eq!(marked.len(), bad_line - line + 1, "{excerpt}");
+    assert!(marked.iter().any(|l| l.contains("15x0.00")), "{excerpt}");
+}
+
+#[test]
+fn csv_our_account_override_restores_counterparties() {
+    let text = std::fs::read_to_string(fixture_path("csv/example.csv")).unwrap();
+    // в шапке счёт записан не так, как в таблице операций
+    let text = text.replacen("40702810440000030888", "30888/RUB", 1);
+
+    let stmt: Statement = CsvData::try_from(text.as_str())
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(stmt.transactions[0].counterparty, None);
+
+    let stmt = CsvData::try_from(text.as_str())
+        .unwrap()
+        .into_statement_with_account("40702810440000030888")
+        .unwrap();
+    assert_eq!(stmt.account_id, "30888/RUB");
+    assert_eq!(
+        stmt.transactions[0].counterparty.as_deref(),
+        Some("40702810600014448120")
+    );
+}
+
+#[test]
+fn csv_with_and_without_utf8_bom_parse_the_same() {
+    let parse = |input: &[u8]| -> Statement {
+        CsvData::parse(input)
+            .expect("failed to parse CSV")
+            .try_into()
+            .expect("failed to convert CsvData into Statement")
+    };
+
+    // BOM не должен попадать в первую ячейку и мешать определению разделителя
+    let without_bom = fixture_with_delimiter(b';');
+    assert!(!without_bom.starts_with(b"\xEF\xBB\xBF"));
+    let mut with_bom = b"\xEF\xBB\xBF".to_vec();
+    with_bom.extend_from_slice(&without_bom);
+
+    assert_eq!(parse(&with_bom), parse(&without_bom));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn csv_gz_fixture_parses_like_plain_csv() {
+    use parser::{DetectedFormat, detect_format, open_maybe_gzip};
+
+    let path = fixture_path("csv/example.csv.gz");
+    let file = File::open(&path).unwrap_or_else(|e| panic!("failed to open fixture {path:?}: {e}"));
+    let mut reader = open_maybe_gzip(BufReader::new(file)).expect("failed to open gzip fixture");
+
+    assert_eq!(detect_format(&mut reader), Some(DetectedFormat::Csv));
+
+    let stmt: Statement = CsvData::parse(reader)
+        .expect("failed to parse gzipped CSV fixture")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+
+    assert_eq!(stmt, parse_csv_fixture());
+}
@@ -100,3 +100,43 @@ fn mt940_example_parses_into_non_empty_statement() {
         "last transaction amount (11,25) should be parsed as 1125 minor units"
     );
 }
+
+#[test]
+fn mt940_with_cr_only_line_endings_parses_like_original() {
+    let original = parse_mt940_to_statement();
+
+    let path = fixture_path();
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read MT940 fixture {path:?}: {e}"));
+
+    // старый маковский формат: только \r между строками
+    let cr_only = text.replace("\r\n", "\n").replace('\n', "\r");
+    assert!(!cr_only.contains('\n'));
+
+    let stmt: Statement = Mt940Data::parse(cr_only.as_bytes())
+        .expect("failed to parse CR-only MT940")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement");
+
+    assert_eq!(stmt.account_id, original.account_id);
+    assert_eq!(stmt.opening_balance, original.opening_balance);
+    assert_eq!(stmt.closing_balance, original.closing_balance);
+    assert_eq!(stmt.transactions, original.transactions);
+}
+
+#[test]
+fn mt940_with_utf8_bom_parses_like_original() {
+    let original = parse_mt940_to_statement();
+
+    let path = fixture_path();
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read MT940 fixture {path:?}: {e}"));
+    let with_bom = format!("\u{feff}{}", text.trim_start_matches('\u{feff}'));
+
+    let stmt: Statement = Mt940Data::parse(with_bom.as_bytes())
+        .expect("failed to parse BOM-prefixed MT940")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement");
+
+    assert_eq!(stmt, original);
+}
@@ -1,4 +1,7 @@
-use parser::{Camt053Data, CsvData, Direction, Mt940Data, Statement};
+use parser::{
+    Camt053Data, CsvData, Direction, Format, Mt940Data, Mt940WriteOptions, ParseWarning,
+    SourceFormat, Statement, convert,
+};
 use std::{
     fs::File,
     io::{BufReader, Cursor},
@@ -141,3 +144,130 @@ fn camt_to_csv_to_mt940_roundtrip_preserves_core_data() {
         );
     }
 }
+
+#[test]
+fn camt_to_mt940_warns_about_dropped_fx_details() {
+    let original = parse_camt_to_statement();
+
+    let fx_tx_index = original
+        .transactions
+        .iter()
+        .position(|tx| tx.fx.is_some())
+        .expect("CAMT053 fixture should contain a transaction with FX details");
+
+    let mut mt940_buf: Vec<u8> = Vec::new();
+    let warnings = original
+        .write_mt940_with(&mut mt940_buf, &Mt940WriteOptions::default())
+        .expect("failed to write Statement as MT940");
+
+    assert!(
+        warnings.iter().any(|w| matches!(
+            w,
+            ParseWarning::DroppedFields { index, fields, .. }
+                if *index == fx_tx_index && fields.contains(&"FX details")
+        )),
+        "expected 'dropped FX' warning for transaction #{fx_tx_index}, got {warnings:?}"
+    );
+}
+
+#[test]
+fn convert_csv_fixture_to_mt940_in_one_call() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("csv")
+        .join("example.csv");
+    let file =
+        File::open(&path).unwrap_or_else(|e| panic!("failed to open CSV fixture {path:?}: {e}"));
+
+    let mut out: Vec<u8> = Vec::new();
+    convert(BufReader::new(file), Format::Csv, &mut out, Format::Mt940)
+        .expect("failed to convert CSV to MT940");
+
+    // результат совпадает с ручным разбором CSV
+    let file = File::open(&path).unwrap();
+    let expected: Statement = CsvData::parse(BufReader::new(file))
+        .expect("failed to parse CSV fixture")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+
+    let converted: Statement = Mt940Data::parse(Cursor::new(&out))
+        .expect("failed to parse converted MT940")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement");
+
+    assert_eq!(converted.account_id, expected.account_id);
+    assert_eq!(converted.opening_balance, expected.opening_balance);
+    assert_eq!(converted.closing_balance, expected.closing_balance);
+    assert_eq!(converted.transactions.len(), expected.transactions.len());
+}
+
+#[test]
+fn csv_to_mt940_to_csv_preserves_account_name() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("csv")
+        .join("example.csv");
+    let file =
+        File::open(&path).unwrap_or_else(|e| panic!("failed to open CSV fixture {path:?}: {e}"));
+    let original: Statement = CsvData::parse(BufReader::new(file))
+        .expect("failed to parse CSV fixture")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+    assert!(
+        original.account_name.is_some(),
+        "CSV fixture should carry an account name"
+    );
+
+    let options = Mt940WriteOptions {
+        write_account_name: true,
+        ..Default::default()
+    };
+    let mut mt940 = Vec::new();
+    original
+        .write_mt940_with(&mut mt940, &options)
+        .expect("failed to write MT940");
+
+    let via_mt940: Statement = Mt940Data::parse(Cursor::new(&mt940))
+        .expect("failed to parse written MT940")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement");
+    assert_eq!(via_mt940.account_name, original.account_name);
+
+    let csv = via_mt940.to_csv_string().expect("failed to write CSV");
+    let roundtrip: Statement = CsvData::try_from(csv.as_str())
+        .expect("failed to parse written CSV")
+        .try_into()
+        .expect("failed to convert CsvData into Statement");
+
+    assert_eq!(roundtrip.account_name, original.account_name);
+    assert_eq!(roundtrip.account_id, original.account_id);
+    assert_eq!(roundtrip.transactions.len(), original.transactions.len());
+}
+
+#[test]
+fn each_parser_stamps_source_format() {
+    let camt_stmt = parse_camt_to_statement();
+    assert_eq!(camt_stmt.source_format, Some(SourceFormat::Camt053));
+
+    let mut csv_buf: Vec<u8> = Vec::new();
+    camt_stmt
+        .write_csv(&mut csv_buf)
+        .expect("failed to write Statement as CSV");
+    let csv_stmt: Statement = CsvData::parse(Cursor::new(&csv_buf))
+        .expect("failed to parse intermediate CSV")
+        .try_into()
+        .expect("failed to convert intermediate CsvData into Statement");
+    assert_eq!(csv_stmt.source_format, Some(SourceFormat::Csv));
+
+    let mut mt940_buf: Vec<u8> = Vec::new();
+    csv_stmt
+        .write_mt940(&mut mt940_buf)
+        .expect("failed to write Statement as MT940");
+    let mt940_stmt: Statement = Mt940Data::parse(Cursor::new(&mt940_buf))
+        .expect("failed to parse intermediate MT940")
+        .try_into()
+        .expect("failed to convert intermediate Mt940Data into Statement");
+    assert_eq!(mt940_stmt.source_format, Some(SourceFormat::Mt940));
+}
@@ -1,4 +1,4 @@
-use parser::{Camt053Data, Direction, Statement};
+use parser::{Camt053Data, Camt053WriteOptions, Direction, PostalAddress, Statement};
 use std::{
     fs::File,
     io::{BufReader, Cursor},
@@ -136,3 +136,110 @@ fn camt053_roundtrip_via_statement_preserves_core_data() {
         );
     }
 }
+
+#[test]
+fn camt053_roundtrip_preserves_zero_and_absent_balances_with_always_emit() {
+    let mut original = parse_camt053_to_statement();
+    original.opening_balance = Some(0);
+    original.closing_balance = None;
+
+    let options = Camt053WriteOptions {
+        always_emit_balances: true,
+        ..Default::default()
+    };
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_camt053_with(&mut buf, &options)
+        .expect("failed to write Statement to CAMT053");
+
+    let roundtrip: Statement = Camt053Data::parse(Cursor::new(&buf))
+        .expect("failed to parse roundtripped CAMT053 XML")
+        .try_into()
+        .expect("failed to convert roundtripped Camt053Data into Statement");
+
+    // нулевой баланс сохраняется, отсутствующий записан как 0.00
+    assert_eq!(roundtrip.opening_balance, Some(0));
+    assert_eq!(roundtrip.closing_balance, Some(0));
+}
+
+#[test]
+fn camt053_roundtrip_preserves_xml_special_characters_in_description() {
+    let mut original = parse_camt053_to_statement();
+    original.transactions[0].description = "A & B <note>".to_string();
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_camt053(&mut buf)
+        .expect("failed to write Statement to CAMT053");
+
+    // экранировано ровно один раз
+    let xml = String::from_utf8(buf.clone()).expect("CAMT053 output must be UTF-8");
+    assert!(xml.contains("A &amp; B &lt;note&gt;"), "{xml}");
+    assert!(!xml.contains("&amp;amp;"), "{xml}");
+
+    let roundtrip: Statement = Camt053Data::parse(Cursor::new(&buf))
+        .expect("failed to parse roundtripped CAMT053 XML")
+        .try_into()
+        .expect("failed to convert roundtripped Camt053Data into Statement");
+
+    assert_eq!(roundtrip.transactions[0].description, "A & B <note>");
+}
+
+#[test]
+fn camt053_roundtrip_preserves_bank_reference() {
+    let mut original = parse_camt053_to_statement();
+    original.transactions[0].bank_reference = Some("2023010500042".to_string());
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_camt053(&mut buf)
+        .expect("failed to write Statement to CAMT053");
+
+    let xml = String::from_utf8(buf.clone()).expect("CAMT053 output must be UTF-8");
+    assert!(
+        xml.contains("<AcctSvcrRef>2023010500042</AcctSvcrRef>"),
+        "{xml}"
+    );
+
+    let roundtrip: Statement = Camt053Data::parse(Cursor::new(&buf))
+        .expect("failed to parse roundtripped CAMT053 XML")
+        .try_into()
+        .expect("failed to convert roundtripped Camt053Data into Statement");
+
+    assert_eq!(
+        roundtrip.transactions[0].bank_reference.as_deref(),
+        Some("2023010500042")
+    );
+}
+
+#[test]
+fn camt053_roundtrip_preserves_counterparty_address() {
+    let mut original = parse_camt053_to_statement();
+    let address = PostalAddress {
+        street: Some("Drottninggatan 1".to_string()),
+        postcode: Some("111 51".to_string()),
+        town: Some("Stockholm".to_string()),
+        country: Some("SE".to_string()),
+        lines: Vec::new(),
+    };
+    original.transactions[0].counterparty_address = Some(address.clone());
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_camt053(&mut buf)
+        .expect("failed to write Statement to CAMT053");
+
+    let xml = String::from_utf8(buf.clone()).expect("CAMT053 output must be UTF-8");
+    assert!(xml.contains("<TwnNm>Stockholm</TwnNm>"), "{xml}");
+    assert!(xml.contains("<Ctry>SE</Ctry>"), "{xml}");
+
+    let roundtrip: Statement = Camt053Data::parse(Cursor::new(&buf))
+        .expect("failed to parse roundtripped CAMT053 XML")
+        .try_into()
+        .expect("failed to convert roundtripped Camt053Data into Statement");
+
+    assert_eq!(
+        roundtrip.transactions[0].counterparty_address,
+        Some(address)
+    );
+}
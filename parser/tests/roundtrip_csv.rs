@@ -1,4 +1,4 @@
-use parser::{CsvData, Direction, Statement};
+use parser::{CsvData, CsvParseOptions, CsvWriteOptions, Direction, Statement};
 use std::{
     fs::File,
     io::{BufReader, Cursor},
@@ -134,6 +134,11 @@ fn csv_roundtrip_via_statement_preserves_core_data() {
             "counterparty mismatch at transaction #{i}"
         );
 
+        assert_eq!(
+            orig_tx.operation_code, rt_tx.operation_code,
+            "operation code mismatch at transaction #{i}"
+        );
+
         let norm_orig_cp_name = normalize_name(&orig_tx.counterparty_name);
         let norm_rt_cp_name = normalize_name(&rt_tx.counterparty_name);
 
@@ -143,3 +148,41 @@ fn csv_roundtrip_via_statement_preserves_core_data() {
         );
     }
 }
+
+#[test]
+fn csv_write_with_semicolon_and_bom_reparses_with_matching_reader() {
+    let original = parse_csv_to_statement();
+
+    let options = CsvWriteOptions {
+        delimiter: b';',
+        write_bom: true,
+        ..Default::default()
+    };
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_csv_with(&mut buf, &options)
+        .expect("failed to write Statement as CSV with options");
+
+    assert!(
+        buf.starts_with(b"\xEF\xBB\xBF"),
+        "CSV output must start with BOM"
+    );
+    let text = String::from_utf8(buf.clone()).expect("CSV output must be UTF-8");
+    assert!(text.contains(";Дата проводки;"), "CSV output must use ';'");
+
+    let parse_options = CsvParseOptions {
+        delimiter: Some(b';'),
+        ..Default::default()
+    };
+    let data = CsvData::parse_with_options(Cursor::new(&buf), &parse_options)
+        .expect("failed to re-parse CSV with ';' delimiter");
+    let roundtrip: Statement = data
+        .try_into()
+        .expect("failed to convert re-parsed CsvData into Statement");
+
+    assert_eq!(original.account_id, roundtrip.account_id);
+    assert_eq!(original.opening_balance, roundtrip.opening_balance);
+    assert_eq!(original.closing_balance, roundtrip.closing_balance);
+    assert_eq!(original.transactions.len(), roundtrip.transactions.len());
+}
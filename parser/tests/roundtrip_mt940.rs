@@ -1,4 +1,4 @@
-use parser::{Direction, Mt940Data, Statement};
+use parser::{Direction, Mt940Data, Mt940WriteOptions, Statement};
 use std::{
     fs::File,
     io::{BufReader, Cursor},
@@ -150,3 +150,147 @@ fn mt940_roundtrip_via_statement_preserves_core_data() {
         }
     }
 }
+
+#[test]
+fn mt940_roundtrip_preserves_bank_reference() {
+    let mut original = parse_mt940_to_statement();
+    original.transactions[0].bank_reference = Some("2023010500042".to_string());
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_mt940(&mut buf)
+        .expect("failed to write Statement to MT940");
+
+    let text = String::from_utf8(buf.clone()).expect("MT940 output must be UTF-8");
+    assert!(text.contains("//2023010500042"), "{text}");
+
+    let roundtrip: Statement = Mt940Data::parse(Cursor::new(&buf))
+        .expect("failed to parse roundtripped MT940 data")
+        .try_into()
+        .expect("failed to convert roundtripped Mt940Data into Statement");
+
+    for (orig_tx, rt_tx) in original.transactions.iter().zip(&roundtrip.transactions) {
+        assert_eq!(orig_tx.bank_reference, rt_tx.bank_reference);
+    }
+}
+
+#[test]
+fn mt940_write_without_options_emits_only_text_block() {
+    let original = parse_mt940_to_statement();
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_mt940(&mut buf)
+        .expect("failed to write MT940");
+
+    let text = String::from_utf8(buf).expect("MT940 output must be UTF-8");
+    assert!(text.starts_with("{4:"), "unexpected MT940 start: {text}");
+}
+
+#[test]
+fn mt940_write_with_bic_headers_roundtrips_bics() {
+    let original = parse_mt940_to_statement();
+
+    let options = Mt940WriteOptions {
+        sender_bic: Some("FOOBARBA".to_string()),
+        receiver_bic: Some("BAZQUXCC123".to_string()),
+        ..Default::default()
+    };
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_mt940_with(&mut buf, &options)
+        .expect("failed to write MT940 with headers");
+
+    let text = String::from_utf8(buf.clone()).expect("MT940 output must be UTF-8");
+    assert!(
+        text.starts_with("{1:F01FOOBARBAAXXX0000000000}{2:I940BAZQUXCCA123N}{4:"),
+        "unexpected MT940 headers: {text}"
+    );
+
+    let data = Mt940Data::parse(Cursor::new(&buf)).expect("failed to re-parse MT940");
+    assert_eq!(data.header.sender_bic.as_deref(), Some("FOOBARBA"));
+    assert_eq!(data.header.receiver_bic.as_deref(), Some("BAZQUXCC123"));
+
+    // тело сообщения не пострадало
+    let roundtrip: Statement = data.try_into().expect("failed to convert MT940");
+    assert_eq!(original.account_id, roundtrip.account_id);
+    assert_eq!(original.transactions.len(), roundtrip.transactions.len());
+}
+
+#[test]
+fn mt940_long_description_is_wrapped_to_swift_line_length() {
+    let mut stmt = parse_mt940_to_statement();
+    let long_description = "Оплата по договору поставки оборудования и материалов ".repeat(4);
+    stmt.transactions[0].description = long_description.trim().to_string();
+
+    let mut buf: Vec<u8> = Vec::new();
+    stmt.write_mt940(&mut buf)
+        .expect("failed to write Statement to MT940");
+    let text = String::from_utf8(buf.clone()).expect("MT940 output must be UTF-8");
+
+    // каждая физическая строка внутри :86: не длиннее 65 символов
+    let mut in_86 = false;
+    let mut lines_86 = 0;
+    for line in text.lines() {
+        if line.starts_with(":86:") {
+            in_86 = true;
+        } else if line.starts_with(':') || line.starts_with('-') {
+            in_86 = false;
+        }
+
+        if in_86 {
+            lines_86 += 1;
+            assert!(
+                line.chars().count() <= 65,
+                "line longer than 65 chars: {line:?}"
+            );
+        }
+    }
+    assert!(lines_86 > stmt.transactions.len(), "description must wrap");
+
+    // перенос не теряет текст описания
+    let roundtrip: Statement = Mt940Data::parse(Cursor::new(&buf))
+        .expect("failed to parse written MT940")
+        .try_into()
+        .expect("failed to convert Mt940Data into Statement");
+    let description = &roundtrip.transactions[0].description;
+    assert!(
+        description.contains(long_description.trim()),
+        "unexpected description: {description}"
+    );
+}
+
+#[test]
+fn mt940_write_with_swift_headers_parses_back() {
+    let original = parse_mt940_to_statement();
+
+    let options = Mt940WriteOptions {
+        sender_bic: Some("BAZQUXCC".to_string()),
+        swift_headers: true,
+        ..Default::default()
+    };
+
+    let mut buf: Vec<u8> = Vec::new();
+    original
+        .write_mt940_with(&mut buf, &options)
+        .expect("failed to write MT940 with SWIFT headers");
+
+    let text = String::from_utf8(buf.clone()).expect("MT940 output must be UTF-8");
+    let (headers, _) = text.split_once("{4:").expect("text block must be present");
+    assert!(
+        headers.starts_with("{1:F01XXXXXXXXAXXX0000000000}{2:O940"),
+        "{headers}"
+    );
+    assert!(headers.ends_with("{3:{108:SERIALIZED}}"), "{headers}");
+
+    let data = Mt940Data::parse(Cursor::new(&buf)).expect("failed to re-parse MT940");
+    assert_eq!(data.header.sender_bic.as_deref(), Some("BAZQUXCC"));
+    assert_eq!(data.header.receiver_bic.as_deref(), Some("XXXXXXXX"));
+
+    let roundtrip: Statement = data.try_into().expect("failed to convert MT940");
+    assert_eq!(original.account_id, roundtrip.account_id);
+    assert_eq!(original.opening_balance, roundtrip.opening_balance);
+    assert_eq!(original.closing_balance, roundtrip.closing_balance);
+    assert_eq!(original.transactions.len(), roundtrip.transactions.len());
+}
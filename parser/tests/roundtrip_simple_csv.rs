@@ -0,0 +1,112 @@
+use parser::{
+    Mt940Data, SimpleCsvData, SimpleCsvParseOptions, SimpleCsvWriteOptions, SourceFormat, Statement,
+};
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+fn fixture_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("mt940")
+        .join("example.mt940")
+}
+
+fn parse_mt940_to_statement() -> Statement {
+    let path = fixture_path();
+    let file =
+        File::open(&path).unwrap_or_else(|e| panic!("failed to open MT940 fixture {path:?}: {e}"));
+
+    let data = Mt940Data::parse(BufReader::new(file)).expect("failed to parse MT940 fixture");
+    data.try_into()
+        .expect("failed to convert Mt940Data into Statement")
+}
+
+fn assert_same_transactions(original: &Statement, roundtrip: &Statement) {
+    assert_eq!(
+        roundtrip.transactions.len(),
+        original.transactions.len(),
+        "transaction count changed after roundtrip"
+    );
+
+    for (i, (a, b)) in original
+        .transactions
+        .iter()
+        .zip(&roundtrip.transactions)
+        .enumerate()
+    {
+        assert_eq!(
+            a.booking_date, b.booking_date,
+            "booking_date mismatch at tx #{i}"
+        );
+        assert_eq!(a.value_date, b.value_date, "value_date mismatch at tx #{i}");
+        assert_eq!(a.amount, b.amount, "amount mismatch at tx #{i}");
+        assert_eq!(a.direction, b.direction, "direction mismatch at tx #{i}");
+        assert_eq!(
+            a.counterparty, b.counterparty,
+            "counterparty mismatch at tx #{i}"
+        );
+        assert_eq!(
+            a.counterparty_name, b.counterparty_name,
+            "counterparty_name mismatch at tx #{i}"
+        );
+        assert_eq!(
+            a.description.trim(),
+            b.description,
+            "description mismatch at tx #{i}"
+        );
+    }
+}
+
+#[test]
+fn simple_csv_roundtrip_preserves_transactions() {
+    let original = parse_mt940_to_statement();
+    assert!(
+        !original.transactions.is_empty(),
+        "fixture MT940 should contain at least one transaction"
+    );
+
+    let mut buf = Vec::new();
+    original
+        .write_simple_csv(&mut buf)
+        .expect("failed to write simple CSV");
+
+    let options = SimpleCsvParseOptions {
+        account_id: original.account_id.clone(),
+        currency: original.currency.clone(),
+        ..Default::default()
+    };
+    let data = SimpleCsvData::parse_with_options(buf.as_slice(), &options)
+        .expect("failed to parse written simple CSV");
+    let roundtrip = Statement::try_from(data).expect("failed to convert SimpleCsvData");
+
+    assert_eq!(roundtrip.account_id, original.account_id);
+    assert_eq!(roundtrip.currency, original.currency);
+    assert_eq!(roundtrip.source_format, Some(SourceFormat::SimpleCsv));
+    assert_eq!(roundtrip.opening_balance, None);
+    assert_eq!(roundtrip.net_amount(), original.net_amount());
+    assert_same_transactions(&original, &roundtrip);
+}
+
+#[test]
+fn simple_csv_roundtrip_with_signed_amounts_and_delimiter() {
+    let original = parse_mt940_to_statement();
+
+    let write_options = SimpleCsvWriteOptions {
+        delimiter: b';',
+        signed_amounts: true,
+    };
+    let mut buf = Vec::new();
+    original
+        .write_simple_csv_with(&mut buf, &write_options)
+        .expect("failed to write simple CSV");
+
+    let parse_options = SimpleCsvParseOptions {
+        delimiter: b';',
+        ..Default::default()
+    };
+    let data = SimpleCsvData::parse_with_options(buf.as_slice(), &parse_options)
+        .expect("failed to parse written simple CSV");
+    let roundtrip = Statement::try_from(data).expect("failed to convert SimpleCsvData");
+
+    assert_same_transactions(&original, &roundtrip);
+}